@@ -0,0 +1,7 @@
+-- Your SQL goes here
+CREATE TABLE e3_cache (
+    key TEXT NOT NULL,
+    response JSONB NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (key)
+);
@@ -0,0 +1,7 @@
+-- Your SQL goes here
+CREATE TABLE stored_reports (
+    key TEXT NOT NULL,
+    request JSONB NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (key)
+);
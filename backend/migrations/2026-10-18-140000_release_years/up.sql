@@ -0,0 +1,34 @@
+-- Your SQL goes here
+CREATE TABLE release_years (
+    release_year INTEGER NOT NULL,
+    publication_date DATE,
+    sources TEXT[] NOT NULL DEFAULT '{}',
+    status TEXT NOT NULL DEFAULT 'draft' CHECK (status IN ('draft', 'published', 'deprecated')),
+    is_default BOOLEAN NOT NULL DEFAULT FALSE,
+    PRIMARY KEY (release_year)
+);
+
+-- At most one release year is the default for new projects
+CREATE UNIQUE INDEX release_years_default ON release_years (is_default) WHERE is_default;
+
+-- Release years already loaded into every table have been in use, with the latest one as the default. Release years
+-- that are only partially loaded stay drafts until the rest of their data is imported.
+WITH complete AS (
+    SELECT release_year FROM discount_rates
+    INTERSECT SELECT release_year FROM energy_price_indices
+    INTERSECT SELECT release_year FROM energy_prices
+    INTERSECT SELECT release_year FROM escalation_rates
+    INTERSECT SELECT release_year FROM region_case_ba
+    INTERSECT SELECT release_year FROM region_case_oil
+    INTERSECT SELECT release_year FROM region_case_propane_lng
+    INTERSECT SELECT release_year FROM region_case_reeds
+    INTERSECT SELECT release_year FROM region_natgas
+    INTERSECT SELECT release_year FROM scc
+)
+INSERT INTO release_years (release_year, status, is_default)
+SELECT
+    release_year,
+    CASE WHEN release_year IN (SELECT release_year FROM complete) THEN 'published' ELSE 'draft' END,
+    release_year IS NOT DISTINCT FROM (SELECT MAX(release_year) FROM complete)
+FROM energy_prices
+GROUP BY release_year;
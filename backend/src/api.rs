@@ -1,21 +1,134 @@
-use std::env;
+use std::collections::{BTreeMap, BTreeSet};
+use std::time::SystemTime;
 
-use actix_web::web::{scope, Data, Json, ServiceConfig};
-use actix_web::{get, post, HttpResponse, Responder};
-use diesel::dsl::{max, min};
+use actix_web::web::{scope, Data, Json, Path, Query, ServiceConfig};
+use actix_web::http::header::{ContentDisposition, DispositionParam, DispositionType, CACHE_CONTROL, CONTENT_LOCATION};
+use actix_web::{get, post, HttpRequest, HttpResponse, Responder};
 use diesel::prelude::*;
 use diesel::{BoolExpressionMethods, ExpressionMethods, QueryDsl, RunQueryDsl, SelectableHelper};
 use serde::{Deserialize, Serialize};
 
+use crate::cache::{content_key, request_key};
+use crate::diff::diff;
+use crate::e3;
+use crate::e3::{AnalysisRequest, Output};
+use crate::lcc::analyze;
 use crate::models::*;
+use crate::{html, pdf};
+use crate::conversion::{coal_to_mj, to_emissions_unit, COAL_KG_CO2E_PER_MJ};
+use crate::project::{Case, DiscountingMethod, DollarMethod, FuelType, NumberOrArray, ProjectData, Unit};
+use crate::release_years::{coverage, USABLE_STATUSES};
+use crate::report::{build, Report, ReportKind};
 use crate::schema::escalation_rates::release_year;
+use crate::series::{first_year, year_series, Extrapolation, YearSeries, YearValue};
+use crate::validate::validate;
+use crate::xml_export::export;
+use crate::xml_import::import;
 use crate::AppData;
 
+const DEFAULT_RELEASE_YEAR: i32 = 2024;
+
 #[derive(Serialize)]
 pub struct ErrorResponse {
     pub error: String,
 }
 
+/// Responds with the value of every year from `from` to `to`. The rows start at [`first_year`], so the series can be
+/// extrapolated past its last year if the request asks for it.
+fn year_range<T: Into<Option<f64>>>(
+    rows: Vec<(i32, T)>,
+    from: i32,
+    to: i32,
+    mode: Option<Extrapolation>,
+) -> HttpResponse {
+    let rows: Vec<(i32, Option<f64>)> = rows.into_iter().map(|(year, value)| (year, value.into())).collect();
+
+    match year_series(&rows, from, to, mode) {
+        Ok(series) => HttpResponse::Ok().json(series),
+        Err(error) => HttpResponse::BadRequest().json(ErrorResponse { error }),
+    }
+}
+
+/// An escalation rate row, flagged if any of its rates were extrapolated rather than read from the data.
+#[derive(Serialize)]
+struct EscalationRateRow {
+    #[serde(flatten)]
+    rate: EscalationRate,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    extrapolated: bool,
+}
+
+/// Takes the escalation rates from `from` to `to` out of rows that start at [`first_year`]. Every division, sector and
+/// case is its own series, and each fuel of it is extrapolated past its last year the same way as any other year range
+/// if the request asks for it. Fuels without any rates are left empty.
+fn escalation_range(
+    rows: Vec<EscalationRate>,
+    from: i32,
+    to: i32,
+    mode: Option<Extrapolation>,
+) -> Result<Vec<EscalationRateRow>, String> {
+    let Some(mode) = mode else {
+        return Ok(rows
+            .into_iter()
+            .filter(|rate| (from..=to).contains(&rate.year))
+            .map(|rate| EscalationRateRow { rate, extrapolated: false })
+            .collect());
+    };
+
+    let mut groups: BTreeMap<(String, String, String), Vec<EscalationRate>> = BTreeMap::new();
+    for rate in rows {
+        groups.entry((rate.division.clone(), rate.sector.clone(), rate.case.clone())).or_default().push(rate);
+    }
+
+    let mut result = Vec::new();
+    for ((group_division, group_sector, group_case), group) in groups {
+        let mut fuels = Vec::new();
+        for fuel in EnergyTypeOptions::ALL {
+            let rates: Vec<(i32, Option<f64>)> = group.iter().map(|rate| (rate.year, fuel.escalation(rate))).collect();
+            let series = match rates.iter().any(|(_, rate)| rate.is_some()) {
+                true => year_series(&rates, from, to, Some(mode))?,
+                false => YearSeries::new(&rates, from, to),
+            };
+            fuels.push((fuel, series));
+        }
+
+        let template = &group[group.len() - 1];
+        for (index, row_year) in (from..=to).enumerate() {
+            let extrapolated = fuels.iter().any(|(_, series)| series.extrapolated.contains(&row_year));
+            if !extrapolated && !group.iter().any(|rate| rate.year == row_year) {
+                continue;
+            }
+
+            let mut rate = EscalationRate {
+                release_year: template.release_year,
+                year: row_year,
+                division: group_division.clone(),
+                sector: group_sector.clone(),
+                case: group_case.clone(),
+                region: template.region.clone(),
+                propane: None,
+                distillate_fuel_oil: None,
+                residual_fuel_oil: None,
+                natural_gas: None,
+                electricity: None,
+                coal: None,
+            };
+            for (fuel, series) in fuels.iter() {
+                fuel.set_escalation(&mut rate, series.values[index].value);
+            }
+
+            result.push(EscalationRateRow { rate, extrapolated });
+        }
+    }
+
+    result.sort_by(|a, b| {
+        let key = |row: &EscalationRateRow| (row.rate.year, row.rate.division.clone(), row.rate.sector.clone(), row.rate.case.clone());
+        key(a).cmp(&key(b))
+    });
+
+    Ok(result)
+}
+
 #[derive(Deserialize, Clone)]
 #[serde(deny_unknown_fields)]
 #[serde(rename_all = "camelCase")]
@@ -26,6 +139,7 @@ struct EscalationRateRequest {
     sector: Option<String>,
     release_year: i32,
     case: String,
+    extrapolate: Option<Extrapolation>,
 }
 
 #[post("/escalation_rates")]
@@ -62,7 +176,7 @@ fn division_escalation(
                     .inner_join(zip_info.on(state.eq(crate::schema::state_division_region::state)))
             )
             .filter(
-                year.between(from, to)
+                year.between(first_year(from, request.extrapolate), to)
                     .and(release_year.eq(request.release_year))
                     .and(zip.eq(zipcode))
                     .and(case.eq(request.case.clone()))
@@ -74,11 +188,13 @@ fn division_escalation(
     }
 
     let result = query
+        .order_by(year)
         .select(EscalationRate::as_select())
         .load(&mut db);
 
-    match result {
-        Ok(rates) => HttpResponse::Ok().json(rates),
+    match result.map(|rates| escalation_range(rates, from, to, request.extrapolate)) {
+        Ok(Ok(rates)) => HttpResponse::Ok().json(rates),
+        Ok(Err(error)) => HttpResponse::BadRequest().json(ErrorResponse { error }),
         Err(_) => HttpResponse::BadRequest().json(ErrorResponse {
             error: format!("Could not get escalation rates from {} to {}", from, to),
         }),
@@ -100,7 +216,7 @@ fn us_average_escalation(
         escalation_rates
             .into_boxed()
             .filter(
-                year.between(from, to)
+                year.between(first_year(from, request.extrapolate), to)
                     .and(release_year.eq(request.release_year))
                     .and(case.eq(request.case.clone()))
                     .and(division.eq("United States".to_string()))
@@ -112,17 +228,118 @@ fn us_average_escalation(
     }
 
     let result = query
+        .order_by(year)
         .select(EscalationRate::as_select())
         .load(&mut db);
 
-    match result {
-        Ok(rates) => HttpResponse::Ok().json(rates),
+    match result.map(|rates| escalation_range(rates, from, to, request.extrapolate)) {
+        Ok(Ok(rates)) => HttpResponse::Ok().json(rates),
+        Ok(Err(error)) => HttpResponse::BadRequest().json(ErrorResponse { error }),
         Err(_) => HttpResponse::BadRequest().json(ErrorResponse {
             error: format!("Could not get escalation rates from {} to {}", from, to),
         }),
     }
 }
 
+/// One entry of a batch request. Without a zipcode the United States average is used, and without a sector every
+/// sector is returned, the same as for `/escalation_rates`.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct EscalationRateBatchEntry {
+    zip: Option<i32>,
+    sector: Option<String>,
+    case: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+struct EscalationRateBatchRequest {
+    from: i32,
+    to: i32,
+    release_year: i32,
+    /// Entries by a key of the caller's choosing, which is used to key the response.
+    rates: BTreeMap<String, EscalationRateBatchEntry>,
+    extrapolate: Option<Extrapolation>,
+}
+
+#[post("/escalation_rates/batch")]
+async fn post_escalation_rates_batch(request: Json<EscalationRateBatchRequest>, data: Data<AppData>) -> impl Responder {
+    use crate::schema::escalation_rates::dsl::escalation_rates;
+    use crate::schema::escalation_rates::{division, sector, year, case};
+    use crate::schema::state_division_region::dsl::state_division_region;
+    use crate::schema::zip_info::dsl::zip_info;
+    use crate::schema::zip_info::{state, zip};
+
+    let mut db = data.pool.get().expect("Failed to get a connection");
+
+    let entries = request.rates.values();
+    let zips: BTreeSet<i32> = entries.clone().filter_map(|entry| entry.zip).collect();
+    let cases: BTreeSet<String> = entries.clone().map(|entry| entry.case.clone()).collect();
+    let sectors: Option<BTreeSet<String>> = entries.clone().map(|entry| entry.sector.clone()).collect();
+    let us_average = entries.clone().any(|entry| entry.zip.is_none());
+
+    // Division rates are joined to the requested zipcodes in their division, while the United States average has no
+    // states and so no zipcode
+    let mut query = escalation_rates
+        .left_join(state_division_region.on(crate::schema::state_division_region::division.eq(division)))
+        .left_join(zip_info.on(state.eq(crate::schema::state_division_region::state)))
+        .filter(
+            year.between(first_year(request.from, request.extrapolate), request.to)
+                .and(release_year.eq(request.release_year))
+                .and(case.eq_any(cases))
+        )
+        .into_boxed();
+
+    query = match us_average {
+        true => query.filter(zip.nullable().eq_any(zips).or(division.eq("United States"))),
+        false => query.filter(zip.nullable().eq_any(zips)),
+    };
+
+    // Entries without a sector need every sector
+    if let Some(sectors) = sectors {
+        query = query.filter(sector.eq_any(sectors));
+    }
+
+    let result: QueryResult<Vec<(Option<i32>, EscalationRate)>> = query
+        .order_by((year, division, sector, case))
+        .select((zip.nullable(), EscalationRate::as_select()))
+        .load(&mut db);
+
+    let rows = match result {
+        Ok(rows) => rows,
+        Err(_) => {
+            return HttpResponse::BadRequest().json(ErrorResponse {
+                error: format!("Could not get escalation rates from {} to {}", request.from, request.to),
+            })
+        }
+    };
+
+    let mut matched: BTreeMap<&String, Vec<EscalationRate>> =
+        request.rates.keys().map(|key| (key, Vec::new())).collect();
+    for (row_zip, rate) in &rows {
+        for (key, entry) in &request.rates {
+            let location = match entry.zip {
+                Some(entry_zip) => *row_zip == Some(entry_zip),
+                None => rate.division == "United States",
+            };
+
+            if location && entry.case == rate.case && entry.sector.as_ref().is_none_or(|entry_sector| *entry_sector == rate.sector) {
+                matched.entry(key).or_default().push(rate.clone());
+            }
+        }
+    }
+
+    let mut response: BTreeMap<&String, Vec<EscalationRateRow>> = BTreeMap::new();
+    for (key, rates) in matched {
+        match escalation_range(rates, request.from, request.to, request.extrapolate) {
+            Ok(rates) => response.insert(key, rates),
+            Err(error) => return HttpResponse::BadRequest().json(ErrorResponse { error: format!("{}: {}", key, error) }),
+        };
+    }
+
+    HttpResponse::Ok().json(response)
+}
+
 #[derive(Deserialize)]
 #[serde(rename_all = "camelCase", deny_unknown_fields)]
 struct RegionCaseBARequest {
@@ -132,6 +349,7 @@ struct RegionCaseBARequest {
     ba: String,
     case: String,
     rate: String,
+    extrapolate: Option<Extrapolation>,
 }
 
 #[post("/region_case_ba")]
@@ -144,19 +362,20 @@ async fn post_region_case_ba(
 
     let mut db = data.pool.get().expect("Failed to get a connection");
 
-    let query: QueryResult<Vec<f64>> = region_case_ba
+    let query: QueryResult<Vec<(i32, f64)>> = region_case_ba
         .filter(
             case.eq(request.case.clone())
                 .and(ba.eq(request.ba.clone()))
                 .and(release_year.eq(request.release_year))
                 .and(rate.eq(request.rate.clone()))
-                .and(year.between(request.from, request.to)),
+                .and(year.between(first_year(request.from, request.extrapolate), request.to)),
         )
-        .select(kg_co2_per_mwh)
+        .order_by(year)
+        .select((year, kg_co2_per_mwh))
         .load(&mut db);
 
     match query {
-        Ok(emissions) => HttpResponse::Ok().json(emissions),
+        Ok(rows) => year_range(rows, request.from, request.to, request.extrapolate),
         Err(_) => HttpResponse::BadRequest().json(ErrorResponse {
             error: "Could not get emissions information".into(),
         }),
@@ -172,6 +391,7 @@ struct RegionNatgasRequest {
     technobasin: String,
     case: String,
     rate: String,
+    extrapolate: Option<Extrapolation>,
 }
 
 #[post("/region_natgas")]
@@ -181,19 +401,20 @@ async fn post_region_natgas(request: Json<RegionNatgasRequest>, data: Data<AppDa
 
     let mut db = data.pool.get().expect("Failed to get a connection");
 
-    let query: QueryResult<Vec<f64>> = region_natgas
+    let query: QueryResult<Vec<(i32, f64)>> = region_natgas
         .filter(
             case.eq(request.case.clone())
                 .and(technobasin.eq(request.technobasin.clone()))
                 .and(release_year.eq(request.release_year))
                 .and(rate.eq(request.rate.clone()))
-                .and(year.between(request.from, request.to)),
+                .and(year.between(first_year(request.from, request.extrapolate), request.to)),
         )
-        .select(kg_co2_per_mj)
+        .order_by(year)
+        .select((year, kg_co2_per_mj))
         .load(&mut db);
 
     match query {
-        Ok(emissions) => HttpResponse::Ok().json(emissions),
+        Ok(rows) => year_range(rows, request.from, request.to, request.extrapolate),
         Err(_) => HttpResponse::BadRequest().json(ErrorResponse {
             error: "Could not get region natgas information".into(),
         }),
@@ -209,6 +430,7 @@ struct RegionCasePropaneLNGRequest {
     padd: String,
     case: String,
     rate: String,
+    extrapolate: Option<Extrapolation>,
 }
 
 #[post("/region_case_propane_lng")]
@@ -218,19 +440,20 @@ async fn post_region_case_propane_lng(request: Json<RegionCasePropaneLNGRequest>
 
     let mut db = data.pool.get().expect("Failed to get a connection");
 
-    let query: QueryResult<Vec<f64>> = region_case_propane_lng
+    let query: QueryResult<Vec<(i32, f64)>> = region_case_propane_lng
         .filter(
             case.eq(request.case.clone())
                 .and(padd.eq(request.padd.clone()))
                 .and(release_year.eq(request.release_year))
                 .and(rate.eq(request.rate.clone()))
-                .and(year.between(request.from, request.to)),
+                .and(year.between(first_year(request.from, request.extrapolate), request.to)),
         )
-        .select(kg_co2_per_mj)
+        .order_by(year)
+        .select((year, kg_co2_per_mj))
         .load(&mut db);
 
     match query {
-        Ok(emissions) => HttpResponse::Ok().json(emissions),
+        Ok(rows) => year_range(rows, request.from, request.to, request.extrapolate),
         Err(_) => HttpResponse::BadRequest().json(ErrorResponse {
             error: "Could not get region case propane lng information".into(),
         }),
@@ -246,6 +469,7 @@ struct RegionOilRequest {
     padd: String,
     case: String,
     rate: String,
+    extrapolate: Option<Extrapolation>,
 }
 
 #[post("/region_case_oil")]
@@ -255,19 +479,20 @@ async fn post_region_case_oil(request: Json<RegionOilRequest>, data: Data<AppDat
 
     let mut db = data.pool.get().expect("Failed to get a connection");
 
-    let query: QueryResult<Vec<f64>> = region_case_oil
+    let query: QueryResult<Vec<(i32, f64)>> = region_case_oil
         .filter(
             case.eq(request.case.clone())
                 .and(padd.eq(request.padd.clone()))
                 .and(release_year.eq(request.release_year))
                 .and(rate.eq(request.rate.clone()))
-                .and(year.between(request.from, request.to)),
+                .and(year.between(first_year(request.from, request.extrapolate), request.to)),
         )
-        .select(kg_co2_per_mj)
+        .order_by(year)
+        .select((year, kg_co2_per_mj))
         .load(&mut db);
 
     match query {
-        Ok(emissions) => HttpResponse::Ok().json(emissions),
+        Ok(rows) => year_range(rows, request.from, request.to, request.extrapolate),
         Err(_) => HttpResponse::BadRequest().json(ErrorResponse {
             error: "Could not get region case oil information".into(),
         }),
@@ -283,6 +508,7 @@ struct RegionCaseReedsRequest {
     reeds: String,
     case: String,
     rate: String,
+    extrapolate: Option<Extrapolation>,
 }
 
 #[post("/region_case_reeds")]
@@ -292,19 +518,20 @@ async fn post_region_case_reeds(request: Json<RegionCaseReedsRequest>, data: Dat
 
     let mut db = data.pool.get().expect("Failed to get a connection");
 
-    let query: QueryResult<Vec<f64>> = region_case_reeds
+    let query: QueryResult<Vec<(i32, f64)>> = region_case_reeds
         .filter(
             case.eq(request.case.clone())
                 .and(reeds.eq(request.reeds.clone()))
                 .and(release_year.eq(request.release_year))
                 .and(rate.eq(request.rate.clone()))
-                .and(year.between(request.from, request.to)),
+                .and(year.between(first_year(request.from, request.extrapolate), request.to)),
         )
-        .select(kg_co2_per_mwh)
+        .order_by(year)
+        .select((year, kg_co2_per_mwh))
         .load(&mut db);
 
     match query {
-        Ok(emissions) => HttpResponse::Ok().json(emissions),
+        Ok(rows) => year_range(rows, request.from, request.to, request.extrapolate),
         Err(_) => HttpResponse::BadRequest().json(ErrorResponse {
             error: "Could not get region case reeds information".into(),
         }),
@@ -348,6 +575,7 @@ struct EmissionsRequest {
     release_year: i32,
     case: String,
     rate: String,
+    extrapolate: Option<Extrapolation>,
 }
 
 #[post("/emissions")]
@@ -366,19 +594,20 @@ async fn post_emissions(request: Json<EmissionsRequest>, data: Data<AppData>) ->
             use crate::schema::region_case_ba::dsl::*;
             use crate::schema::region_case_ba::*;
 
-            let query: QueryResult<Vec<f64>> = region_case_ba
+            let query: QueryResult<Vec<(i32, f64)>> = region_case_ba
                 .filter(
                     case.eq(request.case.clone())
                         .and(ba.eq(info.ba.clone()))
                         .and(release_year.eq(request.release_year))
                         .and(rate.eq(request.rate.clone()))
-                        .and(year.between(request.from, request.to)),
+                        .and(year.between(first_year(request.from, request.extrapolate), request.to)),
                 )
-                .select(kg_co2_per_mwh)
+                .order_by(year)
+                .select((year, kg_co2_per_mwh))
                 .load(&mut db);
 
             match query {
-                Ok(emissions) => HttpResponse::Ok().json(emissions),
+                Ok(rows) => year_range(rows, request.from, request.to, request.extrapolate),
                 Err(_) => HttpResponse::BadRequest().json(ErrorResponse {
                     error: "Could not get requested emissions information".into(),
                 }),
@@ -390,6 +619,326 @@ async fn post_emissions(request: Json<EmissionsRequest>, data: Data<AppData>) ->
     }
 }
 
+/// Where electricity emissions rates come from. Every other fuel has a single source.
+#[derive(Deserialize, Serialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+enum EmissionsSource {
+    /// NIST NETL rates by balancing authority.
+    #[default]
+    Ba,
+    /// NREL Cambium rates by ReEDS balancing area.
+    Reeds,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+struct EmissionsV2Request {
+    zip: i32,
+    from: i32,
+    to: i32,
+    release_year: i32,
+    fuel_type: EnergyTypeOptions,
+    #[serde(default)]
+    source: EmissionsSource,
+    case: String,
+    /// The rate type, such as `avg` or `lrm`. Tables differ in capitalization, so it is matched case-insensitively.
+    rate: String,
+    extrapolate: Option<Extrapolation>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct EmissionsV2Response {
+    /// The BA, PADD, technobasin or ReEDS region of the zipcode.
+    region: String,
+    unit: &'static str,
+    #[serde(flatten)]
+    emissions: YearSeries,
+}
+
+/// Looks up the emissions rates of a fuel for the region of a zipcode: electricity by BA or ReEDS region, natural gas by
+/// technobasin, and oil and propane by PADD.
+fn zip_emissions(request: &EmissionsV2Request, db: &mut PgConnection) -> Result<EmissionsV2Response, HttpResponse> {
+    let error = |message: String| HttpResponse::BadRequest().json(ErrorResponse { error: message });
+
+    let info = {
+        use crate::schema::zip_info::dsl::*;
+
+        zip_info.filter(zip.eq(request.zip))
+            .select(ZipInfo::as_select())
+            .first(db)
+            .optional()
+            .map_err(|_| error("Could not get requested emissions zip information".into()))?
+            .ok_or_else(|| error(format!("Could not get region for zipcode {}", request.zip)))?
+    };
+
+    if request.source == EmissionsSource::Reeds && request.fuel_type != EnergyTypeOptions::Electricity {
+        return Err(error(format!("ReEDS emissions are only available for electricity, not {}", request.fuel_type.name())));
+    }
+
+    let (region_name, region, unit) = match (request.fuel_type, request.source) {
+        (EnergyTypeOptions::Electricity, EmissionsSource::Ba) => ("BA", Some(info.ba), "kg CO2/MWh"),
+        (EnergyTypeOptions::Electricity, EmissionsSource::Reeds) => ("ReEDS region", info.reeds_ba, "kg CO2/MWh"),
+        (EnergyTypeOptions::NaturalGas, _) => ("technobasin", Some(info.technobasin), "kg CO2/MJ"),
+        (EnergyTypeOptions::DistillateFuelOil | EnergyTypeOptions::ResidualFuelOil | EnergyTypeOptions::Propane, _) => {
+            ("PADD", Some(info.padd), "kg CO2/MJ")
+        }
+        // Coal has no regional data, every location uses the same factor as the frontend
+        (EnergyTypeOptions::Coal, _) => {
+            let rows: Vec<(i32, Option<f64>)> =
+                (request.from..=request.to).map(|row_year| (row_year, Some(COAL_KG_CO2E_PER_MJ))).collect();
+
+            return Ok(EmissionsV2Response {
+                region: "United States".to_string(),
+                unit: "kg CO2/MJ",
+                emissions: YearSeries::new(&rows, request.from, request.to),
+            });
+        }
+    };
+
+    // Some zipcodes have no region, which the source data marks with #N/A
+    let Some(region) = region.filter(|region| !region.is_empty() && region != "#N/A") else {
+        return Err(error(format!("No {} for zipcode {}", region_name, request.zip)));
+    };
+
+    let result: QueryResult<Vec<(i32, f64)>> = match (request.fuel_type, request.source) {
+        (EnergyTypeOptions::Electricity, EmissionsSource::Ba) => {
+            use crate::schema::region_case_ba::dsl::*;
+
+            region_case_ba
+                .filter(
+                    case.eq(&request.case)
+                        .and(ba.eq(&region))
+                        .and(release_year.eq(request.release_year))
+                        .and(rate.ilike(&request.rate))
+                        .and(year.between(first_year(request.from, request.extrapolate), request.to)),
+                )
+                .order_by(year)
+                .select((year, kg_co2_per_mwh))
+                .load(db)
+        }
+        (EnergyTypeOptions::Electricity, EmissionsSource::Reeds) => {
+            use crate::schema::region_case_reeds::dsl::*;
+
+            region_case_reeds
+                .filter(
+                    case.eq(&request.case)
+                        .and(reeds.eq(&region))
+                        .and(release_year.eq(request.release_year))
+                        .and(rate.ilike(&request.rate))
+                        .and(year.between(first_year(request.from, request.extrapolate), request.to)),
+                )
+                .order_by(year)
+                .select((year, kg_co2_per_mwh))
+                .load(db)
+        }
+        (EnergyTypeOptions::NaturalGas, _) => {
+            use crate::schema::region_natgas::dsl::*;
+
+            region_natgas
+                .filter(
+                    case.eq(&request.case)
+                        .and(technobasin.eq(&region))
+                        .and(release_year.eq(request.release_year))
+                        .and(rate.ilike(&request.rate))
+                        .and(year.between(first_year(request.from, request.extrapolate), request.to)),
+                )
+                .order_by(year)
+                .select((year, kg_co2_per_mj))
+                .load(db)
+        }
+        (EnergyTypeOptions::Propane, _) => {
+            use crate::schema::region_case_propane_lng::dsl::*;
+
+            region_case_propane_lng
+                .filter(
+                    case.eq(&request.case)
+                        .and(padd.eq(&region))
+                        .and(release_year.eq(request.release_year))
+                        .and(rate.ilike(&request.rate))
+                        .and(year.between(first_year(request.from, request.extrapolate), request.to)),
+                )
+                .order_by(year)
+                .select((year, kg_co2_per_mj))
+                .load(db)
+        }
+        _ => {
+            use crate::schema::region_case_oil::dsl::*;
+
+            region_case_oil
+                .filter(
+                    case.eq(&request.case)
+                        .and(padd.eq(&region))
+                        .and(release_year.eq(request.release_year))
+                        .and(rate.ilike(&request.rate))
+                        .and(year.between(first_year(request.from, request.extrapolate), request.to)),
+                )
+                .order_by(year)
+                .select((year, kg_co2_per_mj))
+                .load(db)
+        }
+    };
+
+    let rows = result.map_err(|_| error("Could not get requested emissions information".into()))?;
+
+    let rows: Vec<(i32, Option<f64>)> = rows.into_iter().map(|(row_year, value)| (row_year, Some(value))).collect();
+    let emissions = year_series(&rows, request.from, request.to, request.extrapolate).map_err(error)?;
+
+    Ok(EmissionsV2Response { region, unit, emissions })
+}
+
+#[post("/emissions/v2")]
+async fn post_emissions_v2(request: Json<EmissionsV2Request>, data: Data<AppData>) -> impl Responder {
+    let mut db = data.pool.get().expect("Failed to get a connection");
+
+    match zip_emissions(&request, &mut db) {
+        Ok(emissions) => HttpResponse::Ok().json(emissions),
+        Err(response) => response,
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+struct ConsumptionEmissionsRequest {
+    zip: i32,
+    from: i32,
+    to: i32,
+    release_year: i32,
+    fuel_type: EnergyTypeOptions,
+    #[serde(default)]
+    source: EmissionsSource,
+    case: String,
+    rate: String,
+    /// The consumption of every year.
+    annual_consumption: f64,
+    unit: ConsumptionUnit,
+    scc_option: SccOption,
+    extrapolate: Option<Extrapolation>,
+}
+
+/// The unit of a consumption, any project unit or MWh which projects do not offer.
+#[derive(Deserialize, Clone, Copy)]
+enum ConsumptionUnit {
+    #[serde(rename = "MWh")]
+    Mwh,
+    #[serde(untagged)]
+    Project(Unit),
+}
+
+impl ConsumptionUnit {
+    /// The consumption in a project unit, with MWh given as kWh.
+    fn to_project(self, amount: f64) -> (Unit, f64) {
+        match self {
+            ConsumptionUnit::Mwh => (Unit::Kwh, amount * 1000.0),
+            ConsumptionUnit::Project(unit) => (unit, amount),
+        }
+    }
+
+    fn label(&self) -> &'static str {
+        match self {
+            ConsumptionUnit::Mwh => "MWh",
+            ConsumptionUnit::Project(unit) => unit.label(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ConsumptionEmissionsResponse {
+    region: String,
+    /// The annual consumption converted to the unit of the emissions rates, MWh for electricity and MJ otherwise.
+    converted_consumption: f64,
+    converted_unit: &'static str,
+    /// The kg CO2 emitted each year, or `null` for years without emissions rates.
+    emissions: Vec<YearValue>,
+    /// The total emissions, or `null` if any year has no emissions rate.
+    total_emissions: Option<f64>,
+    /// The social cost of the emissions each year, or `null` for years without emissions rates or SCC data.
+    social_cost: Vec<YearValue>,
+    /// The total social cost, or `null` if any year has no social cost.
+    total_social_cost: Option<f64>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    extrapolated: Vec<i32>,
+}
+
+/// Converts a consumption into the unit of the emissions factors. Unlike projects, whose coal emissions follow the
+/// energy units the frontend sends to E3, a consumption of coal may also be given by weight.
+fn consumption_to_emissions_unit(fuel_type: FuelType, unit: Unit, amount: f64) -> Option<f64> {
+    to_emissions_unit(fuel_type, unit, amount)
+        .or_else(|| if fuel_type == FuelType::Coal { coal_to_mj(amount, unit) } else { None })
+}
+
+#[post("/emissions/consumption")]
+async fn post_emissions_consumption(request: Json<ConsumptionEmissionsRequest>, data: Data<AppData>) -> impl Responder {
+    let mut db = data.pool.get().expect("Failed to get a connection");
+
+    let (unit, amount) = request.unit.to_project(request.annual_consumption);
+    let Some(converted) = consumption_to_emissions_unit(request.fuel_type.fuel_type(), unit, amount) else {
+        return HttpResponse::BadRequest().json(ErrorResponse {
+            error: format!("Cannot convert {} of {} to an emissions unit", request.unit.label(), request.fuel_type.name()),
+        });
+    };
+
+    let location = EmissionsV2Request {
+        zip: request.zip,
+        from: request.from,
+        to: request.to,
+        release_year: request.release_year,
+        fuel_type: request.fuel_type,
+        source: request.source,
+        case: request.case.clone(),
+        rate: request.rate.clone(),
+        extrapolate: request.extrapolate,
+    };
+    let rates = match zip_emissions(&location, &mut db) {
+        Ok(rates) => rates,
+        Err(response) => return response,
+    };
+
+    let scc_rows = {
+        use crate::schema::scc::dsl::*;
+
+        scc.filter(release_year.eq(request.release_year).and(year.between(request.from, request.to)))
+            .select(Scc::as_select())
+            .load(&mut db)
+    };
+    let scc_by_year: BTreeMap<i32, f64> = match scc_rows {
+        Ok(rows) => rows.iter().map(|row| (row.year, request.scc_option.value(row))).collect(),
+        Err(_) => return HttpResponse::BadRequest().json(ErrorResponse {
+            error: "Could not get scc".to_string(),
+        }),
+    };
+
+    let emissions: Vec<YearValue> = rates
+        .emissions
+        .values
+        .iter()
+        .map(|rate| YearValue { year: rate.year, value: rate.value.map(|rate| rate * converted) })
+        .collect();
+    // SCC is in dollars per metric ton of CO2
+    let social_cost: Vec<YearValue> = emissions
+        .iter()
+        .map(|kg| YearValue {
+            year: kg.year,
+            value: kg.value.zip(scc_by_year.get(&kg.year)).map(|(kg, cost)| kg / 1000.0 * cost),
+        })
+        .collect();
+
+    HttpResponse::Ok().json(ConsumptionEmissionsResponse {
+        region: rates.region,
+        converted_consumption: converted,
+        converted_unit: match request.fuel_type {
+            EnergyTypeOptions::Electricity => "MWh",
+            _ => "MJ",
+        },
+        total_emissions: emissions.iter().map(|kg| kg.value).sum(),
+        emissions,
+        total_social_cost: social_cost.iter().map(|cost| cost.value).sum(),
+        social_cost,
+        extrapolated: rates.emissions.extrapolated,
+    })
+}
+
 #[derive(Deserialize)]
 #[serde(deny_unknown_fields)]
 struct ReleaseYearRequest {
@@ -398,56 +947,112 @@ struct ReleaseYearRequest {
 
 #[post("/release_year")]
 async fn post_check_release_year_exists(request: Json<ReleaseYearRequest>, data: Data<AppData>) -> impl Responder {
-    let mut db = data.pool.get().expect("Failed to get a connection");
+    use crate::schema::release_years::dsl::{release_years, status};
 
-    use crate::schema::energy_prices::dsl::*;
-    use crate::schema::energy_prices::*;
+    let mut db = data.pool.get().expect("Failed to get a connection");
 
-    let query: QueryResult<i32> = energy_prices 
-        .filter(release_year.eq(request.year))
-        .select(release_year)
-        .first(&mut db);
+    // Only published release years exist as far as projects are concerned, they are always fully loaded
+    let query: QueryResult<i64> = release_years
+        .find(request.year)
+        .filter(status.eq_any(USABLE_STATUSES))
+        .count()
+        .get_result(&mut db);
 
-    match query {
-        Ok(_) => HttpResponse::Ok().json(true),
-        Err(_) => HttpResponse::Ok().json(false),
-    }
+    HttpResponse::Ok().json(query.is_ok_and(|count| count > 0))
 }
 
 #[derive(Serialize)]
-struct ReleaseYearResponse {
+pub struct ReleaseYearResponse {
     pub year: i32,
     pub max: Option<i32>,
     pub min: Option<i32>,
+    /// Whether new projects should use this release year.
+    pub default: bool,
 }
 
-type RegionCaseResult = QueryResult<Vec<(i32, Option<i32>, Option<i32>)>>;
+/// Lists the release years offered for new projects with the years their energy prices cover, in ascending order.
+pub fn offered_release_years(db: &mut PgConnection) -> QueryResult<Vec<ReleaseYearResponse>> {
+    let published: Vec<ReleaseYearInfo> = {
+        use crate::schema::release_years::dsl::*;
+
+        release_years
+            .filter(status.eq("published"))
+            .order_by(release_year)
+            .select(ReleaseYearInfo::as_select())
+            .load(db)?
+    };
+
+    let ranges: Vec<(i32, Option<i32>, Option<i32>)> = {
+        use crate::schema::energy_prices::dsl::*;
+
+        energy_prices
+            .filter(release_year.eq_any(published.iter().map(|info| info.release_year)))
+            .group_by(release_year)
+            .select((release_year, diesel::dsl::max(year), diesel::dsl::min(year)))
+            .load(db)?
+    };
+
+    Ok(published
+        .into_iter()
+        .map(|info| {
+            let range = ranges.iter().find(|(year, _, _)| *year == info.release_year);
+
+            ReleaseYearResponse {
+                year: info.release_year,
+                max: range.and_then(|(_, max, _)| *max),
+                min: range.and_then(|(_, _, min)| *min),
+                default: info.is_default,
+            }
+        })
+        .collect())
+}
 
 #[get("/release_year")]
 async fn get_release_years(data: Data<AppData>) -> impl Responder {
     let mut db = data.pool.get().expect("Failed to get a connection");
 
-    use crate::schema::energy_prices::dsl::*;
-    use crate::schema::energy_prices::*;
+    match offered_release_years(&mut db) {
+        Ok(years) => HttpResponse::Ok().json(years),
+        Err(err) => HttpResponse::BadRequest().json(ErrorResponse {
+            error: format!("Could not get release years {}", err),
+        }),
+    }
+}
 
-    let query: RegionCaseResult = energy_prices
-        .group_by(release_year)
-        .select((release_year, max(year), min(year)))
-        .load(&mut db);
+/// Lists every release year with its metadata and the rows loaded into each table, including drafts and partially
+/// loaded release years.
+#[get("/release_year/coverage")]
+async fn get_release_year_coverage(data: Data<AppData>) -> impl Responder {
+    let mut db = data.pool.get().expect("Failed to get a connection");
 
-    match query {
-        Ok(years) => HttpResponse::Ok().json(
-            years.iter()
-                .map(|t| ReleaseYearResponse { year: t.0, max: t.1, min: t.2 })
-                .collect::<Vec<ReleaseYearResponse>>()
-        ),
+    match coverage(&mut db) {
+        Ok(years) => HttpResponse::Ok().json(years),
         Err(err) => HttpResponse::BadRequest().json(ErrorResponse {
-            error: format!("Could not get release years {}", err),
+            error: format!("Could not get release year coverage {}", err),
         }),
     }
 }
 
 #[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ReleaseYearDiffRequest {
+    from: i32,
+    to: i32,
+}
+
+#[get("/release_year/diff")]
+async fn get_release_year_diff(request: Query<ReleaseYearDiffRequest>, data: Data<AppData>) -> impl Responder {
+    let mut db = data.pool.get().expect("Failed to get a connection");
+
+    match diff(request.from, request.to, &mut db) {
+        Ok(diff) => HttpResponse::Ok().json(diff),
+        Err(err) => HttpResponse::BadRequest().json(ErrorResponse {
+            error: format!("Could not compare release years {}", err),
+        }),
+    }
+}
+
+#[derive(Deserialize, Serialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 #[serde(rename_all = "camelCase", deny_unknown_fields)]
 enum SccOption {
     ThreePercentNinetyFifthPercentile,
@@ -455,6 +1060,22 @@ enum SccOption {
     ThreePercentAverage,
 }
 
+impl SccOption {
+    const ALL: [SccOption; 3] = [
+        SccOption::ThreePercentNinetyFifthPercentile,
+        SccOption::FivePercentAverage,
+        SccOption::ThreePercentAverage,
+    ];
+
+    fn value(&self, row: &Scc) -> f64 {
+        match self {
+            SccOption::ThreePercentNinetyFifthPercentile => row.three_percent_ninety_fifth_percentile,
+            SccOption::FivePercentAverage => row.five_percent_average,
+            SccOption::ThreePercentAverage => row.three_percent_average,
+        }
+    }
+}
+
 #[derive(Deserialize)]
 #[serde(rename_all = "camelCase", deny_unknown_fields)]
 struct SccRequest {
@@ -462,6 +1083,7 @@ struct SccRequest {
     to: i32,
     release_year: i32,
     option: SccOption,
+    extrapolate: Option<Extrapolation>,
 }
 
 #[post("/scc")]
@@ -471,50 +1093,182 @@ async fn post_scc(request: Json<SccRequest>, data: Data<AppData>) -> impl Respon
     use crate::schema::scc::dsl::*;
     use crate::schema::scc::*;
 
-    let query: QueryResult<Vec<f64>> = match request.option {
+    let query: QueryResult<Vec<(i32, f64)>> = match request.option {
         SccOption::ThreePercentNinetyFifthPercentile => {
             scc
                 .filter(
                     release_year.eq(request.release_year)
-                        .and(year.between(request.from, request.to))
+                        .and(year.between(first_year(request.from, request.extrapolate), request.to))
                 )
-                .select(three_percent_ninety_fifth_percentile)
+                .order_by(year)
+                .select((year, three_percent_ninety_fifth_percentile))
                 .load(&mut db)
         }
         SccOption::FivePercentAverage => {
             scc
                 .filter(
                     release_year.eq(request.release_year)
-                        .and(year.between(request.from, request.to))
+                        .and(year.between(first_year(request.from, request.extrapolate), request.to))
                 )
-                .select(five_percent_average)
+                .order_by(year)
+                .select((year, five_percent_average))
                 .load(&mut db)
         }
         SccOption::ThreePercentAverage => {
             scc
                 .filter(
                     release_year.eq(request.release_year)
-                        .and(year.between(request.from, request.to))
+                        .and(year.between(first_year(request.from, request.extrapolate), request.to))
                 )
-                .select(three_percent_average)
+                .order_by(year)
+                .select((year, three_percent_average))
                 .load(&mut db)
         }
     };
 
     match query {
-        Ok(values) => HttpResponse::Ok().json(values),
+        Ok(rows) => year_range(rows, request.from, request.to, request.extrapolate),
         Err(_) => HttpResponse::BadRequest().json(ErrorResponse {
             error: "Could not get scc".to_string(),
         }),
     }
 }
 
-#[get("/states")]
-async fn get_states(data: Data<AppData>) -> impl Responder {
-    let mut db = data.pool.get().expect("Failed to get a connection");
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+struct SccMonetizeRequest {
+    release_year: i32,
+    /// The discount rate to use from `discount_rates`, such as `DOE` or `OMB`.
+    rate: String,
+    /// The year that damages are discounted to. Defaults to the first emissions year.
+    base_year: Option<i32>,
+    /// The year of the first emissions value.
+    start: i32,
+    /// The kg CO2 emitted each year.
+    emissions: Vec<f64>,
+}
 
-    use crate::schema::state_division_region::dsl::*;
-    use crate::schema::state_division_region::*;
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SccMonetization {
+    /// The damages of each year, or `null` for years without SCC data.
+    damages: Vec<YearValue>,
+    /// The present value of the damages, or `null` if any year has no SCC data.
+    present_value: Option<f64>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SccMonetizeResponse {
+    /// The real discount rate the damages were discounted with.
+    discount_rate: f64,
+    options: BTreeMap<SccOption, SccMonetization>,
+}
+
+/// The years of the emissions and the year damages are discounted to. The base year may come before the first
+/// emissions year, such as the start of a construction period, but not after the last one.
+fn monetize_years(start: i32, count: usize, base_year: Option<i32>) -> Result<(Vec<i32>, i32), String> {
+    if count == 0 {
+        return Err("No emissions to monetize".to_string());
+    }
+
+    let last = i32::try_from(count - 1)
+        .ok()
+        .and_then(|offset| start.checked_add(offset))
+        .ok_or_else(|| format!("Emissions starting in {} end after the last supported year", start))?;
+    let base_year = base_year.unwrap_or(start);
+    if base_year > last {
+        return Err(format!("The base year {} is after the last emissions year {}", base_year, last));
+    }
+    if last.checked_sub(base_year).and_then(|length| length.checked_add(1)).is_none() {
+        return Err(format!("The base year {} is too far before the emissions", base_year));
+    }
+
+    Ok(((start..=last).collect(), base_year))
+}
+
+/// Monetizes the emissions of each year with every SCC option, discounting the damages from the end of their year to
+/// the base year.
+///
+/// Each option names the discount rate its social cost was estimated with, which is already part of the SCC values.
+/// The damages themselves are discounted with the single real rate of the analysis, the same as every other cost, so
+/// the present values of all options can be added to a life cycle cost.
+fn monetize(
+    years: &[i32],
+    emissions: &[f64],
+    base_year: i32,
+    discount_rate: f64,
+    scc_by_year: &BTreeMap<i32, Scc>,
+) -> BTreeMap<SccOption, SccMonetization> {
+    SccOption::ALL
+        .into_iter()
+        .map(|option| {
+            // SCC is in dollars per metric ton of CO2
+            let damages: Vec<YearValue> = years
+                .iter()
+                .zip(emissions)
+                .map(|(year, kg)| YearValue {
+                    year: *year,
+                    value: scc_by_year.get(year).map(|row| kg / 1000.0 * option.value(row)),
+                })
+                .collect();
+            let present_value = damages
+                .iter()
+                .map(|damage| damage.value.map(|value| value / (1.0 + discount_rate).powi(damage.year - base_year + 1)))
+                .sum();
+
+            (option, SccMonetization { damages, present_value })
+        })
+        .collect()
+}
+
+/// Monetizes an emissions series with every SCC option. Damages are discounted from the end of their year to the base
+/// year with the real rate for the length of the series.
+#[post("/scc/monetize")]
+async fn post_scc_monetize(request: Json<SccMonetizeRequest>, data: Data<AppData>) -> impl Responder {
+    let mut db = data.pool.get().expect("Failed to get a connection");
+
+    let (years, base_year) = match monetize_years(request.start, request.emissions.len(), request.base_year) {
+        Ok(years) => years,
+        Err(error) => return HttpResponse::BadRequest().json(ErrorResponse { error }),
+    };
+    let study_period = years[years.len() - 1] - base_year + 1;
+
+    let discount_rate = match study_discount_rate(request.release_year, &request.rate, study_period, &mut db) {
+        Ok(Some(found)) => found.real,
+        Ok(None) => return HttpResponse::BadRequest().json(ErrorResponse {
+            error: format!("No {} discount rate for release year {}", request.rate, request.release_year),
+        }),
+        Err(_) => return HttpResponse::BadRequest().json(ErrorResponse {
+            error: "Could not get discount rates".to_string()
+        }),
+    };
+
+    let scc_rows = {
+        use crate::schema::scc::dsl::*;
+
+        scc.filter(release_year.eq(request.release_year).and(year.between(years[0], years[years.len() - 1])))
+            .select(Scc::as_select())
+            .load(&mut db)
+    };
+    let scc_by_year: BTreeMap<i32, Scc> = match scc_rows {
+        Ok(rows) => rows.into_iter().map(|row| (row.year, row)).collect(),
+        Err(_) => return HttpResponse::BadRequest().json(ErrorResponse {
+            error: "Could not get scc".to_string(),
+        }),
+    };
+
+    let options = monetize(&years, &request.emissions, base_year, discount_rate, &scc_by_year);
+
+    HttpResponse::Ok().json(SccMonetizeResponse { discount_rate, options })
+}
+
+#[get("/states")]
+async fn get_states(data: Data<AppData>) -> impl Responder {
+    let mut db = data.pool.get().expect("Failed to get a connection");
+
+    use crate::schema::state_division_region::dsl::*;
+    use crate::schema::state_division_region::*;
 
     let query: QueryResult<Vec<String>> = state_division_region.select(state).load(&mut db);
 
@@ -526,7 +1280,7 @@ async fn get_states(data: Data<AppData>) -> impl Responder {
     }
 }
 
-#[derive(Deserialize, Clone)]
+#[derive(Deserialize, Serialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 #[serde(rename_all = "camelCase", deny_unknown_fields)]
 enum EnergyTypeOptions {
     DistillateFuelOil,
@@ -534,6 +1288,121 @@ enum EnergyTypeOptions {
     NaturalGas,
     Electricity,
     Propane,
+    Coal,
+}
+
+impl EnergyTypeOptions {
+    fn name(&self) -> &'static str {
+        match self {
+            EnergyTypeOptions::DistillateFuelOil => "distillate fuel oil",
+            EnergyTypeOptions::ResidualFuelOil => "residual fuel oil",
+            EnergyTypeOptions::NaturalGas => "natural gas",
+            EnergyTypeOptions::Electricity => "electricity",
+            EnergyTypeOptions::Propane => "propane",
+            EnergyTypeOptions::Coal => "coal",
+        }
+    }
+
+    const ALL: [EnergyTypeOptions; 6] = [
+        EnergyTypeOptions::DistillateFuelOil,
+        EnergyTypeOptions::ResidualFuelOil,
+        EnergyTypeOptions::NaturalGas,
+        EnergyTypeOptions::Electricity,
+        EnergyTypeOptions::Propane,
+        EnergyTypeOptions::Coal,
+    ];
+
+    fn fuel_type(&self) -> FuelType {
+        match self {
+            EnergyTypeOptions::DistillateFuelOil => FuelType::DistillateOil,
+            EnergyTypeOptions::ResidualFuelOil => FuelType::ResidualOil,
+            EnergyTypeOptions::NaturalGas => FuelType::NaturalGas,
+            EnergyTypeOptions::Electricity => FuelType::Electricity,
+            EnergyTypeOptions::Propane => FuelType::Propane,
+            EnergyTypeOptions::Coal => FuelType::Coal,
+        }
+    }
+
+    fn index(&self, indices: &EnergyPriceIndices) -> Option<f64> {
+        match self {
+            EnergyTypeOptions::DistillateFuelOil => indices.distillate_fuel_oil,
+            EnergyTypeOptions::ResidualFuelOil => indices.residual_fuel_oil,
+            EnergyTypeOptions::NaturalGas => indices.natural_gas,
+            EnergyTypeOptions::Electricity => indices.electricity,
+            EnergyTypeOptions::Propane => indices.propane,
+            EnergyTypeOptions::Coal => indices.coal,
+        }
+    }
+
+    fn escalation(&self, rates: &EscalationRate) -> Option<f64> {
+        match self {
+            EnergyTypeOptions::DistillateFuelOil => rates.distillate_fuel_oil,
+            EnergyTypeOptions::ResidualFuelOil => rates.residual_fuel_oil,
+            EnergyTypeOptions::NaturalGas => rates.natural_gas,
+            EnergyTypeOptions::Electricity => rates.electricity,
+            EnergyTypeOptions::Propane => rates.propane,
+            EnergyTypeOptions::Coal => rates.coal,
+        }
+    }
+
+    fn set_escalation(&self, rates: &mut EscalationRate, value: Option<f64>) {
+        let rate = match self {
+            EnergyTypeOptions::DistillateFuelOil => &mut rates.distillate_fuel_oil,
+            EnergyTypeOptions::ResidualFuelOil => &mut rates.residual_fuel_oil,
+            EnergyTypeOptions::NaturalGas => &mut rates.natural_gas,
+            EnergyTypeOptions::Electricity => &mut rates.electricity,
+            EnergyTypeOptions::Propane => &mut rates.propane,
+            EnergyTypeOptions::Coal => &mut rates.coal,
+        };
+        *rate = value;
+    }
+
+    fn price(&self, prices: &EnergyPrices) -> Option<f64> {
+        match self {
+            EnergyTypeOptions::DistillateFuelOil => prices.distillate_fuel_oil,
+            EnergyTypeOptions::ResidualFuelOil => prices.residual_fuel_oil,
+            EnergyTypeOptions::NaturalGas => prices.natural_gas,
+            EnergyTypeOptions::Electricity => prices.electricity,
+            EnergyTypeOptions::Propane => prices.propane,
+            EnergyTypeOptions::Coal => prices.coal,
+        }
+    }
+}
+
+/// Finds the census division of a request from either its division or its zipcode, whose state is looked up in
+/// `state_division_region` the same way as for `/escalation_rates`.
+fn resolve_division(
+    given: &Option<String>,
+    zipcode: Option<i32>,
+    db: &mut PgConnection,
+) -> Result<String, HttpResponse> {
+    use crate::schema::state_division_region::dsl::{division, state_division_region};
+    use crate::schema::zip_info::dsl::{state, zip, zip_info};
+
+    match (given, zipcode) {
+        (Some(given), None) => Ok(given.clone()),
+        (None, Some(zipcode)) => {
+            let result: QueryResult<Option<String>> = zip_info
+                .inner_join(state_division_region.on(crate::schema::state_division_region::state.eq(state)))
+                .filter(zip.eq(zipcode))
+                .select(division)
+                .first(db)
+                .optional();
+
+            match result {
+                Ok(Some(found)) => Ok(found),
+                Ok(None) => Err(HttpResponse::BadRequest().json(ErrorResponse {
+                    error: format!("Could not find the division of zipcode {}", zipcode)
+                })),
+                Err(_) => Err(HttpResponse::BadRequest().json(ErrorResponse {
+                    error: format!("Could not get zip info for zipcode {}", zipcode)
+                })),
+            }
+        }
+        _ => Err(HttpResponse::BadRequest().json(ErrorResponse {
+            error: "Either a division or a zip is required, but not both".to_string()
+        })),
+    }
 }
 
 #[derive(Deserialize)]
@@ -542,81 +1411,441 @@ struct EnergyPriceRequest {
     from: i32,
     to: i32,
     release_year: i32,
-    division: String,
+    division: Option<String>,
+    zip: Option<i32>,
     sector: String,
     fuel_type: EnergyTypeOptions,
     case: String,
+    extrapolate: Option<Extrapolation>,
 }
 
 #[post("/energy_prices")]
 async fn post_energy_prices(request: Json<EnergyPriceRequest>, data: Data<AppData>) -> impl Responder {
     let mut db = data.pool.get().expect("Failed to get a connection");
+    let location = match resolve_division(&request.division, request.zip, &mut db) {
+        Ok(location) => location,
+        Err(response) => return response,
+    };
 
     use crate::schema::energy_prices::dsl::*;
     use crate::schema::energy_prices::*;
+    use diesel::dsl::count;
+
+    // Not every fuel is published in every release year, so reject the request rather than returning only nulls
+    let release = energy_prices.filter(release_year.eq(request.release_year));
+    let available: QueryResult<i64> = match request.fuel_type {
+        EnergyTypeOptions::DistillateFuelOil => release.select(count(distillate_fuel_oil)).first(&mut db),
+        EnergyTypeOptions::ResidualFuelOil => release.select(count(residual_fuel_oil)).first(&mut db),
+        EnergyTypeOptions::NaturalGas => release.select(count(natural_gas)).first(&mut db),
+        EnergyTypeOptions::Electricity => release.select(count(electricity)).first(&mut db),
+        EnergyTypeOptions::Propane => release.select(count(propane)).first(&mut db),
+        EnergyTypeOptions::Coal => release.select(count(coal)).first(&mut db),
+    };
+
+    match available {
+        Ok(0) => return HttpResponse::BadRequest().json(ErrorResponse {
+            error: format!("No {} data for release year {}", request.fuel_type.name(), request.release_year)
+        }),
+        Ok(_) => {}
+        Err(_) => return HttpResponse::BadRequest().json(ErrorResponse {
+            error: "Could not get energy prices".to_string()
+        }),
+    }
 
     let query = energy_prices
         .filter(
             release_year.eq(request.release_year)
-                .and(year.between(request.from, request.to))
-                .and(division.eq(request.division.clone()))
+                .and(year.between(first_year(request.from, request.extrapolate), request.to))
+                .and(division.eq(location))
                 .and(case.eq(request.case.clone()))
                 .and(sector.eq(request.sector.clone()))
-        );
-
-    let result: QueryResult<Vec<Option<f64>>> = match request.fuel_type.clone() {
-        EnergyTypeOptions::DistillateFuelOil => query.select(distillate_fuel_oil).load(&mut db),
-        EnergyTypeOptions::ResidualFuelOil => query.select(residual_fuel_oil).load(&mut db),
-        EnergyTypeOptions::NaturalGas => query.select(natural_gas).load(&mut db),
-        EnergyTypeOptions::Electricity => query.select(electricity).load(&mut db),
-        EnergyTypeOptions::Propane => query.select(propane).load(&mut db)
+        )
+        .order_by(year);
+
+    let result: QueryResult<Vec<(i32, Option<f64>)>> = match request.fuel_type {
+        EnergyTypeOptions::DistillateFuelOil => query.select((year, distillate_fuel_oil)).load(&mut db),
+        EnergyTypeOptions::ResidualFuelOil => query.select((year, residual_fuel_oil)).load(&mut db),
+        EnergyTypeOptions::NaturalGas => query.select((year, natural_gas)).load(&mut db),
+        EnergyTypeOptions::Electricity => query.select((year, electricity)).load(&mut db),
+        EnergyTypeOptions::Propane => query.select((year, propane)).load(&mut db),
+        EnergyTypeOptions::Coal => query.select((year, coal)).load(&mut db),
     };
 
     match result {
-        Ok(values) => HttpResponse::Ok().json(values),
+        Ok(rows) => year_range(rows, request.from, request.to, request.extrapolate),
         Err(_) => HttpResponse::BadRequest().json(ErrorResponse {
             error: "Could not get energy prices".to_string()
         })
     }
 }
 
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+struct EnergyPriceBatchRequest {
+    from: i32,
+    to: i32,
+    release_year: i32,
+    division: Option<String>,
+    zip: Option<i32>,
+    sector: String,
+    case: String,
+    /// The fuels to return, or every fuel if empty.
+    #[serde(default)]
+    fuel_types: Vec<EnergyTypeOptions>,
+    extrapolate: Option<Extrapolation>,
+}
+
+#[derive(Serialize)]
+struct EnergyPriceBatchResponse {
+    /// The prices of each fuel every year, with `null` where a fuel has no price. Fuels without any prices are not
+    /// extrapolated.
+    prices: BTreeMap<EnergyTypeOptions, YearSeries>,
+}
+
+#[post("/energy_prices/batch")]
+async fn post_energy_prices_batch(request: Json<EnergyPriceBatchRequest>, data: Data<AppData>) -> impl Responder {
+    let mut db = data.pool.get().expect("Failed to get a connection");
+    let location = match resolve_division(&request.division, request.zip, &mut db) {
+        Ok(location) => location,
+        Err(response) => return response,
+    };
+
+    use crate::schema::energy_prices::dsl::*;
+    use crate::schema::energy_prices::*;
+
+    let result = energy_prices
+        .filter(
+            release_year.eq(request.release_year)
+                .and(year.between(first_year(request.from, request.extrapolate), request.to))
+                .and(division.eq(location))
+                .and(case.eq(request.case.clone()))
+                .and(sector.eq(request.sector.clone()))
+        )
+        .order_by(year)
+        .select(EnergyPrices::as_select())
+        .load(&mut db);
+
+    let fuels = match request.fuel_types.is_empty() {
+        true => EnergyTypeOptions::ALL.to_vec(),
+        false => request.fuel_types.clone(),
+    };
+
+    let rows = match result {
+        Ok(rows) => rows,
+        Err(_) => return HttpResponse::BadRequest().json(ErrorResponse {
+            error: "Could not get energy prices".to_string()
+        }),
+    };
+
+    let mut prices = BTreeMap::new();
+    for fuel in fuels {
+        let fuel_prices: Vec<(i32, Option<f64>)> = rows.iter().map(|row| (row.year, fuel.price(row))).collect();
+        let series = match fuel_prices.iter().any(|(_, price)| price.is_some()) {
+            true => year_series(&fuel_prices, request.from, request.to, request.extrapolate),
+            false => Ok(YearSeries::new(&fuel_prices, request.from, request.to)),
+        };
+
+        match series {
+            Ok(series) => prices.insert(fuel, series),
+            Err(error) => return HttpResponse::BadRequest().json(ErrorResponse {
+                error: format!("{}: {}", fuel.name(), error),
+            }),
+        };
+    }
+
+    HttpResponse::Ok().json(EnergyPriceBatchResponse { prices })
+}
+
 #[post("/energy_price_indices")]
 async fn post_energy_price_indices(request: Json<EnergyPriceRequest>, data: Data<AppData>) -> impl Responder {
     let mut db = data.pool.get().expect("Failed to get a connection");
+    let location = match resolve_division(&request.division, request.zip, &mut db) {
+        Ok(location) => location,
+        Err(response) => return response,
+    };
 
     use crate::schema::energy_price_indices::dsl::*;
     use crate::schema::energy_price_indices::*;
+    use diesel::dsl::count;
+
+    // Not every fuel is published in every release year, so reject the request rather than returning only nulls
+    let release = energy_price_indices.filter(release_year.eq(request.release_year));
+    let available: QueryResult<i64> = match request.fuel_type {
+        EnergyTypeOptions::DistillateFuelOil => release.select(count(distillate_fuel_oil)).first(&mut db),
+        EnergyTypeOptions::ResidualFuelOil => release.select(count(residual_fuel_oil)).first(&mut db),
+        EnergyTypeOptions::NaturalGas => release.select(count(natural_gas)).first(&mut db),
+        EnergyTypeOptions::Electricity => release.select(count(electricity)).first(&mut db),
+        EnergyTypeOptions::Propane => release.select(count(propane)).first(&mut db),
+        EnergyTypeOptions::Coal => release.select(count(coal)).first(&mut db),
+    };
+
+    match available {
+        Ok(0) => return HttpResponse::BadRequest().json(ErrorResponse {
+            error: format!("No {} data for release year {}", request.fuel_type.name(), request.release_year)
+        }),
+        Ok(_) => {}
+        Err(_) => return HttpResponse::BadRequest().json(ErrorResponse {
+            error: "Could not get energy price indices".to_string()
+        }),
+    }
 
     let query = energy_price_indices
         .filter(
             release_year.eq(request.release_year)
-                .and(year.between(request.from, request.to))
-                .and(division.eq(request.division.clone()))
+                .and(year.between(first_year(request.from, request.extrapolate), request.to))
+                .and(division.eq(location))
                 .and(case.eq(request.case.clone()))
                 .and(sector.eq(request.sector.clone()))
-        );
-
-    let result: QueryResult<Vec<Option<f64>>> = match request.fuel_type.clone() {
-        EnergyTypeOptions::DistillateFuelOil => query.select(distillate_fuel_oil).load(&mut db),
-        EnergyTypeOptions::ResidualFuelOil => query.select(residual_fuel_oil).load(&mut db),
-        EnergyTypeOptions::NaturalGas => query.select(natural_gas).load(&mut db),
-        EnergyTypeOptions::Electricity => query.select(electricity).load(&mut db),
-        EnergyTypeOptions::Propane => query.select(propane).load(&mut db)
+        )
+        .order_by(year);
+
+    let result: QueryResult<Vec<(i32, Option<f64>)>> = match request.fuel_type {
+        EnergyTypeOptions::DistillateFuelOil => query.select((year, distillate_fuel_oil)).load(&mut db),
+        EnergyTypeOptions::ResidualFuelOil => query.select((year, residual_fuel_oil)).load(&mut db),
+        EnergyTypeOptions::NaturalGas => query.select((year, natural_gas)).load(&mut db),
+        EnergyTypeOptions::Electricity => query.select((year, electricity)).load(&mut db),
+        EnergyTypeOptions::Propane => query.select((year, propane)).load(&mut db),
+        EnergyTypeOptions::Coal => query.select((year, coal)).load(&mut db),
     };
 
     match result {
-        Ok(values) => HttpResponse::Ok().json(values),
+        Ok(rows) => year_range(rows, request.from, request.to, request.extrapolate),
         Err(_) => HttpResponse::BadRequest().json(ErrorResponse {
             error: "Could not get energy price indices".to_string()
         })
     }
 }
 
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+struct PriceProjectionRequest {
+    release_year: i32,
+    /// The year of the base price. Defaults to the release year.
+    base_year: Option<i32>,
+    /// The price in the base year in constant dollars of that year.
+    base_price: f64,
+    /// The last year of the projection.
+    to: i32,
+    division: Option<String>,
+    zip: Option<i32>,
+    sector: String,
+    fuel_type: EnergyTypeOptions,
+    case: String,
+    /// The real escalation rate for years after the last year of `energy_price_indices`.
+    #[serde(default)]
+    escalation: f64,
+    /// The inflation rate for nominal prices. Defaults to the inflation of the DOE discount rate of the release year.
+    inflation: Option<f64>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PriceProjectionResponse {
+    /// Prices in constant dollars of the base year, with `null` for years up to the last data year without an index.
+    real: Vec<YearValue>,
+    /// Prices in current dollars of each year.
+    nominal: Vec<YearValue>,
+    inflation: f64,
+    /// The last year with price indices. Later prices are escalated at the constant escalation rate.
+    last_data_year: i32,
+}
+
+/// Projects a base price through the study period with the price indices of its location, sector and case.
+#[post("/price_projection")]
+async fn post_price_projection(request: Json<PriceProjectionRequest>, data: Data<AppData>) -> impl Responder {
+    let mut db = data.pool.get().expect("Failed to get a connection");
+
+    let location = match resolve_division(&request.division, request.zip, &mut db) {
+        Ok(location) => location,
+        Err(response) => return response,
+    };
+    let base_year = request.base_year.unwrap_or(request.release_year);
+
+    let result = {
+        use crate::schema::energy_price_indices::dsl::*;
+
+        energy_price_indices
+            .filter(
+                release_year.eq(request.release_year)
+                    .and(year.between(base_year, request.to))
+                    .and(division.eq(&location))
+                    .and(case.eq(&request.case))
+                    .and(sector.eq(&request.sector))
+            )
+            .order_by(year)
+            .select(EnergyPriceIndices::as_select())
+            .load(&mut db)
+    };
+    let indices: BTreeMap<i32, f64> = match result {
+        Ok(rows) => rows.iter().filter_map(|row| Some((row.year, request.fuel_type.index(row)?))).collect(),
+        Err(_) => return HttpResponse::BadRequest().json(ErrorResponse {
+            error: "Could not get energy price indices".to_string()
+        }),
+    };
+
+    // The projection may end before the indices do, so the last year is looked up over every year of the table
+    let last_year: QueryResult<Option<i32>> = {
+        use crate::schema::energy_price_indices::dsl::*;
+
+        let location_indices = energy_price_indices.filter(
+            release_year.eq(request.release_year)
+                .and(division.eq(&location))
+                .and(case.eq(&request.case))
+                .and(sector.eq(&request.sector))
+        );
+
+        match request.fuel_type {
+            EnergyTypeOptions::DistillateFuelOil => {
+                location_indices.filter(distillate_fuel_oil.is_not_null()).select(diesel::dsl::max(year)).first(&mut db)
+            }
+            EnergyTypeOptions::ResidualFuelOil => {
+                location_indices.filter(residual_fuel_oil.is_not_null()).select(diesel::dsl::max(year)).first(&mut db)
+            }
+            EnergyTypeOptions::NaturalGas => {
+                location_indices.filter(natural_gas.is_not_null()).select(diesel::dsl::max(year)).first(&mut db)
+            }
+            EnergyTypeOptions::Electricity => {
+                location_indices.filter(electricity.is_not_null()).select(diesel::dsl::max(year)).first(&mut db)
+            }
+            EnergyTypeOptions::Propane => {
+                location_indices.filter(propane.is_not_null()).select(diesel::dsl::max(year)).first(&mut db)
+            }
+            EnergyTypeOptions::Coal => {
+                location_indices.filter(coal.is_not_null()).select(diesel::dsl::max(year)).first(&mut db)
+            }
+        }
+    };
+    let last_data_year = match last_year {
+        Ok(last_year) => last_year,
+        Err(_) => return HttpResponse::BadRequest().json(ErrorResponse {
+            error: "Could not get energy price indices".to_string()
+        }),
+    };
+
+    let Some(last_data_year) = last_data_year.filter(|_| indices.contains_key(&base_year)) else {
+        return HttpResponse::BadRequest().json(ErrorResponse {
+            error: format!("No {} price index for {} in release year {}", request.fuel_type.name(), base_year, request.release_year),
+        });
+    };
+
+    let inflation = match request.inflation {
+        Some(inflation) => inflation,
+        None => match study_discount_rate(request.release_year, "DOE", request.to - base_year, &mut db) {
+            Ok(Some(rates)) => rates.inflation,
+            _ => return HttpResponse::BadRequest().json(ErrorResponse {
+                error: format!("No inflation rate for release year {}", request.release_year),
+            }),
+        },
+    };
+
+    let real = project_prices(request.base_price, base_year, request.to, &indices, last_data_year, request.escalation);
+    let nominal = real
+        .iter()
+        .map(|price| YearValue {
+            year: price.year,
+            value: price.value.map(|value| value * (1.0 + inflation).powi(price.year - base_year)),
+        })
+        .collect();
+
+    HttpResponse::Ok().json(PriceProjectionResponse { real, nominal, inflation, last_data_year })
+}
+
+/// Projects the base price from `base_year` to `to` with the price indices, which must include the base year. Years
+/// up to the last data year without an index are left empty rather than guessed, and later years escalate the price
+/// of the last data year.
+fn project_prices(
+    base_price: f64,
+    base_year: i32,
+    to: i32,
+    indices: &BTreeMap<i32, f64>,
+    last_data_year: i32,
+    escalation: f64,
+) -> Vec<YearValue> {
+    let base_index = indices[&base_year];
+    let indexed = |projected: i32| indices.get(&projected).map(|index| base_price * index / base_index);
+    let last_price = indexed(last_data_year).unwrap_or(base_price);
+
+    (base_year..=to)
+        .map(|projected| YearValue {
+            year: projected,
+            value: match projected > last_data_year {
+                true => Some(last_price * (1.0 + escalation).powi(projected - last_data_year)),
+                false => indexed(projected),
+            },
+        })
+        .collect()
+}
+
+/// Fills in the rates between published study periods by linear interpolation, as in
+/// `discount_rates_interpolated.csv`, and holds the longest published rate through `through`. Rates published for
+/// period 0 apply to any length, so they are returned unchanged.
+fn interpolate_rates(rates: Vec<DiscountRates>, through: Option<i32>) -> Vec<DiscountRates> {
+    let (Some(first), Some(last)) = (rates.first(), rates.last()) else {
+        return rates;
+    };
+    if first.year == 0 {
+        return rates;
+    }
+
+    let end = through.map_or(last.year, |through| through.max(last.year));
+    (first.year..=end)
+        .map(|period| {
+            let upper = rates.iter().position(|point| point.year >= period).unwrap_or(rates.len() - 1);
+            let (low, high) = (&rates[upper.saturating_sub(1)], &rates[upper]);
+            let fraction = match high.year == low.year || period > high.year {
+                true => 1.0,
+                false => (period - low.year) as f64 / (high.year - low.year) as f64,
+            };
+            let between = |low: f64, high: f64| low + (high - low) * fraction;
+
+            DiscountRates {
+                release_year: high.release_year,
+                rate: high.rate.clone(),
+                year: period,
+                real: between(low.real, high.real),
+                nominal: between(low.nominal, high.nominal),
+                inflation: between(low.inflation, high.inflation),
+            }
+        })
+        .collect()
+}
+
+/// Finds the discount rate of a study period, interpolating between published periods. Studies shorter than every
+/// published period use the shortest one.
+fn study_discount_rate(
+    release: i32,
+    rate_name: &str,
+    study_period: i32,
+    db: &mut PgConnection,
+) -> QueryResult<Option<DiscountRates>> {
+    use crate::schema::discount_rates::dsl::*;
+
+    let rates = discount_rates
+        .filter(release_year.eq(release).and(rate.eq(rate_name)))
+        .order_by(year)
+        .select(DiscountRates::as_select())
+        .load(db)?;
+
+    // Longer studies use the longest published period, without interpolating every year up to them
+    let mut rates = interpolate_rates(rates, None);
+    let found = rates.iter().position(|found| found.year == 0 || found.year >= study_period);
+
+    Ok(match found {
+        Some(index) => Some(rates.swap_remove(index)),
+        None => rates.pop(),
+    })
+}
+
 #[derive(Deserialize)]
 #[serde(rename_all = "camelCase", deny_unknown_fields)]
 struct DiscountRateRequest {
     release_year: i32,
     rate: String,
+    /// Whether to return a rate for every study period length instead of only the published ones.
+    #[serde(default)]
+    interpolate: bool,
+    /// The longest study period to return when interpolating. Periods past the longest published one use its rate.
+    study_period: Option<i32>,
 }
 
 #[post("/discount_rates")]
@@ -636,6 +1865,7 @@ async fn post_discount_rates(request: Json<DiscountRateRequest>, data: Data<AppD
         .load(&mut db);
 
     match result {
+        Ok(rates) if request.interpolate => HttpResponse::Ok().json(interpolate_rates(rates, request.study_period)),
         Ok(rates) => HttpResponse::Ok().json(rates),
         Err(_) => HttpResponse::BadRequest().json(ErrorResponse {
             error: "Could not get discount rates".to_string()
@@ -643,48 +1873,752 @@ async fn post_discount_rates(request: Json<DiscountRateRequest>, data: Data<AppD
     }
 }
 
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+struct DiscountFactorRequest {
+    release_year: i32,
+    rate: String,
+    study_period: i32,
+    #[serde(default = "default_discounting_method")]
+    discounting_method: DiscountingMethod,
+    dollar_method: DollarMethod,
+    /// The escalation rate for UPV*, either constant or one per year of the study period. It should be real for
+    /// constant dollars and nominal for current dollars.
+    escalation: Option<NumberOrArray>,
+}
+
+fn default_discounting_method() -> DiscountingMethod {
+    DiscountingMethod::EndOfYear
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct DiscountFactorResponse {
+    /// The real rate for constant dollars or the nominal rate for current dollars.
+    discount_rate: f64,
+    /// The single present value factor of every year of the study period, starting at year 1.
+    spv: Vec<f64>,
+    /// The uniform present value factor.
+    upv: f64,
+    /// The modified uniform present value factor, which is the UPV of an amount that escalates every year.
+    upv_star: f64,
+}
+
+/// Calculates present value factors as in the FEMP handbook. Amounts are discounted from the end of their year, or the
+/// middle with mid-year discounting.
+#[post("/discount_factors")]
+async fn post_discount_factors(request: Json<DiscountFactorRequest>, data: Data<AppData>) -> impl Responder {
+    let mut db = data.pool.get().expect("Failed to get a connection");
+
+    if request.study_period < 1 {
+        return HttpResponse::BadRequest().json(ErrorResponse {
+            error: "The study period must be at least one year".to_string(),
+        });
+    }
+
+    let rates = match study_discount_rate(request.release_year, &request.rate, request.study_period, &mut db) {
+        Ok(Some(rates)) => rates,
+        Ok(None) => return HttpResponse::BadRequest().json(ErrorResponse {
+            error: format!("No {} discount rate for release year {}", request.rate, request.release_year),
+        }),
+        Err(_) => return HttpResponse::BadRequest().json(ErrorResponse {
+            error: "Could not get discount rates".to_string()
+        }),
+    };
+    let discount_rate = match request.dollar_method {
+        DollarMethod::Constant => rates.real,
+        DollarMethod::Current => rates.nominal,
+    };
+
+    match yearly_escalation(&request.escalation, request.study_period as usize) {
+        Ok(escalation) => {
+            HttpResponse::Ok().json(discount_factors(discount_rate, request.discounting_method, &escalation))
+        }
+        Err(error) => HttpResponse::BadRequest().json(ErrorResponse { error }),
+    }
+}
+
+/// The escalation rate of every year of a study period of `length` years. Rates after the study period are ignored.
+fn yearly_escalation(escalation: &Option<NumberOrArray>, length: usize) -> Result<Vec<f64>, String> {
+    match escalation {
+        None => Ok(vec![0.0; length]),
+        Some(NumberOrArray::Number(rate)) => Ok(vec![*rate; length]),
+        Some(NumberOrArray::Array(escalation)) if escalation.len() >= length => Ok(escalation[..length].to_vec()),
+        Some(NumberOrArray::Array(_)) => Err(format!("Expected an escalation rate for each of the {} years", length)),
+    }
+}
+
+/// The present value factors of a study period with one year per escalation rate.
+fn discount_factors(discount_rate: f64, method: DiscountingMethod, escalation: &[f64]) -> DiscountFactorResponse {
+    let offset = match method {
+        DiscountingMethod::MidYear => 0.5,
+        DiscountingMethod::EndOfYear => 0.0,
+    };
+    let spv: Vec<f64> = (1..=escalation.len())
+        .map(|t| (1.0 + discount_rate).powf(-(t as f64 - offset)))
+        .collect();
+    let upv_star = escalation
+        .iter()
+        .scan(1.0, |index, rate| {
+            *index *= 1.0 + rate;
+            Some(*index)
+        })
+        .zip(&spv)
+        .map(|(index, factor)| index * factor)
+        .sum();
+
+    DiscountFactorResponse { discount_rate, upv: spv.iter().sum(), upv_star, spv }
+}
+
 #[derive(Deserialize)]
 #[serde(deny_unknown_fields)]
 struct E3Request {
-    request: String,
+    request: serde_json::Value,
 }
 
 #[post("/e3_request")]
-async fn post_e3_request(request: Json<E3Request>, data: Data<AppData>) -> impl Responder {
-    let e3_request = request.request.clone();
+async fn post_e3_request(request: Json<E3Request>, data: Data<AppData>, http: HttpRequest) -> impl Responder {
+    let e3_request = match AnalysisRequest::parse(request.into_inner().request) {
+        Ok(e3_request) => e3_request,
+        Err(error) => return HttpResponse::BadRequest().json(ErrorResponse { error }),
+    };
+
+    if let Err(error) = e3_request.validate() {
+        return HttpResponse::BadRequest().json(ErrorResponse { error: format!("Invalid E3 request: {}", error) });
+    }
+
+    // Cache-Control: no-cache skips the lookup but still stores the new result
+    let bypass = http
+        .headers()
+        .get(CACHE_CONTROL)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.contains("no-cache"));
+    let key = request_key(&e3_request);
+
+    if !bypass {
+        if let Some(output) = data.cache.get(&key, &data.pool) {
+            return HttpResponse::Ok().insert_header(("X-Cache", "HIT")).json(output);
+        }
+    }
+
+    match e3::send(&data.client, &data.e3, &e3_request).await {
+        Ok(output) => {
+            data.cache.insert(&key, &output, &data.pool);
+            HttpResponse::Ok()
+                .insert_header(("X-Cache", if bypass { "BYPASS" } else { "MISS" }))
+                .json(output)
+        }
+        Err(err) => HttpResponse::build(err.status()).json(ErrorResponse { error: err.to_string() }),
+    }
+}
+
+#[derive(Serialize)]
+struct ImportResponse {
+    #[serde(flatten)]
+    data: ProjectData,
+    warnings: Vec<String>,
+}
+
+#[post("/import/xml")]
+async fn post_import_xml(body: String, data: Data<AppData>) -> impl Responder {
+    let mut db = data.pool.get().expect("Failed to get a connection");
+
+    use crate::schema::energy_prices::dsl::*;
+
+    // New projects default to the latest release year, the same as in the frontend
+    let latest: Option<i32> = energy_prices
+        .select(diesel::dsl::max(release_year))
+        .first(&mut db)
+        .unwrap_or(None);
+
+    match import(&body, latest.unwrap_or(DEFAULT_RELEASE_YEAR)) {
+        Ok(imported) => HttpResponse::Ok().json(ImportResponse {
+            data: imported.data,
+            warnings: imported.warnings,
+        }),
+        Err(error) => HttpResponse::BadRequest().json(ErrorResponse { error }),
+    }
+}
+
+#[post("/export/xml")]
+async fn post_export_xml(request: Json<ProjectData>) -> impl Responder {
+    HttpResponse::Ok()
+        .content_type("application/xml")
+        .body(export(&request))
+}
+
+/// Looks up the electricity emissions rates for the project location, the same way the frontend does before sending
+/// an E3 request. Returns `None` if the project has no usable zipcode or no data exists.
+fn project_emissions(data: &ProjectData, db: &mut PgConnection) -> Option<Vec<f64>> {
+    let project = &data.project;
+    let zipcode: i32 = project.location.zipcode.as_ref().filter(|zipcode| zipcode.len() >= 5)?.parse().ok()?;
+    let study_period = project.study_period?;
+    let project_case = match project.case {
+        Case::Ref => "REF",
+        Case::LowZtc => "lowZTC",
+    };
+
+    let info = {
+        use crate::schema::zip_info::dsl::*;
+
+        zip_info.filter(zip.eq(zipcode))
+            .select(ZipInfo::as_select())
+            .first(db)
+            .ok()?
+    };
+
+    use crate::schema::region_case_ba::dsl::*;
+
+    region_case_ba
+        .filter(
+            case.eq(project_case)
+                .and(ba.eq(info.ba))
+                .and(release_year.eq(project.release_year))
+                .and(rate.eq("Avg"))
+                .and(year.between(project.release_year, project.release_year + study_period)),
+        )
+        .order_by(year)
+        .select(kg_co2_per_mwh)
+        .load(db)
+        .ok()
+}
+
+#[post("/lcc")]
+async fn post_lcc(request: Json<ProjectData>, data: Data<AppData>) -> impl Responder {
+    let mut db = data.pool.get().expect("Failed to get a connection");
+
+    let emissions = project_emissions(&request, &mut db);
+
+    match analyze(&request, emissions.as_deref()) {
+        Ok(output) => HttpResponse::Ok().json(output),
+        Err(err) => HttpResponse::BadRequest().json(ErrorResponse {
+            error: format!("Could not run analysis: {}", err),
+        }),
+    }
+}
 
-    let response = data.client
-        .post(env::var("E3_URL").expect("E3 URL not set"))
-        .header("Authorization", format!("Api-Key: {}", env::var("E3_API_KEY").expect("E3 API KEY not set")))
-        .body(e3_request)
-        .send()
-        .await
-        .expect("Request failed")
-        .text()
-        .await
-        .expect("Could not parse E3 response");
+#[derive(Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ReportRequest {
+    #[serde(flatten)]
+    data: ProjectData,
+    /// Results of a previous analysis. The project is analyzed if these are not given.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    results: Option<Output>,
+}
+
+/// The project of the request and the results to report, analyzing the project if no results were given.
+fn report_results(request: ReportRequest, db: &mut PgConnection) -> Result<(ProjectData, Output), HttpResponse> {
+    let ReportRequest { data, results } = request;
+
+    let results = match results {
+        Some(results) => results,
+        None => {
+            let emissions = project_emissions(&data, db);
+
+            analyze(&data, emissions.as_deref()).map_err(|err| {
+                HttpResponse::BadRequest().json(ErrorResponse {
+                    error: format!("Could not run analysis: {}", err),
+                })
+            })?
+        }
+    };
 
-    HttpResponse::Ok().body(response)
+    Ok((data, results))
+}
+
+fn build_report(kind: ReportKind, data: &ProjectData, results: &Output) -> Result<Report, HttpResponse> {
+    build(kind, data, results).map_err(|error| HttpResponse::BadRequest().json(ErrorResponse { error }))
+}
+
+#[post("/report/{kind}")]
+async fn post_report(kind: Path<ReportKind>, request: Json<ReportRequest>, data: Data<AppData>) -> impl Responder {
+    let kind = kind.into_inner();
+    let mut db = data.pool.get().expect("Failed to get a connection");
+
+    let report = report_results(request.into_inner(), &mut db)
+        .and_then(|(project, results)| build_report(kind, &project, &results));
+
+    match report {
+        Ok(report) => HttpResponse::Ok()
+            .content_type("application/pdf")
+            .insert_header(ContentDisposition {
+                disposition: DispositionType::Inline,
+                parameters: vec![DispositionParam::Filename(format!("{}.pdf", kind.file_name()))],
+            })
+            .body(pdf::render(&report)),
+        Err(response) => response,
+    }
+}
+
+/// The request stored for a report URL and its key. The results are stored with the project, so the URL keeps showing
+/// the report as it was rendered after newer release year data is loaded. Fails if the stored request could not be
+/// read back, for example because a result is not a finite number.
+fn stored_request(data: ProjectData, results: Output) -> Result<(String, serde_json::Value), String> {
+    let request = serde_json::to_value(ReportRequest { data, results: Some(results) }).map_err(|err| err.to_string())?;
+    serde_json::from_value::<ReportRequest>(request.clone()).map_err(|err| err.to_string())?;
+
+    Ok((content_key(&request), request))
+}
+
+/// The oldest time a stored report request is still kept.
+fn report_cutoff(data: &AppData) -> SystemTime {
+    SystemTime::now() - data.report_retention
+}
+
+/// Renders a report as HTML. The request is stored so every report of the project can later be fetched by URL, which
+/// is returned in the `Content-Location` header. Stored requests expire after `REPORT_RETENTION_DAYS`.
+#[post("/report/{kind}/html")]
+async fn post_report_html(kind: Path<ReportKind>, request: Json<ReportRequest>, data: Data<AppData>) -> impl Responder {
+    let kind = kind.into_inner();
+    let mut db = data.pool.get().expect("Failed to get a connection");
+
+    let (project, results) = match report_results(request.into_inner(), &mut db) {
+        Ok(results) => results,
+        Err(response) => return response,
+    };
+    let report = match build_report(kind, &project, &results) {
+        Ok(report) => report,
+        Err(response) => return response,
+    };
+    let body = match html::render(&report) {
+        Ok(body) => body,
+        Err(err) => {
+            return HttpResponse::InternalServerError().json(ErrorResponse {
+                error: format!("Could not render report: {}", err),
+            })
+        }
+    };
+
+    use crate::schema::stored_reports::dsl;
+
+    let cutoff = report_cutoff(&data);
+    let stored = stored_request(project, results).and_then(|(key, request)| {
+        let entry = StoredReport { key, request, created_at: SystemTime::now() };
+
+        db.transaction(|db| {
+            diesel::delete(dsl::stored_reports.filter(dsl::created_at.le(cutoff))).execute(db)?;
+            diesel::insert_into(dsl::stored_reports)
+                .values(&entry)
+                .on_conflict(dsl::key)
+                .do_update()
+                .set(dsl::created_at.eq(entry.created_at))
+                .execute(db)
+        })
+        .map(|_| entry.key)
+        .map_err(|err: diesel::result::Error| err.to_string())
+    });
+
+    let mut response = HttpResponse::Ok();
+    response.content_type("text/html; charset=utf-8");
+    match stored {
+        Ok(key) => {
+            response.insert_header((CONTENT_LOCATION, format!("/api/report/{}/html/{}", kind.slug(), key)));
+        }
+        Err(err) => log::warn!("Could not store report request: {}", err),
+    }
+
+    response.body(body)
+}
+
+#[get("/report/{kind}/html/{key}")]
+async fn get_report_html(path: Path<(ReportKind, String)>, data: Data<AppData>) -> impl Responder {
+    let (kind, key) = path.into_inner();
+    let mut db = data.pool.get().expect("Failed to get a connection");
+
+    use crate::schema::stored_reports::dsl;
+
+    let stored: Option<serde_json::Value> = match dsl::stored_reports
+        .filter(dsl::key.eq(&key))
+        .filter(dsl::created_at.gt(report_cutoff(&data)))
+        .select(dsl::request)
+        .first(&mut db)
+        .optional()
+    {
+        Ok(stored) => stored,
+        Err(err) => {
+            return HttpResponse::InternalServerError().json(ErrorResponse {
+                error: format!("Could not load report: {}", err),
+            })
+        }
+    };
+
+    // Stored requests always carry their results, the project is never analyzed again
+    let stored = stored.and_then(|stored| serde_json::from_value::<ReportRequest>(stored).ok());
+    let Some(ReportRequest { data: project, results: Some(results) }) = stored else {
+        return HttpResponse::NotFound().json(ErrorResponse {
+            error: format!("No report with key {}", key),
+        });
+    };
+
+    let report = match build_report(kind, &project, &results) {
+        Ok(report) => report,
+        Err(response) => return response,
+    };
+
+    match html::render(&report) {
+        Ok(body) => HttpResponse::Ok().content_type("text/html; charset=utf-8").body(body),
+        Err(err) => HttpResponse::InternalServerError().json(ErrorResponse {
+            error: format!("Could not render report: {}", err),
+        }),
+    }
+}
+
+#[get("/admin/validate/{release_year}")]
+async fn get_validate_release_year(path: Path<i32>, data: Data<AppData>) -> impl Responder {
+    let mut db = data.pool.get().expect("Failed to get a connection");
+
+    match validate(path.into_inner(), &mut db) {
+        Ok(validation) => HttpResponse::Ok().json(validation),
+        Err(err) => HttpResponse::BadRequest().json(ErrorResponse {
+            error: format!("Could not validate release year {}", err),
+        }),
+    }
 }
 
 pub fn config_api(config: &mut ServiceConfig) {
     config.service(
         scope("/api")
             .service(post_escalation_rates)
+            .service(post_escalation_rates_batch)
             .service(post_region_case_ba)
             .service(post_zip_info)
             .service(post_emissions)
+            .service(post_emissions_v2)
+            .service(post_emissions_consumption)
             .service(get_release_years)
+            .service(get_release_year_diff)
+            .service(get_release_year_coverage)
             .service(post_check_release_year_exists)
             .service(post_scc)
+            .service(post_scc_monetize)
             .service(get_states)
             .service(post_region_natgas)
             .service(post_region_case_oil)
             .service(post_region_case_propane_lng)
             .service(post_region_case_reeds)
             .service(post_energy_prices)
+            .service(post_energy_prices_batch)
             .service(post_energy_price_indices)
+            .service(post_price_projection)
             .service(post_discount_rates)
+            .service(post_discount_factors)
             .service(post_e3_request)
+            .service(post_lcc)
+            .service(post_import_xml)
+            .service(post_export_xml)
+            .service(post_report)
+            .service(post_report_html)
+            .service(get_report_html)
+            .service(get_validate_release_year)
     );
 }
+
+#[cfg(test)]
+mod tests {
+    use actix_web::http::StatusCode;
+    use serde_json::json;
+
+    use super::*;
+    use crate::lcc::tests::federal_financed_project;
+
+    #[test]
+    fn consumption_units_include_mwh() {
+        let mwh: ConsumptionUnit = serde_json::from_str("\"MWh\"").unwrap();
+        let ton: ConsumptionUnit = serde_json::from_str("\"Ton\"").unwrap();
+
+        assert_eq!(mwh.to_project(2.0), (Unit::Kwh, 2000.0));
+        assert_eq!(to_emissions_unit(FuelType::Electricity, Unit::Kwh, 2000.0), Some(2.0));
+        assert_eq!(ton.label(), "Ton");
+
+        // Projects can not be saved with MWh, the frontend has no such unit
+        assert!(serde_json::from_str::<Unit>("\"MWh\"").is_err());
+    }
+
+    #[test]
+    fn coal_consumption_by_weight() {
+        // A short ton of coal is about 18.8 MMBtu
+        let mj = consumption_to_emissions_unit(FuelType::Coal, Unit::Ton, 2.0).unwrap();
+        assert!((mj - 39_600.0).abs() < 1e-9);
+        assert!((consumption_to_emissions_unit(FuelType::Coal, Unit::Pound, 2000.0).unwrap() - 19_800.0).abs() < 1e-6);
+        assert_eq!(consumption_to_emissions_unit(FuelType::Coal, Unit::Mj, 5.0), Some(5.0));
+
+        // Projects keep converting coal from energy units only, the same as the E3 request the frontend builds
+        assert_eq!(to_emissions_unit(FuelType::Coal, Unit::Ton, 2.0), None);
+        assert_eq!(consumption_to_emissions_unit(FuelType::NaturalGas, Unit::Ton, 2.0), None);
+    }
+
+    async fn json(response: HttpResponse) -> (StatusCode, serde_json::Value) {
+        let status = response.status();
+        let body = actix_web::body::to_bytes(response.into_body()).await.unwrap();
+
+        (status, serde_json::from_slice(&body).unwrap())
+    }
+
+    #[actix_web::test]
+    async fn year_ranges_keep_gaps_in_place() {
+        // Rows as the region queries load them, with a year missing from the table and a year without a value
+        let rows = vec![(2024, Some(1.5)), (2025, None), (2027, Some(3.0))];
+        let (status, body) = json(year_range(rows, 2023, 2028, None)).await;
+
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(
+            body,
+            json!({
+                "values": [
+                    { "year": 2023, "value": null },
+                    { "year": 2024, "value": 1.5 },
+                    { "year": 2025, "value": null },
+                    { "year": 2026, "value": null },
+                    { "year": 2027, "value": 3.0 },
+                    { "year": 2028, "value": null },
+                ]
+            })
+        );
+    }
+
+    #[actix_web::test]
+    async fn year_ranges_flag_extrapolated_years() {
+        let rows = vec![(2020, 1.0), (2021, 2.0), (2022, 3.0)];
+        let (status, body) = json(year_range(rows.clone(), 2022, 2024, Some(Extrapolation::Linear))).await;
+
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["values"][2], json!({ "year": 2024, "value": 5.0 }));
+        assert_eq!(body["extrapolated"], json!([2023, 2024]));
+
+        let (status, body) = json(year_range(rows, 2022, 2024, Some(Extrapolation::Error))).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["error"], "No data after 2022, but the range ends in 2024");
+    }
+
+    #[test]
+    fn escalation_ranges_extrapolate_each_fuel() {
+        let rate = |year: i32, electricity: Option<f64>, coal: Option<f64>| EscalationRate {
+            release_year: 2024,
+            year,
+            division: "Pacific".to_string(),
+            sector: "Commercial".to_string(),
+            case: "REF".to_string(),
+            region: "West".to_string(),
+            propane: None,
+            distillate_fuel_oil: None,
+            residual_fuel_oil: None,
+            natural_gas: None,
+            electricity,
+            coal,
+        };
+        let rows = vec![rate(2023, Some(0.01), Some(0.02)), rate(2024, None, Some(0.03)), rate(2025, Some(0.02), None)];
+
+        let range = escalation_range(rows, 2024, 2026, Some(Extrapolation::HoldLast)).unwrap();
+        let years: Vec<(i32, bool)> = range.iter().map(|row| (row.rate.year, row.extrapolated)).collect();
+        assert_eq!(years, [(2024, false), (2025, true), (2026, true)]);
+
+        // A gap inside the data stays empty, while each fuel holds its own last value
+        assert_eq!(range[0].rate.electricity, None);
+        assert_eq!(range[1].rate.electricity, Some(0.02));
+        assert_eq!(range[1].rate.coal, Some(0.03));
+        assert_eq!(range[2].rate.coal, Some(0.03));
+        assert_eq!(range[2].rate.propane, None);
+    }
+
+    fn assert_factor(actual: f64, expected: f64) {
+        assert!((actual - expected).abs() < 5e-4, "{} != {}", actual, expected);
+    }
+
+    #[test]
+    fn discount_factors_match_femp() {
+        // FEMP handbook factors at a 3% real discount rate over 10 years
+        let factors = discount_factors(0.03, DiscountingMethod::EndOfYear, &[0.0; 10]);
+        assert_eq!(factors.spv.len(), 10);
+        assert_factor(factors.spv[0], 0.971);
+        assert_factor(factors.spv[9], 0.744);
+        assert_factor(factors.upv, 8.530);
+        assert_factor(factors.upv_star, 8.530);
+
+        // UPV* of an amount escalating 2% a year
+        let factors = discount_factors(0.03, DiscountingMethod::EndOfYear, &[0.02; 10]);
+        assert_factor(factors.upv_star, 9.481);
+
+        // Mid-year discounting moves every amount half a year earlier
+        let factors = discount_factors(0.03, DiscountingMethod::MidYear, &[0.0; 10]);
+        assert_factor(factors.upv, 8.530 * 1.03f64.sqrt());
+    }
+
+    #[test]
+    fn escalation_covers_the_study_period() {
+        assert_eq!(yearly_escalation(&None, 2).unwrap(), [0.0, 0.0]);
+        assert_eq!(yearly_escalation(&Some(NumberOrArray::Number(0.02)), 2).unwrap(), [0.02, 0.02]);
+        assert_eq!(yearly_escalation(&Some(NumberOrArray::Array(vec![0.01, 0.02, 0.03])), 2).unwrap(), [0.01, 0.02]);
+        assert_eq!(
+            yearly_escalation(&Some(NumberOrArray::Array(vec![0.01])), 2).err().unwrap(),
+            "Expected an escalation rate for each of the 2 years"
+        );
+
+        // Escalation compounds from the first year
+        let factors = discount_factors(0.0, DiscountingMethod::EndOfYear, &[0.1, 0.1]);
+        assert_factor(factors.upv_star, 1.1 + 1.21);
+    }
+
+    /// The rows of a discount rate table with a `year,release_year,rate,real,nominal,inflation` header.
+    fn discount_rate_rows(name: &str) -> Vec<DiscountRates> {
+        let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("../docs/db data/tables").join(name);
+        let mut reader = csv::Reader::from_path(path).unwrap();
+
+        reader
+            .deserialize::<(i32, i32, String, f64, f64, f64)>()
+            .map(|row| {
+                let (year, release, rate, real, nominal, inflation) = row.unwrap();
+                DiscountRates { release_year: release, rate, year, real, nominal, inflation }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn interpolates_discount_rates_as_published() {
+        let expected = discount_rate_rows("discount_rates_interpolated_2025.csv");
+        // The periods OMB publishes rates for, every other row is interpolated between them
+        let published: Vec<DiscountRates> = discount_rate_rows("discount_rates_interpolated_2025.csv")
+            .into_iter()
+            .filter(|rates| [3, 5, 7, 10, 20, 30].contains(&rates.year))
+            .collect();
+
+        let actual = interpolate_rates(published, None);
+        assert_eq!(actual.len(), expected.len());
+        for (actual, expected) in actual.iter().zip(&expected) {
+            let period = expected.year;
+            assert_eq!((actual.year, actual.release_year, &actual.rate), (period, 2025, &expected.rate));
+            for (name, actual, expected) in [
+                ("real", actual.real, expected.real),
+                ("nominal", actual.nominal, expected.nominal),
+                ("inflation", actual.inflation, expected.inflation),
+            ] {
+                assert!((actual - expected).abs() < 1e-9, "{} of period {}: {} != {}", name, period, actual, expected);
+            }
+        }
+    }
+
+    #[test]
+    fn holds_the_longest_discount_rate() {
+        let published: Vec<DiscountRates> = discount_rate_rows("discount_rates_interpolated_2025.csv")
+            .into_iter()
+            .filter(|rates| [3, 30].contains(&rates.year))
+            .collect();
+
+        let rates = interpolate_rates(published, Some(40));
+        assert_eq!(rates.first().unwrap().year, 3);
+        assert_eq!(rates.last().unwrap().year, 40);
+        assert_eq!(rates.last().unwrap().real, 0.023);
+
+        // Rates for period 0 apply to any study period
+        let any = DiscountRates {
+            release_year: 2024,
+            rate: "DOE".to_string(),
+            year: 0,
+            real: 0.03,
+            nominal: 0.042,
+            inflation: 0.012,
+        };
+        assert_eq!(interpolate_rates(vec![any], Some(40)).len(), 1);
+    }
+
+    #[test]
+    fn monetizes_within_the_emissions_years() {
+        assert_eq!(monetize_years(2024, 3, None).unwrap(), (vec![2024, 2025, 2026], 2024));
+        assert_eq!(monetize_years(2024, 3, Some(2022)).unwrap(), (vec![2024, 2025, 2026], 2022));
+        assert_eq!(monetize_years(2024, 3, Some(2026)).unwrap().1, 2026);
+
+        assert_eq!(monetize_years(2024, 0, None).err().unwrap(), "No emissions to monetize");
+        assert_eq!(
+            monetize_years(2024, 3, Some(2027)).err().unwrap(),
+            "The base year 2027 is after the last emissions year 2026"
+        );
+        assert!(monetize_years(i32::MAX - 1, 3, None).is_err());
+        assert!(monetize_years(2024, 3, Some(i32::MIN)).is_err());
+    }
+
+    #[test]
+    fn monetizes_every_scc_option() {
+        let row = |year: i32, scale: f64| Scc {
+            year,
+            release_year: 2024,
+            three_percent_ninety_fifth_percentile: 300.0 * scale,
+            five_percent_average: 50.0 * scale,
+            three_percent_average: 100.0 * scale,
+        };
+        let scc_by_year = BTreeMap::from([(2024, row(2024, 1.0)), (2025, row(2025, 2.0))]);
+
+        let options = monetize(&[2024, 2025], &[1000.0, 500.0], 2024, 0.1, &scc_by_year);
+        let average = &options[&SccOption::ThreePercentAverage];
+        let values: Vec<Option<f64>> = average.damages.iter().map(|damage| damage.value).collect();
+        assert_eq!(values, [Some(100.0), Some(100.0)]);
+        assert_factor(average.present_value.unwrap(), 100.0 / 1.1 + 100.0 / 1.21);
+        assert_factor(options[&SccOption::FivePercentAverage].present_value.unwrap(), 50.0 / 1.1 + 50.0 / 1.21);
+
+        // Damages of a base year before the emissions are discounted over the years in between
+        let options = monetize(&[2024, 2025], &[1000.0, 500.0], 2023, 0.1, &scc_by_year);
+        assert_factor(options[&SccOption::ThreePercentAverage].present_value.unwrap(), 100.0 / 1.21 + 100.0 / 1.331);
+
+        // A year without SCC data has no damages, so there is no present value
+        let options = monetize(&[2025, 2026], &[1000.0, 500.0], 2025, 0.1, &scc_by_year);
+        let average = &options[&SccOption::ThreePercentAverage];
+        assert_eq!(average.damages[1].value, None);
+        assert_eq!(average.present_value, None);
+    }
+
+    fn prices(projection: &[YearValue]) -> Vec<(i32, Option<f64>)> {
+        projection.iter().map(|price| (price.year, price.value)).collect()
+    }
+
+    #[test]
+    fn projects_prices_with_the_indices() {
+        let indices = BTreeMap::from([(2024, 1.0), (2025, 1.1), (2026, 0.9)]);
+
+        assert_eq!(
+            prices(&project_prices(10.0, 2024, 2026, &indices, 2026, 0.0)),
+            [(2024, Some(10.0)), (2025, Some(11.0)), (2026, Some(9.0))]
+        );
+        // Prices are relative to the index of the base year
+        assert_eq!(
+            prices(&project_prices(22.0, 2025, 2026, &indices, 2026, 0.0)),
+            [(2025, Some(22.0)), (2026, Some(18.0))]
+        );
+    }
+
+    #[test]
+    fn escalates_prices_after_the_last_data_year() {
+        let indices = BTreeMap::from([(2024, 1.0), (2025, 2.0)]);
+        let projection = project_prices(10.0, 2024, 2027, &indices, 2025, 0.1);
+
+        assert_eq!(projection[1].value, Some(20.0));
+        assert_factor(projection[2].value.unwrap(), 22.0);
+        assert_factor(projection[3].value.unwrap(), 24.2);
+    }
+
+    #[test]
+    fn leaves_missing_index_years_empty() {
+        // 2025 has no index, but later years do, so it is a gap rather than an escalated guess
+        let indices = BTreeMap::from([(2024, 1.0), (2026, 1.2), (2027, 1.5)]);
+        let projection = project_prices(10.0, 2024, 2028, &indices, 2027, 0.1);
+
+        assert_eq!(projection[1].value, None);
+        assert_factor(projection[2].value.unwrap(), 12.0);
+        // Escalation continues from the last data year, not from the gap
+        assert_factor(projection[4].value.unwrap(), 16.5);
+    }
+
+    #[test]
+    fn stores_reports_with_their_results() {
+        let project = federal_financed_project();
+        let results = analyze(&project, None).unwrap();
+
+        let (key, request) = stored_request(project.clone(), results.clone()).unwrap();
+        let stored: ReportRequest = serde_json::from_value(request.clone()).unwrap();
+        assert_eq!(stored.results.unwrap().measure[1].total_costs, results.measure[1].total_costs);
+        assert_eq!(key, content_key(&request));
+
+        // A result that can not be written as JSON would make the URL unreadable, so the request is not stored
+        let mut invalid = results;
+        invalid.measure[0].delta_quantity = Some(BTreeMap::from([("Electricity".to_string(), f64::NAN)]));
+        assert!(stored_request(project, invalid).is_err());
+    }
+}
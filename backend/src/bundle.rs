@@ -0,0 +1,658 @@
+use std::collections::{BTreeSet, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use diesel::prelude::*;
+use diesel::upsert::excluded;
+use serde::de::DeserializeOwned;
+
+use crate::models::*;
+use crate::release_years::{add_drafts, RELEASE_YEAR_TABLES};
+
+/// Rows per insert statement, which keeps the widest tables well below the bind parameter limit of Postgres.
+const CHUNK_SIZE: usize = 1000;
+
+/// The tables a bundle may contain, each with the other names its files use in `docs/db data/tables`.
+const TABLES: [(&str, &[&str]); 12] = [
+    ("discount_rates", &[]),
+    ("energy_price_indices", &[]),
+    ("energy_prices", &[]),
+    ("escalation_rates", &["energy_escalation_rates"]),
+    ("region_case_ba", &[]),
+    ("region_case_oil", &[]),
+    ("region_case_propane_lng", &[]),
+    ("region_case_reeds", &[]),
+    ("region_natgas", &[]),
+    ("scc", &[]),
+    ("state_division_region", &[]),
+    ("zip_info", &[]),
+];
+
+/// A row of a bundle table.
+trait BundleRow: DeserializeOwned {
+    /// The primary key of the row, used to find duplicates within a bundle.
+    fn key(&self) -> String;
+
+    /// The release year of the row, or `None` for the reference tables which do not change between releases.
+    fn release_year(&self) -> Option<i32>;
+}
+
+impl BundleRow for DiscountRates {
+    fn key(&self) -> String {
+        format!("{}, {}, {}", self.release_year, self.rate, self.year)
+    }
+
+    fn release_year(&self) -> Option<i32> {
+        Some(self.release_year)
+    }
+}
+
+impl BundleRow for EnergyPriceIndices {
+    fn key(&self) -> String {
+        format!("{}, {}, {}, {}, {}", self.release_year, self.year, self.division, self.sector, self.case)
+    }
+
+    fn release_year(&self) -> Option<i32> {
+        Some(self.release_year)
+    }
+}
+
+impl BundleRow for EnergyPrices {
+    fn key(&self) -> String {
+        format!("{}, {}, {}, {}, {}", self.release_year, self.year, self.division, self.sector, self.case)
+    }
+
+    fn release_year(&self) -> Option<i32> {
+        Some(self.release_year)
+    }
+}
+
+impl BundleRow for EscalationRate {
+    fn key(&self) -> String {
+        format!(
+            "{}, {}, {}, {}, {}, {}",
+            self.release_year, self.year, self.division, self.sector, self.case, self.region
+        )
+    }
+
+    fn release_year(&self) -> Option<i32> {
+        Some(self.release_year)
+    }
+}
+
+impl BundleRow for RegionCaseBA {
+    fn key(&self) -> String {
+        format!("{}, {}, {}, {}, {}", self.release_year, self.year, self.case, self.rate, self.ba)
+    }
+
+    fn release_year(&self) -> Option<i32> {
+        Some(self.release_year)
+    }
+}
+
+impl BundleRow for RegionCaseOil {
+    fn key(&self) -> String {
+        format!("{}, {}, {}, {}, {}", self.release_year, self.year, self.padd, self.case, self.rate)
+    }
+
+    fn release_year(&self) -> Option<i32> {
+        Some(self.release_year)
+    }
+}
+
+impl BundleRow for RegionCasePropaneLNG {
+    fn key(&self) -> String {
+        format!("{}, {}, {}, {}, {}", self.release_year, self.year, self.padd, self.case, self.rate)
+    }
+
+    fn release_year(&self) -> Option<i32> {
+        Some(self.release_year)
+    }
+}
+
+impl BundleRow for RegionCaseReeds {
+    fn key(&self) -> String {
+        format!("{}, {}, {}, {}, {}", self.release_year, self.year, self.reeds, self.case, self.rate)
+    }
+
+    fn release_year(&self) -> Option<i32> {
+        Some(self.release_year)
+    }
+}
+
+impl BundleRow for RegionCaseNatgas {
+    fn key(&self) -> String {
+        format!("{}, {}, {}, {}, {}", self.release_year, self.year, self.technobasin, self.case, self.rate)
+    }
+
+    fn release_year(&self) -> Option<i32> {
+        Some(self.release_year)
+    }
+}
+
+impl BundleRow for Scc {
+    fn key(&self) -> String {
+        format!("{}, {}", self.release_year, self.year)
+    }
+
+    fn release_year(&self) -> Option<i32> {
+        Some(self.release_year)
+    }
+}
+
+impl BundleRow for Division {
+    fn key(&self) -> String {
+        self.state.clone()
+    }
+
+    fn release_year(&self) -> Option<i32> {
+        None
+    }
+}
+
+impl BundleRow for ZipInfo {
+    fn key(&self) -> String {
+        self.zip.to_string()
+    }
+
+    fn release_year(&self) -> Option<i32> {
+        None
+    }
+}
+
+/// The validated rows of a bundle. Reference tables without files in the bundle are empty and are left untouched by
+/// [`load`], while every release year table has rows for each release year in the bundle.
+pub struct Bundle {
+    discount_rates: Vec<DiscountRates>,
+    energy_price_indices: Vec<EnergyPriceIndices>,
+    energy_prices: Vec<EnergyPrices>,
+    escalation_rates: Vec<EscalationRate>,
+    region_case_ba: Vec<RegionCaseBA>,
+    region_case_oil: Vec<RegionCaseOil>,
+    region_case_propane_lng: Vec<RegionCasePropaneLNG>,
+    region_case_reeds: Vec<RegionCaseReeds>,
+    region_natgas: Vec<RegionCaseNatgas>,
+    scc: Vec<Scc>,
+    state_division_region: Vec<Division>,
+    zip_info: Vec<ZipInfo>,
+}
+
+impl Bundle {
+    /// Finds the release years that are missing from some of the release year tables. Projects could otherwise be
+    /// offered a release year that is only partially loaded.
+    fn incomplete(&self) -> Vec<String> {
+        let tables = RELEASE_YEAR_TABLES.into_iter().zip([
+            release_years(&self.discount_rates),
+            release_years(&self.energy_price_indices),
+            release_years(&self.energy_prices),
+            release_years(&self.escalation_rates),
+            release_years(&self.region_case_ba),
+            release_years(&self.region_case_oil),
+            release_years(&self.region_case_propane_lng),
+            release_years(&self.region_case_reeds),
+            release_years(&self.region_natgas),
+            release_years(&self.scc),
+        ]);
+        let tables: Vec<_> = tables.collect();
+        let years: BTreeSet<i32> = tables.iter().flat_map(|(_, years)| years.iter().copied()).collect();
+
+        years
+            .into_iter()
+            .filter_map(|year| {
+                let missing: Vec<&str> =
+                    tables.iter().filter(|(_, years)| !years.contains(&year)).map(|(table, _)| *table).collect();
+
+                (!missing.is_empty()).then(|| format!("release year {} has no rows in {}", year, missing.join(", ")))
+            })
+            .collect()
+    }
+}
+
+/// The result of reading a bundle. Errors point at a file and, where possible, a line in it.
+pub struct Parsed {
+    pub bundle: Bundle,
+    pub warnings: Vec<String>,
+    pub errors: Vec<String>,
+}
+
+/// Reads and validates the CSV files in `directory`. A table is read from `<table>.csv` and from every CSV file in a
+/// `<table>/` directory. If `release_year` is given, every row of a release year table must belong to it. Each release
+/// year in the bundle must have rows in every release year table.
+pub fn read(directory: &Path, release_year: Option<i32>) -> Result<Parsed, String> {
+    let entries = fs::read_dir(directory).map_err(|err| format!("Could not read {}: {}", directory.display(), err))?;
+
+    let mut files: HashMap<&str, Vec<PathBuf>> = HashMap::new();
+    let mut warnings = Vec::new();
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let name = path.file_stem().and_then(|name| name.to_str()).unwrap_or_default();
+        let table = TABLES
+            .iter()
+            .find(|(table, aliases)| *table == name || aliases.contains(&name))
+            .map(|(table, _)| *table);
+
+        match table {
+            Some(table) if path.is_dir() => files.entry(table).or_default().extend(csv_files(&path)?),
+            Some(table) if is_csv(&path) => files.entry(table).or_default().push(path),
+            _ => warnings.push(format!("Skipping {}, it does not match a table", path.display())),
+        }
+    }
+
+    let mut errors = Vec::new();
+    let mut reader = Reader { files, release_year, errors: &mut errors };
+
+    let bundle = Bundle {
+        discount_rates: reader.table("discount_rates"),
+        energy_price_indices: reader.table("energy_price_indices"),
+        energy_prices: reader.table("energy_prices"),
+        escalation_rates: reader.table("escalation_rates"),
+        region_case_ba: reader.table("region_case_ba"),
+        region_case_oil: reader.table("region_case_oil"),
+        region_case_propane_lng: reader.table("region_case_propane_lng"),
+        region_case_reeds: reader.table("region_case_reeds"),
+        region_natgas: reader.table("region_natgas"),
+        scc: reader.table("scc"),
+        state_division_region: reader.table("state_division_region"),
+        zip_info: reader.table("zip_info"),
+    };
+    errors.extend(bundle.incomplete());
+
+    Ok(Parsed { bundle, warnings, errors })
+}
+
+fn is_csv(path: &Path) -> bool {
+    path.is_file() && path.extension().is_some_and(|extension| extension.eq_ignore_ascii_case("csv"))
+}
+
+fn csv_files(directory: &Path) -> Result<Vec<PathBuf>, String> {
+    let entries = fs::read_dir(directory).map_err(|err| format!("Could not read {}: {}", directory.display(), err))?;
+
+    let mut files: Vec<PathBuf> = entries.flatten().map(|entry| entry.path()).filter(|path| is_csv(path)).collect();
+    files.sort();
+
+    Ok(files)
+}
+
+struct Reader<'a> {
+    files: HashMap<&'static str, Vec<PathBuf>>,
+    release_year: Option<i32>,
+    errors: &'a mut Vec<String>,
+}
+
+impl Reader<'_> {
+    fn table<T: BundleRow>(&mut self, table: &str) -> Vec<T> {
+        let mut rows = Vec::new();
+        let mut keys: HashMap<String, String> = HashMap::new();
+
+        for path in self.files.remove(table).unwrap_or_default() {
+            self.file(table, &path, &mut rows, &mut keys);
+        }
+
+        rows
+    }
+
+    fn file<T: BundleRow>(&mut self, table: &str, path: &Path, rows: &mut Vec<T>, keys: &mut HashMap<String, String>) {
+        let file = path.display();
+        let mut reader = match csv::Reader::from_path(path) {
+            Ok(reader) => reader,
+            Err(err) => return self.errors.push(format!("{}: {}", file, err)),
+        };
+
+        let original = match reader.headers() {
+            Ok(headers) => headers.clone(),
+            Err(err) => return self.errors.push(format!("{}: {}", file, err)),
+        };
+        let headers: csv::StringRecord = original.iter().map(|header| field_name(table, header)).collect();
+
+        for record in reader.records() {
+            let record = match record {
+                Ok(record) => record,
+                Err(err) => {
+                    self.errors.push(format!("{}: {}", file, err));
+                    continue;
+                }
+            };
+            let line = record.position().map(|position| position.line()).unwrap_or_default();
+            let location = format!("{}:{}", file, line);
+
+            // The CSV deserializer accepts NaN and infinity, which would poison every calculation using the value
+            if let Some(index) = record.iter().position(|value| value.parse::<f64>().is_ok_and(|value| !value.is_finite())) {
+                self.errors.push(format!("{}: column '{}' is not a finite number", location, &original[index]));
+                continue;
+            }
+
+            let row: T = match record.deserialize(Some(&headers)) {
+                Ok(row) => row,
+                Err(err) => match err.kind() {
+                    csv::ErrorKind::Deserialize { err, .. } => match err.field() {
+                        Some(index) => {
+                            self.errors.push(format!("{}: column '{}': {}", location, &original[index as usize], err.kind()));
+                            continue;
+                        }
+                        // Errors without a field are missing columns, which would be reported for every row
+                        None => return self.errors.push(format!("{}: {}", file, column_error(&err.kind().to_string()))),
+                    },
+                    _ => {
+                        self.errors.push(format!("{}: {}", location, err));
+                        continue;
+                    }
+                },
+            };
+
+            if let (Some(expected), Some(actual)) = (self.release_year, row.release_year()) {
+                if expected != actual {
+                    self.errors.push(format!("{}: release year {} does not match {}", location, actual, expected));
+                    continue;
+                }
+            }
+
+            let key = row.key();
+            if let Some(first) = keys.get(&key) {
+                self.errors.push(format!("{}: duplicate of the row at {} ({})", location, first, key));
+                continue;
+            }
+            keys.insert(key, location);
+
+            rows.push(row);
+        }
+    }
+}
+
+/// Maps a CSV header to the name of the model field it fills. Headers are matched case-insensitively with punctuation
+/// and spaces as underscores, so both `Release Year` and `release_year` work.
+fn field_name(table: &str, header: &str) -> String {
+    let normalized = header
+        .to_lowercase()
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("_");
+
+    let column = match (table, normalized.as_str()) {
+        (_, "distillate_oil") => "distillate_fuel_oil",
+        (_, "ba_name") => "ba",
+        (_, "padd_region") => "padd",
+        (_, "kg_co2_eq_mwh") => "kg_co2_per_mwh",
+        (_, "kg_co2_eq_mj") => "kg_co2_per_mj",
+        ("scc", "3_dr_95th_percentile") => "three_percent_ninety_fifth_percentile",
+        ("scc", "5_dr_average") => "five_percent_average",
+        ("scc", "3_dr_average") => "three_percent_average",
+        ("zip_info", "zip_code") => "zip",
+        ("zip_info", "state_abbr") => "state",
+        ("zip_info", "reeds_gea") => "reeds_ba",
+        ("zip_info", "cambium_gea") => "gea",
+        (_, column) => column,
+    };
+
+    // Models are deserialized with their camelCase JSON names
+    let mut parts = column.split('_');
+    let first = parts.next().unwrap_or_default().to_string();
+    parts.fold(first, |mut name, part| {
+        let mut chars = part.chars();
+        if let Some(c) = chars.next() {
+            name.extend(c.to_uppercase());
+            name.push_str(chars.as_str());
+        }
+        name
+    })
+}
+
+/// Names the column in serde's missing field errors the way it is written in the schema.
+fn column_error(message: &str) -> String {
+    match message.strip_prefix("missing field `").and_then(|field| field.strip_suffix('`')) {
+        Some(field) => {
+            let column = field.chars().fold(String::new(), |mut column, c| {
+                if c.is_ascii_uppercase() {
+                    column.push('_');
+                }
+                column.push(c.to_ascii_lowercase());
+                column
+            });
+            format!("missing column {}", column)
+        }
+        None => message.to_string(),
+    }
+}
+
+/// The number of rows loaded into a table and the release years they replaced.
+pub struct Loaded {
+    pub table: &'static str,
+    pub rows: usize,
+    pub release_years: BTreeSet<i32>,
+}
+
+fn release_years<T: BundleRow>(rows: &[T]) -> BTreeSet<i32> {
+    rows.iter().filter_map(|row| row.release_year()).collect()
+}
+
+/// Replaces the release years present in each table of the bundle with its rows. Deletes and inserts run in one
+/// statement per chunk inside the caller's transaction.
+macro_rules! replace_release_years {
+    ($db:expr, $loaded:expr, $rows:expr, $table:ident) => {{
+        let rows = &$rows;
+        if !rows.is_empty() {
+            use crate::schema::$table::dsl;
+
+            let years = release_years(rows);
+            diesel::delete(dsl::$table.filter(dsl::release_year.eq_any(&years))).execute($db)?;
+            for chunk in rows.chunks(CHUNK_SIZE) {
+                diesel::insert_into(dsl::$table).values(chunk).execute($db)?;
+            }
+
+            $loaded.push(Loaded { table: stringify!($table), rows: rows.len(), release_years: years });
+        }
+    }};
+}
+
+/// Loads a bundle in a single transaction, so either every table is updated or none is. Release year tables have the
+/// release years in the bundle replaced, while the reference tables are updated by key.
+pub fn load(bundle: &Bundle, db: &mut PgConnection) -> QueryResult<Vec<Loaded>> {
+    db.transaction(|db| {
+        let mut loaded = Vec::new();
+
+        replace_release_years!(db, loaded, bundle.discount_rates, discount_rates);
+        replace_release_years!(db, loaded, bundle.energy_price_indices, energy_price_indices);
+        replace_release_years!(db, loaded, bundle.energy_prices, energy_prices);
+        replace_release_years!(db, loaded, bundle.escalation_rates, escalation_rates);
+        replace_release_years!(db, loaded, bundle.region_case_ba, region_case_ba);
+        replace_release_years!(db, loaded, bundle.region_case_oil, region_case_oil);
+        replace_release_years!(db, loaded, bundle.region_case_propane_lng, region_case_propane_lng);
+        replace_release_years!(db, loaded, bundle.region_case_reeds, region_case_reeds);
+        replace_release_years!(db, loaded, bundle.region_natgas, region_natgas);
+        replace_release_years!(db, loaded, bundle.scc, scc);
+
+        if !bundle.state_division_region.is_empty() {
+            use crate::schema::state_division_region::dsl::*;
+
+            for chunk in bundle.state_division_region.chunks(CHUNK_SIZE) {
+                diesel::insert_into(state_division_region)
+                    .values(chunk)
+                    .on_conflict(state)
+                    .do_update()
+                    .set((division.eq(excluded(division)), region.eq(excluded(region))))
+                    .execute(db)?;
+            }
+
+            loaded.push(Loaded {
+                table: "state_division_region",
+                rows: bundle.state_division_region.len(),
+                release_years: BTreeSet::new(),
+            });
+        }
+
+        if !bundle.zip_info.is_empty() {
+            use crate::schema::zip_info::dsl::*;
+
+            for chunk in bundle.zip_info.chunks(CHUNK_SIZE) {
+                diesel::insert_into(zip_info)
+                    .values(chunk)
+                    .on_conflict(zip)
+                    .do_update()
+                    .set((
+                        ba.eq(excluded(ba)),
+                        gea.eq(excluded(gea)),
+                        state.eq(excluded(state)),
+                        padd.eq(excluded(padd)),
+                        technobasin.eq(excluded(technobasin)),
+                        reeds_ba.eq(excluded(reeds_ba)),
+                    ))
+                    .execute(db)?;
+            }
+
+            loaded.push(Loaded { table: "zip_info", rows: bundle.zip_info.len(), release_years: BTreeSet::new() });
+        }
+
+        // New release years stay drafts, hidden from the frontend, until they are published
+        let years = loaded.iter().flat_map(|table| table.release_years.iter().copied()).collect();
+        add_drafts(&years, db)?;
+
+        Ok(loaded)
+    })
+}
+
+const USAGE: &str = "Usage: backend import <directory> [--release-year <year>]";
+
+/// Runs `backend import`, returning the process exit code. Nothing is loaded if any row of the bundle is invalid.
+pub fn command(args: &[String], db: &mut PgConnection) -> i32 {
+    let (directory, release_year) = match args {
+        [directory] => (directory, None),
+        [directory, flag, year] if flag == "--release-year" => match year.parse() {
+            Ok(year) => (directory, Some(year)),
+            Err(_) => {
+                eprintln!("Invalid release year {}\n{}", year, USAGE);
+                return 2;
+            }
+        },
+        _ => {
+            eprintln!("{}", USAGE);
+            return 2;
+        }
+    };
+
+    let parsed = match read(Path::new(directory), release_year) {
+        Ok(parsed) => parsed,
+        Err(err) => {
+            eprintln!("{}", err);
+            return 1;
+        }
+    };
+
+    for warning in &parsed.warnings {
+        eprintln!("warning: {}", warning);
+    }
+
+    if !parsed.errors.is_empty() {
+        for error in &parsed.errors {
+            eprintln!("error: {}", error);
+        }
+        eprintln!("{} errors, nothing was loaded", parsed.errors.len());
+        return 1;
+    }
+
+    match load(&parsed.bundle, db) {
+        Ok(loaded) if loaded.is_empty() => {
+            eprintln!("No tables found in {}", directory);
+            1
+        }
+        Ok(loaded) => {
+            for table in loaded {
+                let years = table.release_years.iter().map(|year| year.to_string()).collect::<Vec<_>>();
+                match years.is_empty() {
+                    true => println!("{}: {} rows", table.table, table.rows),
+                    false => println!("{}: {} rows for release years {}", table.table, table.rows, years.join(", ")),
+                }
+            }
+            0
+        }
+        Err(err) => {
+            eprintln!("Could not load bundle, nothing was loaded: {}", err);
+            1
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    use super::*;
+
+    fn shipped_tables() -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("../docs/db data/tables")
+    }
+
+    #[test]
+    fn maps_the_shipped_headers() {
+        let cases = [
+            ("discount_rates", "Release Year", "releaseYear"),
+            ("escalation_rates", "Distillate Oil", "distillateFuelOil"),
+            ("escalation_rates", "Residual Fuel Oil", "residualFuelOil"),
+            ("escalation_rates", "distillate_fuel_oil", "distillateFuelOil"),
+            ("region_case_ba", "BA Name", "ba"),
+            ("region_case_ba", "kg CO2 eq / MWh", "kgCo2PerMwh"),
+            ("region_case_oil", "PADD REGION", "padd"),
+            ("region_natgas", "kg CO2 eq / MJ", "kgCo2PerMj"),
+            ("region_case_reeds", "release year", "releaseYear"),
+            ("scc", "3% DR (95th percentile)", "threePercentNinetyFifthPercentile"),
+            ("scc", "5% DR (Average)", "fivePercentAverage"),
+            ("scc", "3% DR (Average)", "threePercentAverage"),
+            ("zip_info", "ZIP Code", "zip"),
+            ("zip_info", "State Abbr", "state"),
+            ("zip_info", "REEDS-GEA", "reedsBa"),
+            ("zip_info", "cambium_gea", "gea"),
+        ];
+
+        for (table, header, field) in cases {
+            assert_eq!(field_name(table, header), field, "{} in {}", header, table);
+        }
+    }
+
+    #[test]
+    fn names_missing_columns_as_in_the_schema() {
+        assert_eq!(column_error("missing field `kgCo2PerMwh`"), "missing column kg_co2_per_mwh");
+        assert_eq!(column_error("invalid length"), "invalid length");
+    }
+
+    #[test]
+    fn reads_the_shipped_tables() {
+        let parsed = read(&shipped_tables(), None).unwrap();
+        let bundle = &parsed.bundle;
+
+        // Every row parses, the only problems are the release years that are not loaded into every table
+        assert_eq!(
+            parsed.errors,
+            [
+                "release year 2023 has no rows in region_case_reeds",
+                "release year 2025 has no rows in discount_rates, region_case_ba, region_case_oil, \
+                 region_case_propane_lng, region_case_reeds, region_natgas, scc",
+            ]
+        );
+        assert!(parsed.warnings.iter().any(|warning| warning.contains("discount_rates_interpolated.csv")));
+
+        assert_eq!(bundle.discount_rates.len(), 14);
+        assert_eq!(bundle.scc.len(), 88);
+        assert_eq!(bundle.state_division_region.len(), 51);
+        assert_eq!(release_years(&bundle.escalation_rates), BTreeSet::from([2023, 2024, 2025]));
+        assert_eq!(release_years(&bundle.region_case_reeds), BTreeSet::from([2024]));
+        assert!(bundle.zip_info.iter().any(|info| info.zip == 20899 && info.state == "MD"));
+    }
+
+    #[test]
+    fn rejects_partial_release_years() {
+        let directory = env::temp_dir().join(format!("blcc-bundle-{}", std::process::id()));
+        fs::create_dir_all(directory.join("energy_prices")).unwrap();
+        fs::copy(shipped_tables().join("energy_prices/2025_ref.csv"), directory.join("energy_prices/2025.csv")).unwrap();
+
+        let parsed = read(&directory, Some(2025));
+        fs::remove_dir_all(&directory).unwrap();
+
+        assert_eq!(
+            parsed.unwrap().errors,
+            [
+                "release year 2025 has no rows in discount_rates, energy_price_indices, escalation_rates, \
+                 region_case_ba, region_case_oil, region_case_propane_lng, region_case_reeds, region_natgas, scc"
+            ]
+        );
+    }
+}
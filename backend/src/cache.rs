@@ -0,0 +1,229 @@
+use std::env;
+use std::num::NonZeroUsize;
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime};
+
+use diesel::prelude::*;
+use lru::LruCache;
+use serde::Serialize;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+
+use crate::e3::AnalysisRequest;
+use crate::models::E3CacheEntry;
+use crate::DbPool;
+
+/// Cache of E3 results as E3 sent them, keyed on a hash of the request. Results are kept in memory and, if enabled, in
+/// the `e3_cache` table so they survive restarts and are shared between instances.
+pub struct E3Cache {
+    memory: Mutex<LruCache<String, (Instant, Value)>>,
+    ttl: Duration,
+    persist: bool,
+}
+
+impl E3Cache {
+    pub fn from_env() -> E3Cache {
+        let size = env::var("E3_CACHE_SIZE")
+            .ok()
+            .and_then(|value| value.parse::<usize>().ok())
+            .and_then(NonZeroUsize::new)
+            .unwrap_or(NonZeroUsize::new(256).unwrap());
+        let ttl = env::var("E3_CACHE_TTL")
+            .ok()
+            .and_then(|value| value.parse::<u64>().ok())
+            .unwrap_or(24 * 60 * 60);
+        let persist = env::var("E3_CACHE_DB").is_ok_and(|value| value == "true" || value == "1");
+
+        E3Cache {
+            memory: Mutex::new(LruCache::new(size)),
+            ttl: Duration::from_secs(ttl),
+            persist,
+        }
+    }
+
+    /// Returns the cached result for the key if one exists and has not expired.
+    pub fn get(&self, hash: &str, pool: &DbPool) -> Option<Value> {
+        {
+            let mut memory = self.memory.lock().unwrap();
+            match memory.get(hash) {
+                Some((inserted, output)) if inserted.elapsed() < self.ttl => return Some(output.clone()),
+                Some(_) => {
+                    memory.pop(hash);
+                }
+                None => {}
+            }
+        }
+
+        if !self.persist {
+            return None;
+        }
+
+        use crate::schema::e3_cache::dsl::*;
+
+        let mut db = pool.get().ok()?;
+
+        let entry = e3_cache
+            .filter(key.eq(hash))
+            .filter(created_at.gt(self.cutoff()))
+            .select(E3CacheEntry::as_select())
+            .first(&mut db)
+            .ok()?;
+        let output = entry.response;
+
+        self.memory.lock().unwrap().put(entry.key, (inserted_at(entry.created_at), output.clone()));
+
+        Some(output)
+    }
+
+    pub fn insert(&self, hash: &str, output: &Value, pool: &DbPool) {
+        self.memory.lock().unwrap().put(hash.to_string(), (Instant::now(), output.clone()));
+
+        if !self.persist {
+            return;
+        }
+
+        use crate::schema::e3_cache::dsl;
+
+        let Ok(mut db) = pool.get() else {
+            return;
+        };
+        let entry = E3CacheEntry {
+            key: hash.to_string(),
+            response: output.clone(),
+            created_at: SystemTime::now(),
+        };
+
+        let result = db.transaction(|db| {
+            diesel::delete(dsl::e3_cache.filter(dsl::created_at.le(self.cutoff()))).execute(db)?;
+            diesel::insert_into(dsl::e3_cache)
+                .values(&entry)
+                .on_conflict(dsl::key)
+                .do_update()
+                .set((dsl::response.eq(&entry.response), dsl::created_at.eq(entry.created_at)))
+                .execute(db)
+        });
+
+        if let Err(err) = result {
+            log::warn!("Could not store E3 result in cache: {}", err);
+        }
+    }
+
+    fn cutoff(&self) -> SystemTime {
+        SystemTime::now() - self.ttl
+    }
+}
+
+/// The instant an entry stored at `created_at` was inserted, so entries loaded from the database only live as long as
+/// they have left.
+fn inserted_at(created_at: SystemTime) -> Instant {
+    let age = SystemTime::now().duration_since(created_at).unwrap_or_default();
+
+    Instant::now().checked_sub(age).unwrap_or_else(Instant::now)
+}
+
+/// Hashes the request in a normalized form, so requests that only differ in key order or number formatting (`1` and
+/// `1.0`) share a cache entry.
+pub fn request_key(request: &AnalysisRequest) -> String {
+    content_key(request)
+}
+
+/// Hashes any serializable value in the same normalized form as [`request_key`].
+pub fn content_key<T: Serialize>(content: &T) -> String {
+    let value = serde_json::to_value(content).map(normalize).unwrap_or(Value::Null);
+    let hash = Sha256::digest(value.to_string().as_bytes());
+
+    hash.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+fn normalize(value: Value) -> Value {
+    match value {
+        Value::Number(number) => number.as_f64().map(Value::from).unwrap_or(Value::Number(number)),
+        Value::Array(values) => Value::Array(values.into_iter().map(normalize).collect()),
+        Value::Object(map) => Value::Object(map.into_iter().map(|(key, value)| (key, normalize(value))).collect()),
+        value => value,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use diesel::r2d2::ConnectionManager;
+    use diesel::PgConnection;
+    use r2d2::Pool;
+    use serde_json::json;
+
+    use super::*;
+
+    /// A pool that never connects, for caches that only use memory.
+    fn pool() -> DbPool {
+        Pool::builder()
+            .min_idle(Some(0))
+            .build_unchecked(ConnectionManager::<PgConnection>::new("postgres://localhost/unused"))
+    }
+
+    fn cache(ttl: Duration) -> E3Cache {
+        E3Cache {
+            memory: Mutex::new(LruCache::new(NonZeroUsize::new(2).unwrap())),
+            ttl,
+            persist: false,
+        }
+    }
+
+    #[test]
+    fn normalizes_content_keys() {
+        let key = content_key(&json!({ "a": 1, "b": [1.0, 2], "c": { "d": 0.5 } }));
+
+        assert_eq!(key.len(), 64);
+        assert!(key.chars().all(|c| c.is_ascii_hexdigit()));
+        assert_eq!(key, content_key(&json!({ "c": { "d": 0.5 }, "b": [1, 2.0], "a": 1.0 })));
+        assert_ne!(key, content_key(&json!({ "a": 1, "b": [2, 1], "c": { "d": 0.5 } })));
+        assert_ne!(key, content_key(&json!({ "a": "1", "b": [1, 2], "c": { "d": 0.5 } })));
+    }
+
+    #[test]
+    fn expires_entries_after_the_ttl() {
+        let pool = pool();
+        let cache = cache(Duration::from_secs(60));
+        cache.insert("key", &json!({ "measure": [] }), &pool);
+        assert_eq!(cache.get("key", &pool), Some(json!({ "measure": [] })));
+
+        // Age the entry past the TTL
+        let expired = Instant::now() - Duration::from_secs(61);
+        cache.memory.lock().unwrap().get_mut("key").unwrap().0 = expired;
+        assert_eq!(cache.get("key", &pool), None);
+        assert!(!cache.memory.lock().unwrap().contains("key"));
+        assert_eq!(cache.get("missing", &pool), None);
+    }
+
+    #[test]
+    fn evicts_the_least_recently_used_entry() {
+        let pool = pool();
+        let cache = cache(Duration::from_secs(60));
+        cache.insert("first", &json!(1), &pool);
+        cache.insert("second", &json!(2), &pool);
+        cache.get("first", &pool);
+        cache.insert("third", &json!(3), &pool);
+
+        assert_eq!(cache.get("first", &pool), Some(json!(1)));
+        assert_eq!(cache.get("second", &pool), None);
+    }
+
+    #[test]
+    fn purges_entries_older_than_the_ttl() {
+        let ttl = Duration::from_secs(24 * 60 * 60);
+        let cache = cache(ttl);
+
+        let before = SystemTime::now() - ttl;
+        let cutoff = cache.cutoff();
+        let after = SystemTime::now() - ttl;
+        assert!(before <= cutoff && cutoff <= after);
+
+        // An entry stored just after the cutoff is kept for the rest of its TTL once it is loaded into memory
+        let created_at = cutoff + Duration::from_secs(10);
+        let inserted = inserted_at(created_at);
+        assert!(inserted.elapsed() < ttl);
+        assert!(inserted.elapsed() > ttl - Duration::from_secs(11));
+
+        // Clocks that moved backwards do not make an entry older than it is
+        assert!(inserted_at(SystemTime::now() + Duration::from_secs(60)).elapsed() < Duration::from_secs(1));
+    }
+}
@@ -0,0 +1,125 @@
+use crate::project::{FuelType, Unit};
+
+const PROPANE: f64 = 9.63e7; // J/gallon
+const NATURAL_GAS: f64 = 1.09e6; // J/ft^3
+const COAL: f64 = 1.98e4; // MJ/short ton, about 18.8 MMBtu
+
+/// Coal has no regional emissions data, so the frontend uses this single factor for every location and year.
+pub const COAL_KG_CO2E_PER_MJ: f64 = 0.09042;
+
+const GALLON: f64 = 3.785;
+const K_GALLON: f64 = 3785.0;
+const K_LITER: f64 = 1000.0;
+const CUBIC_METERS: f64 = 1000.0;
+const CUBIC_FEET: f64 = 28.317;
+
+const MJ_PER_KWH: f64 = 3.6;
+const KG_PER_SHORT_TON: f64 = 907.184_74;
+const KG_PER_POUND: f64 = 0.453_592_37;
+const MJ_PER_MWH: f64 = 3600.0;
+const MJ_PER_GJ: f64 = 1000.0;
+const CUBIC_FEET_PER_CUBIC_METER: f64 = 35.314_666_721_488_59;
+const LITERS_PER_GALLON: f64 = 3.785_411_784;
+const GALLONS_PER_CUBIC_METER: f64 = 264.172_052_358_148_4;
+const GALLONS_PER_CUBIC_FOOT: f64 = 7.480_519_480_519_48;
+
+fn therm_to_mwh(therm: f64) -> f64 {
+    therm / 34.13
+}
+
+fn mbtu_to_mwh(mbtu: f64) -> f64 {
+    mbtu / 3_412_000.0
+}
+
+fn natural_gas_cubic_feet_to_mj(cubic_feet: f64) -> f64 {
+    // Approximate 1 cubic foot * natural gas constant as one 1 joule
+    cubic_feet * NATURAL_GAS / 1000.0
+}
+
+fn propane_gallon_to_mj(gallon: f64) -> f64 {
+    // Approximate 1 gallon * propane constant as 1 joule
+    gallon * PROPANE / 1000.0
+}
+
+/// Converts an amount of energy in the given unit to megajoules, if the unit is an energy unit.
+fn energy_to_mj(amount: f64, unit: Unit) -> Option<f64> {
+    match unit {
+        Unit::Kwh => Some(amount * MJ_PER_KWH),
+        Unit::Therm => Some(therm_to_mwh(amount) * MJ_PER_MWH),
+        Unit::Gj => Some(amount * MJ_PER_GJ),
+        Unit::Mj => Some(amount),
+        Unit::Mbtu => Some(mbtu_to_mwh(amount) * MJ_PER_MWH),
+        _ => None,
+    }
+}
+
+/// Converts a weight of coal to megajoules. Tons are short tons.
+pub fn coal_to_mj(amount: f64, unit: Unit) -> Option<f64> {
+    match unit {
+        Unit::Ton => Some(amount * COAL),
+        Unit::Pound => Some(amount * KG_PER_POUND / KG_PER_SHORT_TON * COAL),
+        Unit::Kg => Some(amount / KG_PER_SHORT_TON * COAL),
+        _ => None,
+    }
+}
+
+fn propane_to_mj(amount: f64, unit: Unit) -> Option<f64> {
+    match unit {
+        Unit::Liter => Some(propane_gallon_to_mj(amount / LITERS_PER_GALLON)),
+        Unit::KLiter => Some(propane_gallon_to_mj(amount * 1000.0 / LITERS_PER_GALLON)),
+        Unit::Gallon => Some(propane_gallon_to_mj(amount)),
+        Unit::KGallon => Some(propane_gallon_to_mj(amount * 1000.0)),
+        _ => None,
+    }
+}
+
+/// Converts an annual consumption into the unit the emissions factors are given in: MWh for electricity and MJ for
+/// every other fuel. Returns `None` if the unit cannot be converted for the given fuel type.
+pub fn to_emissions_unit(fuel_type: FuelType, unit: Unit, amount: f64) -> Option<f64> {
+    match fuel_type {
+        FuelType::Electricity => match unit {
+            Unit::Kwh => Some(amount / 1000.0),
+            Unit::Therm => Some(therm_to_mwh(amount)),
+            Unit::Gj => Some(amount * MJ_PER_GJ / MJ_PER_MWH),
+            Unit::Mj => Some(amount / MJ_PER_MWH),
+            Unit::Mbtu => Some(mbtu_to_mwh(amount)),
+            _ => None,
+        },
+        FuelType::NaturalGas => match unit {
+            Unit::CubicMeters => Some(natural_gas_cubic_feet_to_mj(amount * CUBIC_FEET_PER_CUBIC_METER)),
+            Unit::CubicFeet => Some(natural_gas_cubic_feet_to_mj(amount)),
+            _ => energy_to_mj(amount, unit),
+        },
+        FuelType::Propane => match unit {
+            Unit::CubicMeters => Some(propane_gallon_to_mj(amount * GALLONS_PER_CUBIC_METER)),
+            Unit::CubicFeet => Some(propane_gallon_to_mj(amount * GALLONS_PER_CUBIC_FOOT)),
+            _ => energy_to_mj(amount, unit).or_else(|| propane_to_mj(amount, unit)),
+        },
+        FuelType::DistillateOil | FuelType::ResidualOil => {
+            energy_to_mj(amount, unit).or_else(|| propane_to_mj(amount, unit))
+        }
+        FuelType::Coal => energy_to_mj(amount, unit),
+        FuelType::Other => None,
+    }
+}
+
+/// Converts a water amount into liters.
+pub fn to_liters(amount: f64, unit: Unit) -> f64 {
+    amount * liters_per_unit(unit)
+}
+
+/// Converts a water cost per unit into a cost per liter.
+pub fn cost_per_liter(cost_per_unit: f64, unit: Unit) -> f64 {
+    cost_per_unit / liters_per_unit(unit)
+}
+
+fn liters_per_unit(unit: Unit) -> f64 {
+    match unit {
+        Unit::Gallon => GALLON,
+        Unit::KGallon => K_GALLON,
+        Unit::KLiter => K_LITER,
+        Unit::CubicMeters => CUBIC_METERS,
+        Unit::CubicFeet => CUBIC_FEET,
+        _ => 1.0,
+    }
+}
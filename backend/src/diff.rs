@@ -0,0 +1,253 @@
+use std::collections::BTreeMap;
+
+use diesel::prelude::*;
+use serde::Serialize;
+
+use crate::models::{DiscountRates, EnergyPrices, EscalationRate, Scc};
+
+/// The values of a table in one release year, by series key and then year.
+type Series = BTreeMap<Vec<String>, BTreeMap<i32, f64>>;
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReleaseYearDiff {
+    pub from: i32,
+    pub to: i32,
+    pub tables: Vec<TableDiff>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TableDiff {
+    pub table: &'static str,
+    /// The names of the columns that make up each series key.
+    pub key_columns: Vec<&'static str>,
+    /// Series only in the `to` release year.
+    pub added: Vec<Vec<String>>,
+    /// Series only in the `from` release year.
+    pub removed: Vec<Vec<String>>,
+    /// Series in both release years.
+    pub series: Vec<SeriesDiff>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SeriesDiff {
+    pub key: Vec<String>,
+    /// The change of every year in both release years.
+    pub deltas: Vec<YearDelta>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub added_years: Vec<i32>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub removed_years: Vec<i32>,
+}
+
+#[derive(Serialize)]
+pub struct YearDelta {
+    pub year: i32,
+    pub from: f64,
+    pub to: f64,
+    pub delta: f64,
+}
+
+/// Compares two release years across the energy price, escalation rate, discount rate and SCC tables.
+pub fn diff(from: i32, to: i32, db: &mut PgConnection) -> QueryResult<ReleaseYearDiff> {
+    let energy_key = vec!["division", "sector", "case", "fuel"];
+
+    Ok(ReleaseYearDiff {
+        from,
+        to,
+        tables: vec![
+            compare("energy_prices", energy_key.clone(), energy_prices(from, db)?, energy_prices(to, db)?),
+            compare("escalation_rates", energy_key, escalation_rates(from, db)?, escalation_rates(to, db)?),
+            compare("discount_rates", vec!["rate", "measure"], discount_rates(from, db)?, discount_rates(to, db)?),
+            compare("scc", vec!["option"], scc(from, db)?, scc(to, db)?),
+        ],
+    })
+}
+
+fn compare(table: &'static str, key_columns: Vec<&'static str>, from: Series, to: Series) -> TableDiff {
+    let added = to.keys().filter(|key| !from.contains_key(*key)).cloned().collect();
+    let removed = from.keys().filter(|key| !to.contains_key(*key)).cloned().collect();
+
+    let series = from
+        .iter()
+        .filter_map(|(key, old)| {
+            let new = to.get(key)?;
+
+            Some(SeriesDiff {
+                key: key.clone(),
+                deltas: old
+                    .iter()
+                    .filter_map(|(year, from)| {
+                        new.get(year).map(|to| YearDelta { year: *year, from: *from, to: *to, delta: to - from })
+                    })
+                    .collect(),
+                added_years: new.keys().filter(|year| !old.contains_key(year)).copied().collect(),
+                removed_years: old.keys().filter(|year| !new.contains_key(year)).copied().collect(),
+            })
+        })
+        .collect();
+
+    TableDiff { table, key_columns, added, removed, series }
+}
+
+/// Adds the non-null value of every fuel of a row to the series for its division, sector and case.
+fn insert_fuels(series: &mut Series, key: [&str; 3], year: i32, fuels: [(&str, Option<f64>); 6]) {
+    for (fuel, value) in fuels {
+        if let Some(value) = value {
+            let key = key.iter().map(|column| column.to_string()).chain([fuel.to_string()]).collect();
+            series.entry(key).or_default().insert(year, value);
+        }
+    }
+}
+
+fn energy_prices(release: i32, db: &mut PgConnection) -> QueryResult<Series> {
+    use crate::schema::energy_prices::dsl::*;
+
+    let rows = energy_prices.filter(release_year.eq(release)).select(EnergyPrices::as_select()).load(db)?;
+
+    let mut series = Series::new();
+    for row in rows {
+        insert_fuels(
+            &mut series,
+            [&row.division, &row.sector, &row.case],
+            row.year,
+            [
+                ("propane", row.propane),
+                ("distillateFuelOil", row.distillate_fuel_oil),
+                ("residualFuelOil", row.residual_fuel_oil),
+                ("naturalGas", row.natural_gas),
+                ("electricity", row.electricity),
+                ("coal", row.coal),
+            ],
+        );
+    }
+
+    Ok(series)
+}
+
+fn escalation_rates(release: i32, db: &mut PgConnection) -> QueryResult<Series> {
+    use crate::schema::escalation_rates::dsl::*;
+
+    let rows = escalation_rates.filter(release_year.eq(release)).select(EscalationRate::as_select()).load(db)?;
+
+    let mut series = Series::new();
+    for row in rows {
+        insert_fuels(
+            &mut series,
+            [&row.division, &row.sector, &row.case],
+            row.year,
+            [
+                ("propane", row.propane),
+                ("distillateFuelOil", row.distillate_fuel_oil),
+                ("residualFuelOil", row.residual_fuel_oil),
+                ("naturalGas", row.natural_gas),
+                ("electricity", row.electricity),
+                ("coal", row.coal),
+            ],
+        );
+    }
+
+    Ok(series)
+}
+
+fn discount_rates(release: i32, db: &mut PgConnection) -> QueryResult<Series> {
+    use crate::schema::discount_rates::dsl::*;
+
+    let rows = discount_rates.filter(release_year.eq(release)).select(DiscountRates::as_select()).load(db)?;
+
+    let mut series = Series::new();
+    for row in rows {
+        for (measure, value) in [("real", row.real), ("nominal", row.nominal), ("inflation", row.inflation)] {
+            series.entry(vec![row.rate.clone(), measure.to_string()]).or_default().insert(row.year, value);
+        }
+    }
+
+    Ok(series)
+}
+
+fn scc(release: i32, db: &mut PgConnection) -> QueryResult<Series> {
+    use crate::schema::scc::dsl::*;
+
+    let rows = scc.filter(release_year.eq(release)).select(Scc::as_select()).load(db)?;
+
+    let mut series = Series::new();
+    for row in rows {
+        for (option, value) in [
+            ("threePercentNinetyFifthPercentile", row.three_percent_ninety_fifth_percentile),
+            ("fivePercentAverage", row.five_percent_average),
+            ("threePercentAverage", row.three_percent_average),
+        ] {
+            series.entry(vec![option.to_string()]).or_default().insert(row.year, value);
+        }
+    }
+
+    Ok(series)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    type Entry<'a> = (&'a [&'a str], &'a [(i32, f64)]);
+
+    fn series(entries: &[Entry]) -> Series {
+        entries.iter().map(|(columns, values)| (key(columns), values.iter().copied().collect())).collect()
+    }
+
+    fn key(columns: &[&str]) -> Vec<String> {
+        columns.iter().map(|column| column.to_string()).collect()
+    }
+
+    #[test]
+    fn compares_release_years() {
+        let from = series(&[
+            (&["Pacific", "Commercial", "REF", "coal"], &[(2024, 1.0)]),
+            (&["Pacific", "Commercial", "REF", "electricity"], &[(2024, 0.10), (2025, 0.12), (2026, 0.13)]),
+        ]);
+        let to = series(&[
+            (&["Pacific", "Commercial", "REF", "electricity"], &[(2025, 0.11), (2026, 0.15), (2027, 0.16)]),
+            (&["Pacific", "Commercial", "REF", "propane"], &[(2025, 2.0)]),
+        ]);
+
+        let diff = compare("energy_prices", vec!["division", "sector", "case", "fuel"], from, to);
+
+        assert_eq!(diff.added, [key(&["Pacific", "Commercial", "REF", "propane"])]);
+        assert_eq!(diff.removed, [key(&["Pacific", "Commercial", "REF", "coal"])]);
+        assert_eq!(diff.series.len(), 1);
+
+        let electricity = &diff.series[0];
+        assert_eq!(electricity.key, key(&["Pacific", "Commercial", "REF", "electricity"]));
+        assert_eq!(electricity.added_years, [2027]);
+        assert_eq!(electricity.removed_years, [2024]);
+
+        // Deltas are `to - from`, so a lower value in the newer release year is negative
+        let deltas: Vec<(i32, f64, f64)> =
+            electricity.deltas.iter().map(|delta| (delta.year, delta.from, delta.to)).collect();
+        assert_eq!(deltas, [(2025, 0.12, 0.11), (2026, 0.13, 0.15)]);
+        assert!((electricity.deltas[0].delta + 0.01).abs() < 1e-12);
+        assert!((electricity.deltas[1].delta - 0.02).abs() < 1e-12);
+    }
+
+    #[test]
+    fn omits_empty_year_lists() {
+        let from = series(&[(&["3% Average"], &[(2024, 100.0)])]);
+        let to = series(&[(&["3% Average"], &[(2024, 100.0)])]);
+
+        let json = serde_json::to_value(compare("scc", vec!["option"], from, to)).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "table": "scc",
+                "keyColumns": ["option"],
+                "added": [],
+                "removed": [],
+                "series": [{
+                    "key": ["3% Average"],
+                    "deltas": [{ "year": 2024, "from": 100.0, "to": 100.0, "delta": 0.0 }]
+                }]
+            })
+        );
+    }
+}
@@ -0,0 +1,777 @@
+use std::collections::{BTreeMap, HashSet};
+use std::env;
+use std::fmt::{Display, Formatter};
+use std::time::Duration;
+
+use actix_web::http::StatusCode;
+use actix_web::rt::time::sleep;
+use reqwest::{Client, ClientBuilder};
+use serde::{Deserialize, Deserializer, Serialize};
+use serde_json::{Map, Value};
+
+use crate::project::ID;
+
+/*
+ * E3 connection settings, read once at startup.
+ */
+
+#[derive(Clone)]
+pub struct E3Config {
+    pub url: Option<String>,
+    pub api_key: Option<String>,
+    pub timeout: Duration,
+    pub connect_timeout: Duration,
+    pub retries: u32,
+    pub backoff: Duration,
+}
+
+impl E3Config {
+    /// Reads the E3 settings from the environment. A missing URL or key does not stop the server from starting, E3
+    /// requests will fail with an error response instead.
+    pub fn from_env() -> E3Config {
+        let number = |name: &str, default: u64| {
+            env::var(name)
+                .ok()
+                .and_then(|value| value.parse::<u64>().ok())
+                .unwrap_or(default)
+        };
+
+        E3Config {
+            url: env::var("E3_URL").ok(),
+            api_key: env::var("E3_API_KEY").ok(),
+            timeout: Duration::from_secs(number("E3_TIMEOUT", 60)),
+            connect_timeout: Duration::from_secs(number("E3_CONNECT_TIMEOUT", 10)),
+            retries: number("E3_RETRIES", 2) as u32,
+            backoff: Duration::from_millis(number("E3_RETRY_BACKOFF_MS", 500)),
+        }
+    }
+}
+
+/// Builds the HTTP client used for E3 requests. The connect timeout is set here, the overall request timeout is set on
+/// each request.
+pub fn client(config: &E3Config) -> Client {
+    ClientBuilder::new()
+        .use_rustls_tls()
+        .connect_timeout(config.connect_timeout)
+        .build()
+        .unwrap()
+}
+
+pub enum E3Error {
+    NotConfigured,
+    Timeout,
+    Connection(String),
+    Upstream { status: StatusCode, body: String },
+    InvalidResponse(String),
+}
+
+impl E3Error {
+    /// The status to answer the frontend with. Errors reported by E3 itself are passed through unchanged.
+    pub fn status(&self) -> StatusCode {
+        match self {
+            E3Error::NotConfigured => StatusCode::SERVICE_UNAVAILABLE,
+            E3Error::Timeout => StatusCode::GATEWAY_TIMEOUT,
+            E3Error::Connection(_) | E3Error::InvalidResponse(_) => StatusCode::BAD_GATEWAY,
+            E3Error::Upstream { status, .. } => *status,
+        }
+    }
+
+    /// Whether trying the same request again could succeed.
+    fn is_transient(&self) -> bool {
+        match self {
+            E3Error::Timeout | E3Error::Connection(_) => true,
+            E3Error::Upstream { status, .. } => {
+                status.is_server_error() || *status == StatusCode::TOO_MANY_REQUESTS
+            }
+            E3Error::NotConfigured | E3Error::InvalidResponse(_) => false,
+        }
+    }
+}
+
+impl Display for E3Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            E3Error::NotConfigured => write!(f, "E3 is not configured on this server"),
+            E3Error::Timeout => write!(f, "E3 did not respond in time"),
+            E3Error::Connection(err) => write!(f, "Could not connect to E3: {}", err),
+            E3Error::Upstream { status, body } if body.is_empty() => write!(f, "E3 responded with {}", status),
+            E3Error::Upstream { status, body } => write!(f, "E3 responded with {}: {}", status, body),
+            E3Error::InvalidResponse(err) => write!(f, "Could not parse E3 response: {}", err),
+        }
+    }
+}
+
+/// Sends the request to E3, retrying transient failures with exponential backoff. The result is returned exactly as E3
+/// sent it once it is known to have the shape of an [`Output`], so values the typed result can not hold, such as an
+/// infinite SIR, reach the frontend unchanged.
+pub async fn send(client: &Client, config: &E3Config, request: &AnalysisRequest) -> Result<Value, E3Error> {
+    let mut attempt = 0;
+
+    loop {
+        match send_once(client, config, request).await {
+            Err(err) if err.is_transient() && attempt < config.retries => {
+                let delay = config.backoff * 2u32.pow(attempt);
+                log::warn!("E3 request failed ({}), retrying in {:?}", err, delay);
+
+                sleep(delay).await;
+                attempt += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
+async fn send_once(client: &Client, config: &E3Config, request: &AnalysisRequest) -> Result<Value, E3Error> {
+    let (Some(url), Some(api_key)) = (&config.url, &config.api_key) else {
+        return Err(E3Error::NotConfigured);
+    };
+
+    let response = client
+        .post(url)
+        .header("Authorization", format!("Api-Key: {}", api_key))
+        .timeout(config.timeout)
+        .json(request)
+        .send()
+        .await
+        .map_err(from_reqwest)?;
+
+    let status = response.status();
+    if !status.is_success() {
+        let body = response.text().await.unwrap_or_default();
+        let status = StatusCode::from_u16(status.as_u16()).unwrap_or(StatusCode::BAD_GATEWAY);
+
+        return Err(E3Error::Upstream { status, body });
+    }
+
+    let body = response.bytes().await.map_err(from_reqwest)?;
+    let output: Value = serde_json::from_slice(&body).map_err(|err| E3Error::InvalidResponse(err.to_string()))?;
+    Output::deserialize(&output).map_err(|err| E3Error::InvalidResponse(err.to_string()))?;
+
+    Ok(output)
+}
+
+fn from_reqwest(err: reqwest::Error) -> E3Error {
+    if err.is_timeout() {
+        E3Error::Timeout
+    } else {
+        E3Error::Connection(err.to_string())
+    }
+}
+
+/*
+ * E3 analysis request. Only the fields the backend validates are typed, everything else is kept as is and forwarded
+ * to E3 untouched.
+ */
+
+#[derive(Deserialize, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct AnalysisRequest {
+    pub analysis_object: Analysis,
+    pub alternative_objects: Vec<Alternative>,
+    pub bcn_objects: Vec<Bcn>,
+    #[serde(flatten)]
+    pub other: Map<String, Value>,
+}
+
+#[derive(Deserialize, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Analysis {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub analysis_type: Option<String>,
+    pub study_period: i32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timestep_value: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timestep_comp: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub output_real_bool: Option<bool>,
+    #[serde(rename = "dRateReal", skip_serializing_if = "Option::is_none")]
+    pub discount_rate_real: Option<f64>,
+    #[serde(rename = "dRateNom", skip_serializing_if = "Option::is_none")]
+    pub discount_rate_nominal: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub inflation_rate: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reinvest_rate: Option<f64>,
+    #[serde(flatten)]
+    pub other: Map<String, Value>,
+}
+
+#[derive(Deserialize, Serialize, Clone)]
+pub struct Alternative {
+    #[serde(rename = "altID")]
+    pub alt_id: ID,
+    #[serde(rename = "altName", skip_serializing_if = "Option::is_none")]
+    pub alt_name: Option<String>,
+    #[serde(rename = "altBCNList", default)]
+    pub alt_bcn_list: Vec<ID>,
+    #[serde(rename = "baselineBool", default)]
+    pub baseline_bool: bool,
+    #[serde(flatten)]
+    pub other: Map<String, Value>,
+}
+
+#[derive(Deserialize, Serialize, Clone)]
+pub struct Bcn {
+    #[serde(rename = "bcnID")]
+    pub bcn_id: ID,
+    #[serde(rename = "bcnName", skip_serializing_if = "Option::is_none")]
+    pub bcn_name: Option<String>,
+    #[serde(rename = "initialOcc", skip_serializing_if = "Option::is_none")]
+    pub initial_occurrence: Option<i32>,
+    #[serde(rename = "recurInterval", skip_serializing_if = "Option::is_none")]
+    pub recur_interval: Option<i32>,
+    #[serde(rename = "quantVarValue", skip_serializing_if = "Option::is_none")]
+    pub quantity_var_value: Option<Value>,
+    #[serde(rename = "recurVarValue", skip_serializing_if = "Option::is_none")]
+    pub recur_var_value: Option<Value>,
+    #[serde(flatten)]
+    pub other: Map<String, Value>,
+}
+
+impl AnalysisRequest {
+    /// Parses a request that was either sent as a JSON encoded string, as the frontend does, or as a JSON object.
+    pub fn parse(value: Value) -> Result<AnalysisRequest, String> {
+        let result = match value {
+            Value::String(string) => serde_json::from_str(&string),
+            value => serde_json::from_value(value),
+        };
+
+        result.map_err(|err| format!("Malformed E3 request: {}", err))
+    }
+
+    /// Checks the request for errors E3 would otherwise reject or silently compute garbage from. Every problem found
+    /// is reported, not just the first.
+    pub fn validate(&self) -> Result<(), String> {
+        let mut errors = vec![];
+        let analysis = &self.analysis_object;
+
+        if analysis.study_period < 0 {
+            errors.push(format!("Study period must not be negative, got {}", analysis.study_period));
+        }
+
+        let rates = [
+            ("Real discount rate", analysis.discount_rate_real),
+            ("Nominal discount rate", analysis.discount_rate_nominal),
+            ("Inflation rate", analysis.inflation_rate),
+            ("Reinvest rate", analysis.reinvest_rate),
+        ];
+        for (name, rate) in rates {
+            if let Some(rate) = rate {
+                if !rate.is_finite() || rate <= -1.0 {
+                    errors.push(format!("{} must be a number greater than -1, got {}", name, rate));
+                }
+            }
+        }
+
+        if self.alternative_objects.is_empty() {
+            errors.push("Request must have at least one alternative".to_string());
+        }
+
+        let baselines = self.alternative_objects.iter().filter(|alt| alt.baseline_bool).count();
+        if baselines > 1 {
+            errors.push(format!("Request must have at most one baseline alternative, got {}", baselines));
+        }
+
+        let mut alt_ids = HashSet::new();
+        for alternative in self.alternative_objects.iter() {
+            if !alt_ids.insert(alternative.alt_id) {
+                errors.push(format!("Duplicate alternative ID {}", alternative.alt_id));
+            }
+        }
+
+        let mut bcn_ids = HashSet::new();
+        for bcn in self.bcn_objects.iter() {
+            if !bcn_ids.insert(bcn.bcn_id) {
+                errors.push(format!("Duplicate BCN ID {}", bcn.bcn_id));
+            }
+
+            // Occurrences include the construction period, which is not part of the study period sent to E3, so only
+            // the start of the analysis can be checked
+            let name = bcn.bcn_name.clone().unwrap_or_else(|| bcn.bcn_id.to_string());
+            if let Some(initial) = bcn.initial_occurrence.filter(|initial| *initial < 0) {
+                errors.push(format!("BCN {} occurs in year {} which is before the start of the analysis", name, initial));
+            }
+
+            if bcn.recur_interval.is_some_and(|interval| interval < 0) {
+                errors.push(format!("BCN {} has a negative recurrence interval", name));
+            }
+
+            for (field, value) in [("quantVarValue", &bcn.quantity_var_value), ("recurVarValue", &bcn.recur_var_value)] {
+                if value.as_ref().is_some_and(|value| !is_number_or_numbers(value)) {
+                    errors.push(format!("BCN {} has a non-numeric {}", name, field));
+                }
+            }
+        }
+
+        for alternative in self.alternative_objects.iter() {
+            for id in alternative.alt_bcn_list.iter().filter(|id| !bcn_ids.contains(id)) {
+                errors.push(format!("Alternative {} references BCN {} which does not exist", alternative.alt_id, id));
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors.join("; "))
+        }
+    }
+}
+
+fn is_number_or_numbers(value: &Value) -> bool {
+    match value {
+        Value::Null | Value::Number(_) => true,
+        Value::Array(values) => values.iter().all(|value| value.is_number()),
+        _ => false,
+    }
+}
+
+/*
+ * E3 results. These are also produced by the native engine in `lcc`.
+ */
+
+#[derive(Deserialize, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Optional {
+    pub alt_id: ID,
+    pub tag: String,
+    pub total_tag_cashflow_discounted: Vec<f64>,
+    pub total_tag_cashflow_non_discounted: Vec<f64>,
+    pub total_tag_quantity: Vec<f64>,
+    pub units: Option<String>,
+}
+
+#[derive(Deserialize, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Required {
+    pub alt_id: ID,
+    pub total_costs_non_discounted: Vec<f64>,
+    pub total_costs_discounted: Vec<f64>,
+    pub total_benefits_non_discounted: Vec<f64>,
+    pub total_benefits_discounted: Vec<f64>,
+    pub total_costs_non_discounted_invest: Vec<f64>,
+    pub total_costs_discounted_invest: Vec<f64>,
+    pub total_benefits_non_discounted_invest: Vec<f64>,
+    pub total_benefits_discounted_invest: Vec<f64>,
+    pub total_costs_non_discounted_non_invest: Vec<f64>,
+    pub total_costs_discounted_non_invest: Vec<f64>,
+    pub total_benefits_non_discounted_non_invest: Vec<f64>,
+    pub total_benefits_discounted_non_invest: Vec<f64>,
+    pub total_costs_non_discounted_direct: Vec<f64>,
+    pub total_costs_discounted_direct: Vec<f64>,
+    pub total_benefits_non_discounted_direct: Vec<f64>,
+    pub total_benefits_discounted_direct: Vec<f64>,
+    pub total_costs_non_discounted_indirect: Vec<f64>,
+    pub total_costs_discounted_indirect: Vec<f64>,
+    pub total_benefits_non_discounted_indirect: Vec<f64>,
+    pub total_benefits_discounted_indirect: Vec<f64>,
+    pub total_costs_non_discounted_external: Vec<f64>,
+    pub total_costs_discounted_external: Vec<f64>,
+    pub total_benefits_non_discounted_external: Vec<f64>,
+    pub total_benefits_discounted_external: Vec<f64>,
+}
+
+#[derive(Deserialize, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Measures {
+    pub alt_id: ID,
+    pub total_benefits: f64,
+    pub total_costs: f64,
+    pub total_costs_invest: f64,
+    pub total_cost_non_invest: f64,
+    #[serde(deserialize_with = "float_map")]
+    pub total_tag_flows: BTreeMap<String, f64>,
+    #[serde(default, deserialize_with = "lenient_number")]
+    pub net_benefits: Option<f64>,
+    #[serde(default, deserialize_with = "lenient_number")]
+    pub net_savings: Option<f64>,
+    #[serde(default, deserialize_with = "lenient_number")]
+    pub sir: Option<f64>,
+    #[serde(default, deserialize_with = "lenient_number")]
+    pub irr: Option<f64>,
+    #[serde(default, deserialize_with = "lenient_number")]
+    pub airr: Option<f64>,
+    #[serde(default, deserialize_with = "lenient_number")]
+    pub dpp: Option<f64>,
+    #[serde(default, deserialize_with = "lenient_number")]
+    pub spp: Option<f64>,
+    #[serde(default, deserialize_with = "lenient_number")]
+    pub bcr: Option<f64>,
+    #[serde(deserialize_with = "float_map")]
+    pub quantity_sum: BTreeMap<String, f64>,
+    pub quantity_units: BTreeMap<String, Option<String>>,
+    #[serde(default, deserialize_with = "lenient_number")]
+    pub marr: Option<f64>,
+    #[serde(default, deserialize_with = "optional_float_map")]
+    pub delta_quantity: Option<BTreeMap<String, f64>>,
+    #[serde(default, deserialize_with = "lenient_number_map")]
+    pub ns_percent_quantity: Option<BTreeMap<String, Option<f64>>>,
+    #[serde(default, deserialize_with = "lenient_number_map")]
+    pub ns_delta_quantity: Option<BTreeMap<String, Option<f64>>>,
+    #[serde(default, deserialize_with = "lenient_number_map")]
+    pub ns_elasticity_quantity: Option<BTreeMap<String, Option<f64>>>,
+}
+
+#[derive(Deserialize, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Output {
+    #[serde(default)]
+    pub optional: Vec<Optional>,
+    #[serde(default)]
+    pub required: Vec<Required>,
+    #[serde(default)]
+    pub measure: Vec<Measures>,
+}
+
+/// E3 reports values like an infinite SIR as the strings "Infinity" or "NaN". These carry no usable value so they
+/// are read as `None`, the same way non-finite values are written.
+fn to_number(value: Value) -> Option<f64> {
+    value.as_f64().filter(|value| value.is_finite())
+}
+
+fn lenient_number<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<f64>, D::Error> {
+    Ok(to_number(Value::deserialize(deserializer)?))
+}
+
+/// Reads a number that E3 may have written as "Infinity", "-Infinity" or "NaN" without losing the value.
+fn to_float<E: serde::de::Error>(value: Value) -> Result<f64, E> {
+    match value {
+        Value::Number(number) => number.as_f64().ok_or_else(|| E::custom("number out of range")),
+        Value::String(string) => match string.as_str() {
+            "Infinity" => Ok(f64::INFINITY),
+            "-Infinity" => Ok(f64::NEG_INFINITY),
+            "NaN" => Ok(f64::NAN),
+            _ => Err(E::custom(format!("expected a number, got \"{}\"", string))),
+        },
+        value => Err(E::custom(format!("expected a number, got {}", value))),
+    }
+}
+
+fn float_map<'de, D: Deserializer<'de>>(deserializer: D) -> Result<BTreeMap<String, f64>, D::Error> {
+    let map: BTreeMap<String, Value> = BTreeMap::deserialize(deserializer)?;
+
+    map.into_iter().map(|(key, value)| Ok((key, to_float(value)?))).collect()
+}
+
+fn optional_float_map<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<BTreeMap<String, f64>>, D::Error> {
+    let map: Option<BTreeMap<String, Value>> = Option::deserialize(deserializer)?;
+
+    map.map(|map| map.into_iter().map(|(key, value)| Ok((key, to_float(value)?))).collect()).transpose()
+}
+
+fn lenient_number_map<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<BTreeMap<String, Option<f64>>>, D::Error> {
+    let map: Option<BTreeMap<String, Value>> = Option::deserialize(deserializer)?;
+
+    Ok(map.map(|map| map.into_iter().map(|(key, value)| (key, to_number(value))).collect()))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::{SocketAddr, TcpListener, TcpStream};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::time::Instant;
+
+    use actix_web::{web, App, HttpResponse, HttpServer};
+    use serde_json::json;
+
+    use super::*;
+
+    fn config(url: String, retries: u32) -> E3Config {
+        E3Config {
+            url: Some(url),
+            api_key: Some("test".to_string()),
+            timeout: Duration::from_secs(5),
+            connect_timeout: Duration::from_secs(5),
+            retries,
+            backoff: Duration::from_millis(20),
+        }
+    }
+
+    fn request() -> AnalysisRequest {
+        AnalysisRequest::parse(json!({
+            "analysisObject": { "studyPeriod": 1 },
+            "alternativeObjects": [],
+            "bcnObjects": []
+        }))
+        .unwrap()
+    }
+
+    /// Starts a local stand-in for E3 that answers every request with `respond(n)`, where `n` counts the requests
+    /// received so far starting at zero. Returns the URL to send requests to and the request counter.
+    fn mock_e3<F, R>(respond: F) -> (String, Arc<AtomicUsize>)
+    where
+        F: Fn(usize) -> R + Clone + Send + 'static,
+        R: std::future::Future<Output = HttpResponse> + 'static,
+    {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let counter = calls.clone();
+
+        let server = HttpServer::new(move || {
+            let counter = counter.clone();
+            let respond = respond.clone();
+
+            App::new().default_service(web::to(move || respond(counter.fetch_add(1, Ordering::SeqCst))))
+        })
+        .workers(1)
+        .bind(("127.0.0.1", 0))
+        .unwrap();
+        let address = server.addrs()[0];
+        actix_web::rt::spawn(server.run());
+
+        (format!("http://{}/api/v2/analysis", address), calls)
+    }
+
+    /// A listener whose accept queue is full, so new connections are never established.
+    fn unreachable_e3() -> (SocketAddr, TcpListener, Vec<TcpStream>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+
+        let mut connections = vec![];
+        while let Ok(stream) = TcpStream::connect_timeout(&address, Duration::from_millis(100)) {
+            connections.push(stream);
+            assert!(connections.len() < 10_000, "accept queue never filled up");
+        }
+
+        (address, listener, connections)
+    }
+
+    /// A request with a baseline and one alternative, each with a cost that recurs every year of the study period.
+    fn valid_request() -> Value {
+        json!({
+            "analysisObject": {
+                "analysisType": "LCCA",
+                "studyPeriod": 25,
+                "dRateReal": 0.03,
+                "dRateNom": 0.045,
+                "inflationRate": 0.015,
+                "reinvestRate": 0.015,
+                "outputRealBool": true
+            },
+            "alternativeObjects": [
+                { "altID": 0, "altName": "Baseline", "altBCNList": [0], "baselineBool": true },
+                { "altID": 1, "altName": "Alternative", "altBCNList": [1], "baselineBool": false }
+            ],
+            "bcnObjects": [
+                { "bcnID": 0, "bcnName": "Energy", "initialOcc": 1, "recurInterval": 1, "quantVarValue": [0.01, 0.02] },
+                { "bcnID": 1, "bcnName": "Retrofit", "initialOcc": 0, "quantVarValue": 1.0 }
+            ]
+        })
+    }
+
+    fn validate(request: Value) -> Result<(), String> {
+        AnalysisRequest::parse(request).unwrap().validate()
+    }
+
+    #[test]
+    fn accepts_valid_requests() {
+        assert!(validate(valid_request()).is_ok());
+
+        // The frontend sends the request as a JSON encoded string
+        assert!(validate(Value::String(valid_request().to_string())).is_ok());
+    }
+
+    #[test]
+    fn accepts_occurrences_in_the_construction_period() {
+        // A two year construction period moves the last occurrences past the study period sent to E3
+        let mut request = valid_request();
+        request["bcnObjects"][0]["initialOcc"] = json!(27);
+
+        assert!(validate(request).is_ok());
+    }
+
+    #[test]
+    fn rejects_invalid_analysis_settings() {
+        let mut request = valid_request();
+        request["analysisObject"]["studyPeriod"] = json!(-1);
+        request["analysisObject"]["dRateReal"] = json!(-1.0);
+        request["analysisObject"]["reinvestRate"] = json!(-2.5);
+
+        let error = validate(request).unwrap_err();
+        assert!(error.contains("Study period must not be negative, got -1"), "{}", error);
+        assert!(error.contains("Real discount rate must be a number greater than -1, got -1"), "{}", error);
+        assert!(error.contains("Reinvest rate must be a number greater than -1, got -2.5"), "{}", error);
+        assert!(!error.contains("Nominal"), "{}", error);
+    }
+
+    #[test]
+    fn rejects_invalid_alternatives() {
+        let mut request = valid_request();
+        request["alternativeObjects"][1]["altID"] = json!(0);
+        request["alternativeObjects"][1]["baselineBool"] = json!(true);
+        request["alternativeObjects"][1]["altBCNList"] = json!([1, 7]);
+
+        let error = validate(request).unwrap_err();
+        assert_eq!(
+            error,
+            "Request must have at most one baseline alternative, got 2; Duplicate alternative ID 0; \
+             Alternative 0 references BCN 7 which does not exist"
+        );
+
+        let mut request = valid_request();
+        request["alternativeObjects"] = json!([]);
+        assert_eq!(validate(request).unwrap_err(), "Request must have at least one alternative");
+    }
+
+    #[test]
+    fn rejects_invalid_bcns() {
+        let mut request = valid_request();
+        request["bcnObjects"][1]["bcnID"] = json!(0);
+        request["bcnObjects"][1]["initialOcc"] = json!(-1);
+        request["bcnObjects"][1]["recurInterval"] = json!(-1);
+        request["bcnObjects"][0]["quantVarValue"] = json!([0.01, "0.02"]);
+        request["bcnObjects"][0]["recurVarValue"] = json!("1");
+
+        let error = validate(request).unwrap_err();
+        assert_eq!(
+            error,
+            "BCN Energy has a non-numeric quantVarValue; BCN Energy has a non-numeric recurVarValue; \
+             Duplicate BCN ID 0; BCN Retrofit occurs in year -1 which is before the start of the analysis; \
+             BCN Retrofit has a negative recurrence interval; Alternative 1 references BCN 1 which does not exist"
+        );
+    }
+
+    #[actix_web::test]
+    async fn returns_results_as_e3_sent_them() {
+        let body = json!({
+            "optional": [],
+            "required": [],
+            "measure": [{
+                "altId": 0,
+                "totalBenefits": 0.0,
+                "totalCosts": 10.5,
+                "totalCostsInvest": 0.0,
+                "totalCostNonInvest": 10.5,
+                "totalTagFlows": { "Energy": 10.5 },
+                "sir": "Infinity",
+                "airr": "NaN",
+                "quantitySum": { "Energy": 1.0 },
+                "quantityUnits": { "Energy": "kWh" },
+                "deltaQuantity": { "Energy": "NaN" },
+                "newMeasure": [1, 2]
+            }]
+        });
+        let response = body.clone();
+        let (url, _) = mock_e3(move |_| {
+            let response = response.clone();
+            async move { HttpResponse::Ok().json(response) }
+        });
+        let config = config(url, 0);
+
+        let output = send(&client(&config), &config, &request()).await.ok().unwrap();
+        assert_eq!(output, body);
+    }
+
+    #[actix_web::test]
+    async fn rejects_results_of_the_wrong_shape() {
+        let (url, _) = mock_e3(|_| async { HttpResponse::Ok().json(json!({ "measure": [{ "altId": "zero" }] })) });
+        let config = config(url, 0);
+
+        let Err(err) = send(&client(&config), &config, &request()).await else {
+            panic!("expected the result to be rejected");
+        };
+
+        assert!(matches!(err, E3Error::InvalidResponse(_)));
+        assert_eq!(err.status(), StatusCode::BAD_GATEWAY);
+    }
+
+    #[actix_web::test]
+    async fn retries_server_errors_with_backoff() {
+        let (url, calls) = mock_e3(|n| async move {
+            match n {
+                0 | 1 => HttpResponse::ServiceUnavailable().finish(),
+                _ => HttpResponse::Ok().json(json!({ "optional": [], "required": [], "measure": [] })),
+            }
+        });
+        let config = config(url, 2);
+
+        let start = Instant::now();
+        let result = send(&client(&config), &config, &request()).await;
+
+        assert!(result.is_ok());
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+        // Two retries wait 20ms and then 40ms
+        assert!(start.elapsed() >= Duration::from_millis(60));
+    }
+
+    #[actix_web::test]
+    async fn gives_up_after_configured_retries() {
+        let (url, calls) = mock_e3(|_| async { HttpResponse::InternalServerError().body("boom") });
+        let config = config(url, 1);
+
+        let Err(err) = send(&client(&config), &config, &request()).await else {
+            panic!("expected the request to fail");
+        };
+
+        assert_eq!(err.status(), StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[actix_web::test]
+    async fn passes_client_errors_through_without_retrying() {
+        let (url, calls) = mock_e3(|_| async { HttpResponse::UnprocessableEntity().body("bad bcn") });
+        let config = config(url, 2);
+
+        let Err(err) = send(&client(&config), &config, &request()).await else {
+            panic!("expected the request to fail");
+        };
+
+        assert_eq!(err.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(err.to_string(), "E3 responded with 422 Unprocessable Entity: bad bcn");
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[actix_web::test]
+    async fn request_timeout_maps_to_gateway_timeout() {
+        let (url, calls) = mock_e3(|_| async {
+            sleep(Duration::from_secs(2)).await;
+            HttpResponse::Ok().json(json!({}))
+        });
+        let config = E3Config { timeout: Duration::from_millis(100), ..config(url, 1) };
+
+        let start = Instant::now();
+        let Err(err) = send(&client(&config), &config, &request()).await else {
+            panic!("expected the request to time out");
+        };
+
+        assert!(matches!(err, E3Error::Timeout));
+        assert_eq!(err.status(), StatusCode::GATEWAY_TIMEOUT);
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+        assert!(start.elapsed() < Duration::from_secs(2));
+    }
+
+    #[actix_web::test]
+    async fn connect_timeout_is_separate_from_request_timeout() {
+        let (address, _listener, _connections) = unreachable_e3();
+        let config = E3Config {
+            connect_timeout: Duration::from_millis(100),
+            timeout: Duration::from_secs(30),
+            ..config(format!("http://{}/api/v2/analysis", address), 0)
+        };
+
+        let start = Instant::now();
+        let Err(err) = send(&client(&config), &config, &request()).await else {
+            panic!("expected the connection to time out");
+        };
+
+        assert_eq!(err.status(), StatusCode::GATEWAY_TIMEOUT);
+        assert!(start.elapsed() < Duration::from_secs(5));
+    }
+
+    #[actix_web::test]
+    async fn refused_connection_maps_to_bad_gateway() {
+        let address = TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap();
+        let config = config(format!("http://{}/api/v2/analysis", address), 0);
+
+        let Err(err) = send(&client(&config), &config, &request()).await else {
+            panic!("expected the connection to fail");
+        };
+
+        assert!(matches!(err, E3Error::Connection(_)));
+        assert_eq!(err.status(), StatusCode::BAD_GATEWAY);
+    }
+}
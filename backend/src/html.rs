@@ -0,0 +1,57 @@
+use askama::Template;
+
+use crate::report::{Block, Report, RowStyle};
+
+/// Renders reports with `templates/report.html`, which follows the markup of the BLCC 5.3 HTML reports.
+#[derive(Template)]
+#[template(path = "report.html")]
+struct ReportTemplate<'a> {
+    report: &'a Report,
+}
+
+/// Renders a report to an HTML document.
+pub fn render(report: &Report) -> Result<String, askama::Error> {
+    ReportTemplate { report }.render()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::report::tests::federal_financed_report;
+    use crate::report::{ReportKind, Row, Section, Table};
+
+    #[test]
+    fn renders_the_legacy_markup() {
+        let html = render(&federal_financed_report(ReportKind::SummaryLcc)).unwrap();
+
+        assert!(html.contains("<title>NIST BLCC: Summary LCC</title>"));
+        assert!(html.contains("<h2>Alternative: Lighting Retrofit</h2>"));
+        assert!(html.contains("<h4>LCC Summary</h4>"));
+        assert!(html.contains("<th align=\"left\">Discount Rate:</th>\n        <td align=\"right\">3%</td>"));
+        assert!(html.contains(
+            "<tr class=\"total\">\n        <td align=\"left\">Total Life-Cycle Cost</td>\n        \
+             <td align=\"right\">$661,375</td>"
+        ));
+    }
+
+    #[test]
+    fn escapes_text() {
+        let report = Report {
+            title: "<script>".to_string(),
+            subtitle: String::new(),
+            sections: vec![Section {
+                heading: "OM&R".to_string(),
+                blocks: vec![Block::Table(Table {
+                    caption: None,
+                    columns: vec!["".to_string(), "Value".to_string()],
+                    rows: vec![Row { cells: vec!["\"Group\"".to_string()], style: RowStyle::Group }],
+                })],
+            }],
+        };
+        let html = render(&report).unwrap();
+
+        assert!(html.contains("<h1>&#60;script&#62;</h1>"));
+        assert!(html.contains("<h2>OM&#38;R</h2>"));
+        assert!(html.contains("<td align=\"left\" colspan=\"2\">&#34;Group&#34;</td>"));
+    }
+}
@@ -0,0 +1,1278 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::{Display, Formatter};
+
+use crate::conversion::{cost_per_liter, to_emissions_unit, to_liters};
+use crate::e3::{Measures, Optional, Output, Required};
+use crate::project::*;
+
+const DEFAULT_REAL_DISCOUNT_RATE: f64 = 0.03;
+const DEFAULT_INFLATION_RATE: f64 = 0.015;
+
+/// Errors that prevent a project from being analyzed.
+#[derive(Debug)]
+pub enum LccError {
+    MissingStudyPeriod,
+    NoAlternatives,
+    MissingCost { alternative: String, cost: ID },
+}
+
+impl Display for LccError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LccError::MissingStudyPeriod => write!(f, "Project does not have a study period"),
+            LccError::NoAlternatives => write!(f, "Project does not have any alternatives"),
+            LccError::MissingCost { alternative, cost } => {
+                write!(f, "Alternative {} references cost {} which does not exist", alternative, cost)
+            }
+        }
+    }
+}
+
+/*
+ * Internal benefit-cost representation. Every project cost is split into one or more of these flows, using the same
+ * rules the frontend uses to build an E3 request.
+ */
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum BcnType {
+    Cost,
+    Benefit,
+    NonMonetary,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum VarRate {
+    PercentDelta,
+    YearByYear,
+}
+
+#[derive(Clone)]
+struct Recur {
+    interval: i32,
+    end: Option<i32>,
+    var_rate: Option<VarRate>,
+    var_value: Vec<f64>,
+}
+
+impl Recur {
+    fn new(interval: i32) -> Self {
+        Recur { interval, end: None, var_rate: None, var_value: vec![] }
+    }
+
+    fn end(mut self, end: i32) -> Self {
+        self.end = Some(end);
+        self
+    }
+
+    fn rate(mut self, var_rate: VarRate, var_value: Vec<f64>) -> Self {
+        self.var_rate = Some(var_rate);
+        self.var_value = var_value;
+        self
+    }
+}
+
+#[derive(Clone)]
+struct Bcn {
+    bcn_type: BcnType,
+    invest: bool,
+    real: bool,
+    initial_occurrence: i32,
+    recur: Option<Recur>,
+    quantity: f64,
+    quantity_value: f64,
+    quantity_var_rate: Option<VarRate>,
+    quantity_var_value: Vec<f64>,
+    quantity_unit: Option<String>,
+    timestep_offset: Option<f64>,
+    tags: Vec<String>,
+}
+
+impl Bcn {
+    fn new(bcn_type: BcnType, initial_occurrence: i32) -> Self {
+        Bcn {
+            bcn_type,
+            invest: false,
+            real: false,
+            initial_occurrence,
+            recur: None,
+            quantity: 1.0,
+            quantity_value: 0.0,
+            quantity_var_rate: None,
+            quantity_var_value: vec![],
+            quantity_unit: None,
+            timestep_offset: None,
+            tags: vec![],
+        }
+    }
+
+    fn invest(mut self) -> Self {
+        self.invest = true;
+        self
+    }
+
+    fn quantity(mut self, quantity: f64, value: f64) -> Self {
+        self.quantity = quantity;
+        self.quantity_value = value;
+        self
+    }
+
+    fn unit(mut self, unit: &str) -> Self {
+        self.quantity_unit = Some(unit.to_string()).filter(|unit| !unit.is_empty());
+        self
+    }
+
+    fn recur(mut self, recur: Recur) -> Self {
+        self.recur = Some(recur);
+        self
+    }
+
+    fn tag<T: Into<String>>(mut self, tags: impl IntoIterator<Item = T>) -> Self {
+        self.tags.extend(tags.into_iter().map(Into::into).filter(|tag: &String| !tag.is_empty()));
+        self
+    }
+
+    fn occurs(&self, t: i32, study_period: i32) -> bool {
+        match &self.recur {
+            None => t == self.initial_occurrence,
+            Some(recur) => {
+                t >= self.initial_occurrence
+                    && (t - self.initial_occurrence) % recur.interval.max(1) == 0
+                    && t <= recur.end.unwrap_or(study_period)
+            }
+        }
+    }
+}
+
+/// Returns the multiplier for timestep `t` given a variable rate definition. A single value is treated as a constant
+/// rate for every timestep.
+fn multiplier(var_rate: Option<VarRate>, values: &[f64], t: i32) -> f64 {
+    let rate_at = |s: usize| -> Option<f64> {
+        if values.len() == 1 {
+            values.first().copied()
+        } else {
+            values.get(s).copied()
+        }
+    };
+
+    match var_rate {
+        None => 1.0,
+        _ if values.is_empty() => 1.0,
+        Some(VarRate::PercentDelta) => {
+            (1..=t.max(0) as usize).fold(1.0, |acc, s| acc * (1.0 + rate_at(s).unwrap_or(0.0)))
+        }
+        Some(VarRate::YearByYear) => rate_at(t.max(0) as usize).or_else(|| values.last().copied()).unwrap_or(1.0),
+    }
+}
+
+/// Settings shared by every flow in the analysis.
+struct Analysis {
+    study_period: i32,
+    discount_rate: f64,
+    inflation_rate: f64,
+    reinvest_rate: f64,
+    nominal: bool,
+    timestep_offset: f64,
+}
+
+impl Analysis {
+    fn from_project(project: &Project) -> Result<Self, LccError> {
+        let study_period = project.study_period.ok_or(LccError::MissingStudyPeriod)?;
+        let inflation_rate = project.inflation_rate.unwrap_or(DEFAULT_INFLATION_RATE);
+        let nominal = project.dollar_method == DollarMethod::Current;
+
+        Ok(Analysis {
+            study_period,
+            discount_rate: discount_rate(project),
+            inflation_rate,
+            // The frontend uses the inflation rate as the reinvestment rate, or zero if the project does not set one
+            reinvest_rate: project.inflation_rate.unwrap_or(0.0),
+            nominal,
+            timestep_offset: match project.discounting_method {
+                Some(DiscountingMethod::MidYear) => 0.5,
+                _ => 0.0,
+            },
+        })
+    }
+
+    fn length(&self) -> usize {
+        self.study_period.max(0) as usize + 1
+    }
+
+    fn discount_factor(&self, t: i32, offset: Option<f64>) -> f64 {
+        let exponent = (t as f64 - offset.unwrap_or(self.timestep_offset)).max(0.0);
+        (1.0 + self.discount_rate).powf(-exponent)
+    }
+}
+
+/// The discount rate the analysis uses: the real rate for constant dollar projects and the nominal rate for current
+/// dollar projects, falling back to the defaults when the project does not set them.
+pub fn discount_rate(project: &Project) -> f64 {
+    let real = project.real_discount_rate.unwrap_or(DEFAULT_REAL_DISCOUNT_RATE);
+
+    match project.dollar_method {
+        DollarMethod::Current => project.nominal_discount_rate.unwrap_or_else(|| {
+            calculate_nominal_discount_rate(real, project.inflation_rate.unwrap_or(DEFAULT_INFLATION_RATE))
+        }),
+        DollarMethod::Constant => real,
+    }
+}
+
+/// Calculates the nominal discount rate from the given real and inflation rates.
+pub fn calculate_nominal_discount_rate(real: f64, inflation: f64) -> f64 {
+    (1.0 + real) * (1.0 + inflation) - 1.0
+}
+
+/// The evaluated yearly values of a single flow.
+struct Flow {
+    bcn_type: BcnType,
+    invest: bool,
+    quantity: Vec<f64>,
+    non_discounted: Vec<f64>,
+    discounted: Vec<f64>,
+}
+
+fn evaluate(bcn: &Bcn, analysis: &Analysis) -> Flow {
+    let length = analysis.length();
+    let mut flow = Flow {
+        bcn_type: bcn.bcn_type,
+        invest: bcn.invest,
+        quantity: vec![0.0; length],
+        non_discounted: vec![0.0; length],
+        discounted: vec![0.0; length],
+    };
+
+    for t in 0..=analysis.study_period {
+        if !bcn.occurs(t, analysis.study_period) {
+            continue;
+        }
+
+        let quantity = bcn.quantity * multiplier(bcn.quantity_var_rate, &bcn.quantity_var_value, t);
+        let mut value = bcn.quantity_value;
+        if let Some(recur) = &bcn.recur {
+            value *= multiplier(recur.var_rate, &recur.var_value, t);
+        }
+        if bcn.real && analysis.nominal {
+            value *= (1.0 + analysis.inflation_rate).powi(t);
+        }
+
+        let i = t as usize;
+        let amount = quantity * value;
+        flow.quantity[i] = quantity;
+        flow.non_discounted[i] = amount;
+        // Non-monetary flows (emissions, user defined units) are never discounted
+        flow.discounted[i] = match bcn.bcn_type {
+            BcnType::NonMonetary => amount,
+            _ => amount * analysis.discount_factor(t, bcn.timestep_offset),
+        };
+    }
+
+    flow
+}
+
+/// Runs a life-cycle cost analysis on the given project. `emissions` is the yearly emissions rate for the project
+/// location starting at the release year, as returned by the emissions endpoint.
+pub fn analyze(data: &ProjectData, emissions: Option<&[f64]>) -> Result<Output, LccError> {
+    let project = &data.project;
+    let analysis = Analysis::from_project(project)?;
+
+    if data.alternatives.is_empty() {
+        return Err(LccError::NoAlternatives);
+    }
+
+    let costs: HashMap<ID, &Cost> = data.costs.iter().map(|cost| (cost.id(), cost)).collect();
+
+    let baseline_index = data
+        .alternatives
+        .iter()
+        .position(|alternative| alternative.baseline.unwrap_or(false))
+        .unwrap_or(0);
+
+    let mut results = Vec::with_capacity(data.alternatives.len());
+    for (index, alternative) in data.alternatives.iter().enumerate() {
+        let alt_id = alternative.id.unwrap_or(index as ID);
+
+        let mut bcns = vec![];
+        for id in alternative.costs.iter() {
+            let cost = costs.get(id).ok_or_else(|| LccError::MissingCost {
+                alternative: alternative.name.clone(),
+                cost: *id,
+            })?;
+            bcns.extend(cost_to_bcns(project, cost, analysis.study_period, emissions));
+        }
+
+        results.push(AlternativeResult::new(alt_id, &bcns, &analysis));
+    }
+
+    let baseline = &results[baseline_index];
+    let measure = results
+        .iter()
+        .enumerate()
+        .map(|(index, result)| result.measures(if index == baseline_index { None } else { Some(baseline) }, &analysis))
+        .collect();
+
+    Ok(Output {
+        optional: results.iter().flat_map(|result| result.optionals.clone()).collect(),
+        required: results.iter().map(|result| result.required.clone()).collect(),
+        measure,
+    })
+}
+
+/// All the summed flows for a single alternative.
+struct AlternativeResult {
+    alt_id: ID,
+    optionals: Vec<Optional>,
+    required: Required,
+}
+
+fn add(target: &mut [f64], values: &[f64]) {
+    target.iter_mut().zip(values).for_each(|(target, value)| *target += value);
+}
+
+fn sum(values: &[f64]) -> f64 {
+    values.iter().sum()
+}
+
+fn finite(value: f64) -> Option<f64> {
+    Some(value).filter(|value| value.is_finite())
+}
+
+impl AlternativeResult {
+    fn new(alt_id: ID, bcns: &[Bcn], analysis: &Analysis) -> Self {
+        let length = analysis.length();
+        let zero = || vec![0.0; length];
+
+        let mut required = Required {
+            alt_id,
+            total_costs_non_discounted: zero(),
+            total_costs_discounted: zero(),
+            total_benefits_non_discounted: zero(),
+            total_benefits_discounted: zero(),
+            total_costs_non_discounted_invest: zero(),
+            total_costs_discounted_invest: zero(),
+            total_benefits_non_discounted_invest: zero(),
+            total_benefits_discounted_invest: zero(),
+            total_costs_non_discounted_non_invest: zero(),
+            total_costs_discounted_non_invest: zero(),
+            total_benefits_non_discounted_non_invest: zero(),
+            total_benefits_discounted_non_invest: zero(),
+            total_costs_non_discounted_direct: zero(),
+            total_costs_discounted_direct: zero(),
+            total_benefits_non_discounted_direct: zero(),
+            total_benefits_discounted_direct: zero(),
+            total_costs_non_discounted_indirect: zero(),
+            total_costs_discounted_indirect: zero(),
+            total_benefits_non_discounted_indirect: zero(),
+            total_benefits_discounted_indirect: zero(),
+            total_costs_non_discounted_external: zero(),
+            total_costs_discounted_external: zero(),
+            total_benefits_non_discounted_external: zero(),
+            total_benefits_discounted_external: zero(),
+        };
+
+        // Keep tags in order of first appearance
+        let mut optionals: Vec<Optional> = vec![];
+
+        for bcn in bcns {
+            let flow = evaluate(bcn, analysis);
+
+            for tag in bcn.tags.iter() {
+                let optional = match optionals.iter_mut().position(|optional| &optional.tag == tag) {
+                    Some(i) => &mut optionals[i],
+                    None => {
+                        optionals.push(Optional {
+                            alt_id,
+                            tag: tag.clone(),
+                            total_tag_cashflow_discounted: zero(),
+                            total_tag_cashflow_non_discounted: zero(),
+                            total_tag_quantity: zero(),
+                            units: None,
+                        });
+                        optionals.last_mut().unwrap()
+                    }
+                };
+
+                add(&mut optional.total_tag_cashflow_discounted, &flow.discounted);
+                add(&mut optional.total_tag_cashflow_non_discounted, &flow.non_discounted);
+                add(&mut optional.total_tag_quantity, &flow.quantity);
+                if optional.units.is_none() {
+                    optional.units = bcn.quantity_unit.clone();
+                }
+            }
+
+            // Every flow BLCC creates is a direct flow, so the indirect and external totals stay at zero
+            match flow.bcn_type {
+                BcnType::Cost => {
+                    add(&mut required.total_costs_non_discounted, &flow.non_discounted);
+                    add(&mut required.total_costs_discounted, &flow.discounted);
+                    add(&mut required.total_costs_non_discounted_direct, &flow.non_discounted);
+                    add(&mut required.total_costs_discounted_direct, &flow.discounted);
+                    if flow.invest {
+                        add(&mut required.total_costs_non_discounted_invest, &flow.non_discounted);
+                        add(&mut required.total_costs_discounted_invest, &flow.discounted);
+                    } else {
+                        add(&mut required.total_costs_non_discounted_non_invest, &flow.non_discounted);
+                        add(&mut required.total_costs_discounted_non_invest, &flow.discounted);
+                    }
+                }
+                BcnType::Benefit => {
+                    add(&mut required.total_benefits_non_discounted, &flow.non_discounted);
+                    add(&mut required.total_benefits_discounted, &flow.discounted);
+                    add(&mut required.total_benefits_non_discounted_direct, &flow.non_discounted);
+                    add(&mut required.total_benefits_discounted_direct, &flow.discounted);
+                    if flow.invest {
+                        add(&mut required.total_benefits_non_discounted_invest, &flow.non_discounted);
+                        add(&mut required.total_benefits_discounted_invest, &flow.discounted);
+                    } else {
+                        add(&mut required.total_benefits_non_discounted_non_invest, &flow.non_discounted);
+                        add(&mut required.total_benefits_discounted_non_invest, &flow.discounted);
+                    }
+                }
+                BcnType::NonMonetary => {}
+            }
+        }
+
+        AlternativeResult { alt_id, optionals, required }
+    }
+
+    fn quantity_sum(&self) -> BTreeMap<String, f64> {
+        self.optionals
+            .iter()
+            .map(|optional| (optional.tag.clone(), sum(&optional.total_tag_quantity)))
+            .collect()
+    }
+
+    /// Yearly net cash flow: costs minus benefits.
+    fn net_flow(&self, discounted: bool) -> Vec<f64> {
+        let (costs, benefits) = if discounted {
+            (&self.required.total_costs_discounted, &self.required.total_benefits_discounted)
+        } else {
+            (&self.required.total_costs_non_discounted, &self.required.total_benefits_non_discounted)
+        };
+
+        costs.iter().zip(benefits).map(|(cost, benefit)| cost - benefit).collect()
+    }
+
+    /// Adjusted internal rate of return the way E3 computes it: the non-investment savings compounded to the end of
+    /// the study period against the additional investment discounted to the start, both at the reinvestment rate.
+    fn airr(&self, baseline: &AlternativeResult, analysis: &Analysis) -> Option<f64> {
+        if analysis.study_period <= 0 {
+            return None;
+        }
+
+        let base = &baseline.required;
+        let required = &self.required;
+        let growth = 1.0 + analysis.reinvest_rate;
+
+        let terminal_savings: f64 = base
+            .total_costs_non_discounted_non_invest
+            .iter()
+            .zip(&required.total_costs_non_discounted_non_invest)
+            .enumerate()
+            .map(|(t, (base, alt))| (base - alt) * growth.powi(analysis.study_period - t as i32))
+            .sum();
+        let present_investment: f64 = required
+            .total_costs_non_discounted_invest
+            .iter()
+            .zip(&base.total_costs_non_discounted_invest)
+            .enumerate()
+            .map(|(t, (alt, base))| (alt - base) * growth.powi(-(t as i32)))
+            .sum();
+
+        let ratio = terminal_savings / present_investment;
+        if ratio <= 0.0 {
+            return None;
+        }
+
+        finite(ratio.powf(1.0 / analysis.study_period as f64) - 1.0)
+    }
+
+    fn measures(&self, baseline: Option<&AlternativeResult>, analysis: &Analysis) -> Measures {
+        let required = &self.required;
+        let total_costs = sum(&required.total_costs_discounted);
+        let total_benefits = sum(&required.total_benefits_discounted);
+        let total_costs_invest = sum(&required.total_costs_discounted_invest);
+        let total_cost_non_invest = sum(&required.total_costs_discounted_non_invest);
+
+        let quantity_sum = self.quantity_sum();
+
+        let mut measures = Measures {
+            alt_id: self.alt_id,
+            total_benefits,
+            total_costs,
+            total_costs_invest,
+            total_cost_non_invest,
+            total_tag_flows: self
+                .optionals
+                .iter()
+                .map(|optional| (optional.tag.clone(), sum(&optional.total_tag_cashflow_discounted)))
+                .collect(),
+            net_benefits: None,
+            net_savings: None,
+            sir: None,
+            irr: None,
+            airr: None,
+            dpp: None,
+            spp: None,
+            bcr: None,
+            quantity_sum: quantity_sum.clone(),
+            quantity_units: self
+                .optionals
+                .iter()
+                .map(|optional| (optional.tag.clone(), optional.units.clone()))
+                .collect(),
+            marr: None,
+            delta_quantity: None,
+            ns_percent_quantity: None,
+            ns_delta_quantity: None,
+            ns_elasticity_quantity: None,
+        };
+
+        // The baseline is not compared against anything
+        let Some(baseline) = baseline else {
+            return measures;
+        };
+
+        let base_required = &baseline.required;
+        let net_savings = sum(&base_required.total_costs_discounted) - total_costs;
+        let net_benefits = net_savings + total_benefits - sum(&base_required.total_benefits_discounted);
+
+        let delta_invest = total_costs_invest - sum(&base_required.total_costs_discounted_invest);
+        let non_invest_savings = sum(&base_required.total_costs_discounted_non_invest) - total_cost_non_invest;
+        let benefit_gain = total_benefits - sum(&base_required.total_benefits_discounted);
+        let sir = finite(non_invest_savings / delta_invest);
+
+        measures.net_savings = Some(net_savings);
+        measures.net_benefits = Some(net_benefits);
+        measures.sir = sir;
+        measures.bcr = finite((non_invest_savings + benefit_gain) / delta_invest);
+        measures.airr = sir.filter(|sir| *sir > 0.0).and_then(|_| self.airr(baseline, analysis));
+
+        // Yearly savings of this alternative relative to the baseline
+        let savings = |discounted: bool| -> Vec<f64> {
+            baseline
+                .net_flow(discounted)
+                .iter()
+                .zip(self.net_flow(discounted))
+                .map(|(base, alt)| base - alt)
+                .collect()
+        };
+        measures.spp = payback(&savings(false));
+        measures.dpp = payback(&savings(true));
+        measures.irr = irr(&savings(false));
+
+        let base_quantity = baseline.quantity_sum();
+        let mut delta_quantity = BTreeMap::new();
+        let mut ns_percent_quantity = BTreeMap::new();
+        let mut ns_delta_quantity = BTreeMap::new();
+        let mut ns_elasticity_quantity = BTreeMap::new();
+
+        for (tag, quantity) in quantity_sum.iter() {
+            let base = base_quantity.get(tag).copied().unwrap_or(0.0);
+            // Tags missing from the baseline have no meaningful ratio, so they are reported as null
+            let ratio = finite(quantity / base);
+            let delta = quantity - base;
+
+            delta_quantity.insert(tag.clone(), delta);
+            ns_percent_quantity.insert(tag.clone(), ratio.and_then(|ratio| finite(net_savings / ratio)));
+            ns_delta_quantity.insert(tag.clone(), finite(net_savings / delta));
+            ns_elasticity_quantity
+                .insert(tag.clone(), ratio.and_then(|ratio| finite(net_savings / total_costs / ratio)));
+        }
+
+        measures.delta_quantity = Some(delta_quantity);
+        measures.ns_percent_quantity = Some(ns_percent_quantity);
+        measures.ns_delta_quantity = Some(ns_delta_quantity);
+        measures.ns_elasticity_quantity = Some(ns_elasticity_quantity);
+
+        measures
+    }
+}
+
+/// The first year in which the cumulative savings are no longer negative.
+fn payback(savings: &[f64]) -> Option<f64> {
+    let mut cumulative = 0.0;
+    for (year, value) in savings.iter().enumerate() {
+        cumulative += value;
+        if cumulative >= 0.0 {
+            return Some(year as f64);
+        }
+    }
+
+    None
+}
+
+/// Finds the internal rate of return of the given yearly savings by bisection. Returns `None` if the net present value
+/// never changes sign, in which case there is no IRR.
+fn irr(savings: &[f64]) -> Option<f64> {
+    let npv = |rate: f64| -> f64 {
+        savings
+            .iter()
+            .enumerate()
+            .map(|(t, value)| value / (1.0 + rate).powi(t as i32))
+            .sum()
+    };
+
+    let (mut low, mut high) = (-0.99, 10.0);
+    let (mut npv_low, npv_high) = (npv(low), npv(high));
+    if !npv_low.is_finite() || !npv_high.is_finite() || npv_low.signum() == npv_high.signum() {
+        return None;
+    }
+
+    for _ in 0..200 {
+        let middle = (low + high) / 2.0;
+        let npv_middle = npv(middle);
+
+        if npv_middle.abs() < 1e-9 {
+            return Some(middle);
+        }
+
+        if npv_middle.signum() == npv_low.signum() {
+            low = middle;
+            npv_low = npv_middle;
+        } else {
+            high = middle;
+        }
+    }
+
+    Some((low + high) / 2.0)
+}
+
+/*
+ * Conversion of project costs into flows.
+ */
+
+fn rate_array(value: &NumberOrArray) -> Vec<f64> {
+    match value {
+        NumberOrArray::Number(rate) => vec![*rate],
+        // Add 0 for the initial year, so year 1 is the first year of the analysis
+        NumberOrArray::Array(rates) => [vec![0.0], rates.clone()].concat(),
+    }
+}
+
+fn index_array(value: &NumberOrArray) -> Vec<f64> {
+    match value {
+        NumberOrArray::Number(index) => vec![*index],
+        NumberOrArray::Array(indices) => indices.clone(),
+    }
+}
+
+fn to_nominal(project: &Project, rates: Vec<f64>) -> Vec<f64> {
+    match project.dollar_method {
+        DollarMethod::Current => {
+            let inflation = project.inflation_rate.unwrap_or(DEFAULT_INFLATION_RATE);
+            rates.into_iter().map(|rate| calculate_nominal_discount_rate(rate, inflation)).collect()
+        }
+        DollarMethod::Constant => rates,
+    }
+}
+
+fn savings_sign(base: &BaseCost) -> f64 {
+    if base.is_savings() {
+        -1.0
+    } else {
+        1.0
+    }
+}
+
+fn cost_to_bcns(project: &Project, cost: &Cost, study_period: i32, emissions: Option<&[f64]>) -> Vec<Bcn> {
+    match cost {
+        Cost::Capital(cost) => capital_cost_to_bcns(project, cost, study_period),
+        Cost::Energy(cost) => energy_cost_to_bcns(project, cost, emissions),
+        Cost::Water(cost) => water_cost_to_bcns(project, cost),
+        Cost::ReplacementCapital(cost) => replacement_capital_cost_to_bcns(project, cost, study_period),
+        Cost::Omr(cost) => omr_cost_to_bcns(project, cost),
+        Cost::ImplementationContract(cost) => implementation_contract_cost_to_bcns(project, cost),
+        Cost::RecurringContract(cost) => recurring_contract_cost_to_bcns(project, cost),
+        Cost::Other(cost) => other_cost_to_bcns(project, cost),
+        Cost::OtherNonMonetary(cost) => other_non_monetary_cost_to_bcns(project, cost),
+        Cost::Ercip(cost) => ercip_cost_to_bcns(cost),
+    }
+}
+
+fn capital_cost_to_bcns(project: &Project, cost: &CapitalCost, study_period: i32) -> Vec<Bcn> {
+    let tags = ["Initial Investment".to_string(), "LCC".to_string(), cost.base.id.to_string()];
+    let sign = savings_sign(&cost.base);
+    let initial_cost = cost.initial_cost.unwrap_or(0.0);
+
+    let mut result: Vec<Bcn> = match &cost.phase_in {
+        Some(phase_in) => {
+            let adjusted = initial_cost * (1.0 + cost.cost_adjustment.unwrap_or(0.0)).powi(phase_in.len() as i32);
+
+            phase_in
+                .iter()
+                .enumerate()
+                .map(|(i, portion)| {
+                    Bcn::new(BcnType::Cost, i as i32)
+                        .invest()
+                        .tag(tags.clone())
+                        .quantity(sign, adjusted * portion)
+                })
+                .collect()
+        }
+        None => vec![Bcn::new(BcnType::Cost, 0).invest().tag(tags).quantity(sign, initial_cost)],
+    };
+
+    if let Some(first) = result.first_mut() {
+        *first = apply_rate_of_change_non_recurring(first.clone(), 0, &cost.rate_of_change_value, project);
+    }
+
+    if let Some(residual_value) = &cost.residual_value {
+        result.push(residual_value_bcn(
+            project,
+            0,
+            cost.expected_life,
+            &cost.rate_of_change_value,
+            initial_cost + cost.amount_financed.unwrap_or(0.0),
+            residual_value,
+            study_period,
+        ));
+    }
+
+    result
+}
+
+fn ercip_cost_to_bcns(cost: &ErcipCost) -> Vec<Bcn> {
+    let tag = "Initial Investment";
+    let id = cost.base.id.to_string();
+
+    [
+        (cost.construction_cost, true),
+        (cost.sioh, true),
+        (cost.design_cost, true),
+        (cost.salvage_value, false),
+        (cost.public_utility_rebate, false),
+        (cost.cybersecurity, false),
+    ]
+    .into_iter()
+    .map(|(value, is_cost)| {
+        let mut bcn = Bcn::new(BcnType::Cost, 0)
+            .invest()
+            .tag([tag, "LCC", id.as_str()])
+            .quantity(if is_cost { 1.0 } else { -1.0 }, value);
+        bcn.real = true;
+        bcn
+    })
+    .collect()
+}
+
+fn energy_cost_recurrence(project: &Project, cost: &EnergyCost) -> Recur {
+    // We are using custom escalation for this cost.
+    if let Some(escalation) = &cost.escalation {
+        let rates = match escalation {
+            NumberOrArray::Array(rates) => [vec![0.0], to_nominal(project, rates.clone())].concat(),
+            NumberOrArray::Number(rate) => {
+                let rate = to_nominal(project, vec![*rate])[0];
+                [vec![0.0], vec![rate; project.study_period.unwrap_or(0).max(0) as usize]].concat()
+            }
+        };
+
+        return Recur::new(1).rate(VarRate::PercentDelta, rates);
+    }
+
+    // We are not using custom escalation but project escalation rates exist.
+    if let Some(project_rates) = &project.project_escalation_rates {
+        let sector = cost.customer_sector.map(|sector| sector.label());
+        let escalation = project_rates
+            .iter()
+            .filter(|rate| Some(rate.sector.as_str()) == sector)
+            .map(|rate| {
+                match cost.fuel_type {
+                    FuelType::Electricity => rate.electricity,
+                    FuelType::Propane => rate.propane,
+                    FuelType::NaturalGas => rate.natural_gas,
+                    FuelType::Coal => rate.coal,
+                    FuelType::DistillateOil => rate.distillate_fuel_oil,
+                    FuelType::ResidualOil => rate.residual_fuel_oil,
+                    FuelType::Other => rate.natural_gas,
+                }
+                .unwrap_or(0.0)
+            })
+            .collect();
+
+        return Recur::new(1).rate(VarRate::PercentDelta, [vec![0.0], to_nominal(project, escalation)].concat());
+    }
+
+    // There are no custom escalation nor project escalation rates.
+    Recur::new(1)
+}
+
+fn energy_cost_to_bcns(project: &Project, cost: &EnergyCost, emissions: Option<&[f64]>) -> Vec<Bcn> {
+    let initial = project.construction_period + 1;
+    let id = cost.base.id.to_string();
+    let name_tag = |name: &str| format!("{} - {}", name, id);
+
+    let mut main = Bcn::new(BcnType::Cost, initial)
+        .tag(["Energy", cost.fuel_type.label(), cost.unit.label(), "LCC", id.as_str()])
+        .recur(energy_cost_recurrence(project, cost))
+        .quantity(savings_sign(&cost.base) * cost.annual_consumption, cost.cost_per_unit)
+        .unit(cost.unit.label());
+
+    if let Some(use_index) = &cost.use_index {
+        main.quantity_var_rate = Some(VarRate::YearByYear);
+        main.quantity_var_value = index_array(use_index);
+    }
+
+    if let Some(sector) = cost.customer_sector {
+        main = main.tag([sector.label()]);
+    }
+
+    let mut result = vec![main];
+
+    if let Some(demand_charge) = cost.demand_charge {
+        result.push(
+            Bcn::new(BcnType::Cost, initial)
+                .tag(["Demand Charge".to_string(), name_tag("Demand Charge"), "LCC".to_string()])
+                .recur(energy_cost_recurrence(project, cost))
+                .quantity(1.0, demand_charge),
+        );
+    }
+
+    if let Some(rebate) = cost.rebate {
+        result.push(
+            Bcn::new(BcnType::Benefit, initial)
+                .tag(["Rebate".to_string(), name_tag("Rebate"), "LCC".to_string()])
+                .recur(energy_cost_recurrence(project, cost))
+                .quantity(1.0, -rebate),
+        );
+    }
+
+    // Convert consumption to the emissions unit. Usually MJ, but can also be MWh for electricity.
+    let converted = to_emissions_unit(cost.fuel_type, cost.unit, cost.annual_consumption);
+    let (Some(converted), Some(emissions)) = (converted, emissions) else {
+        return result;
+    };
+
+    let rates = cost.emissions.as_deref().unwrap_or(emissions);
+    if !rates.is_empty() {
+        let mut bcn = Bcn::new(BcnType::NonMonetary, initial)
+            .tag(["Emissions".to_string(), format!("{} Emissions", cost.fuel_type.label()), "kg CO2e".to_string()])
+            .recur(Recur::new(1))
+            .quantity(1.0, 1.0)
+            .unit("kg CO2e");
+        bcn.quantity_var_rate = Some(VarRate::YearByYear);
+        bcn.quantity_var_value = rates.iter().map(|rate| rate * converted).collect();
+        result.push(bcn);
+    }
+
+    result
+}
+
+fn water_cost_to_bcns(project: &Project, cost: &WaterCost) -> Vec<Bcn> {
+    let recur = match &cost.escalation {
+        Some(escalation) => Recur::new(1).rate(VarRate::PercentDelta, rate_array(escalation)),
+        None => Recur::new(1),
+    };
+    let id = cost.base.id.to_string();
+    let sign = savings_sign(&cost.base);
+
+    let usage_bcn = |usage: &SeasonUsage, category: &str| {
+        let mut bcn = Bcn::new(BcnType::Cost, project.construction_period + 1)
+            .invest()
+            .tag([cost.unit.label(), "LCC", category, "Water", id.as_str()])
+            .recur(recur.clone())
+            .quantity(sign * to_liters(usage.amount, cost.unit), cost_per_liter(usage.cost_per_unit, cost.unit))
+            .unit("Liter");
+
+        if let Some(use_index) = &cost.use_index {
+            bcn.quantity_var_rate = Some(VarRate::YearByYear);
+            bcn.quantity_var_value = index_array(use_index);
+        }
+
+        bcn
+    };
+
+    cost.usage
+        .iter()
+        .map(|usage| usage_bcn(usage, "Usage"))
+        .chain(cost.disposal.iter().map(|disposal| usage_bcn(disposal, "Disposal")))
+        .collect()
+}
+
+fn replacement_capital_cost_to_bcns(project: &Project, cost: &ReplacementCapitalCost, study_period: i32) -> Vec<Bcn> {
+    let id = cost.base.id.to_string();
+    let initial_cost = cost.initial_cost.unwrap_or(-1.0);
+
+    let bcn = Bcn::new(BcnType::Cost, cost.initial_occurrence + project.construction_period)
+        .invest()
+        .tag(["Replacement Capital", "LCC", id.as_str()])
+        .quantity(savings_sign(&cost.base), initial_cost);
+    let bcn = apply_rate_of_change_non_recurring(bcn, cost.initial_occurrence, &cost.rate_of_change_value, project);
+
+    match &cost.residual_value {
+        Some(residual_value) => vec![
+            bcn,
+            residual_value_bcn(
+                project,
+                cost.initial_occurrence,
+                cost.expected_life,
+                &cost.rate_of_change_value,
+                initial_cost,
+                residual_value,
+                study_period,
+            ),
+        ],
+        None => vec![bcn],
+    }
+}
+
+fn omr_cost_to_bcns(project: &Project, cost: &OmrCost) -> Vec<Bcn> {
+    let id = cost.base.id.to_string();
+    let bcn = Bcn::new(BcnType::Cost, cost.initial_occurrence + project.construction_period)
+        .tag(["OMR", "LCC", id.as_str()])
+        .quantity(savings_sign(&cost.base), cost.initial_cost.unwrap_or(-1.0));
+
+    let recurring = cost
+        .recurring
+        .as_ref()
+        .and_then(|recurring| recurring.rate_of_recurrence)
+        .is_some_and(|rate| rate > 0);
+
+    if recurring {
+        let bcn = bcn.tag(["OMR Recurring"]);
+        vec![apply_rate_of_change_recurring(
+            bcn,
+            cost.initial_occurrence,
+            &cost.recurring,
+            &cost.rate_of_change_value,
+            project,
+        )]
+    } else {
+        let bcn = bcn.tag(["OMR Non-Recurring"]);
+        vec![apply_rate_of_change_non_recurring(bcn, cost.initial_occurrence, &cost.rate_of_change_value, project)]
+    }
+}
+
+fn implementation_contract_cost_to_bcns(project: &Project, cost: &ImplementationContractCost) -> Vec<Bcn> {
+    let id = cost.base.id.to_string();
+    let bcn = Bcn::new(BcnType::Cost, cost.initial_occurrence + project.construction_period)
+        .invest()
+        .tag(["Implementation Contract Cost", "LCC", id.as_str()])
+        .quantity(savings_sign(&cost.base), cost.cost.unwrap_or(-1.0));
+
+    vec![apply_rate_of_change_non_recurring(bcn, cost.initial_occurrence, &cost.rate_of_change_value, project)]
+}
+
+fn recurring_contract_cost_to_bcns(project: &Project, cost: &RecurringContractCost) -> Vec<Bcn> {
+    let id = cost.base.id.to_string();
+    let bcn = Bcn::new(BcnType::Cost, cost.initial_occurrence + project.construction_period)
+        .invest()
+        .tag(["Recurring Contract Cost", "LCC", id.as_str()])
+        .quantity(savings_sign(&cost.base), cost.initial_cost.unwrap_or(-1.0));
+
+    vec![apply_rate_of_change_recurring(
+        bcn,
+        cost.initial_occurrence,
+        &cost.recurring,
+        &cost.rate_of_change_value,
+        project,
+    )]
+}
+
+fn other_cost_to_bcns(project: &Project, cost: &OtherCost) -> Vec<Bcn> {
+    let id = cost.base.id.to_string();
+    let unit = cost.unit.as_ref().map(|unit| unit.label().to_string()).unwrap_or_default();
+    let bcn_type = if cost.base.is_savings() { BcnType::Benefit } else { BcnType::Cost };
+
+    let mut bcn = Bcn::new(bcn_type, cost.initial_occurrence + project.construction_period)
+        .invest()
+        .tag(["Other", "LCC", id.as_str()])
+        .tag(cost.tags.clone().unwrap_or_default())
+        .tag([unit.as_str()])
+        .quantity(savings_sign(&cost.base) * cost.number_of_units, cost.value_per_unit)
+        .unit(&unit);
+
+    apply_rate_of_change_units(&mut bcn, &cost.rate_of_change_units);
+    vec![apply_rate_of_change_recurring(
+        bcn,
+        cost.initial_occurrence,
+        &cost.recurring,
+        &cost.rate_of_change_value,
+        project,
+    )]
+}
+
+fn other_non_monetary_cost_to_bcns(project: &Project, cost: &OtherNonMonetaryCost) -> Vec<Bcn> {
+    let id = cost.base.id.to_string();
+    let unit = cost.unit.as_ref().map(|unit| unit.label().to_string()).unwrap_or_default();
+
+    let mut bcn = Bcn::new(BcnType::NonMonetary, cost.initial_occurrence + project.construction_period)
+        .tag(["Other Non-Monetary", id.as_str()])
+        .tag([unit.as_str()])
+        .tag(cost.tags.clone().unwrap_or_default())
+        .quantity(savings_sign(&cost.base) * cost.number_of_units, 1.0)
+        .unit(&unit);
+
+    apply_rate_of_change_units(&mut bcn, &cost.rate_of_change_units);
+    bcn.recur = Some(recurrence_for(cost.initial_occurrence, &cost.recurring, project));
+    vec![bcn]
+}
+
+fn recurrence_for(initial_occurrence: i32, recurring: &Option<Recurring>, project: &Project) -> Recur {
+    let max_time_length = project.construction_period + project.study_period.unwrap_or(50) + 1;
+
+    match recurring {
+        Some(recurring) => Recur::new(recurring.rate_of_recurrence.unwrap_or(1)).end(
+            recurring.duration.unwrap_or(max_time_length) + initial_occurrence + project.construction_period - 1,
+        ),
+        None => Recur::new(50).end(initial_occurrence + project.construction_period + 1),
+    }
+}
+
+fn apply_rate_of_change_non_recurring(
+    bcn: Bcn,
+    initial_occurrence: i32,
+    rate_of_change: &Option<NumberOrArray>,
+    project: &Project,
+) -> Bcn {
+    let recur = Recur::new(50).end(initial_occurrence + project.construction_period + 1);
+    apply_rate_of_change_value(bcn, recur, rate_of_change, project)
+}
+
+fn apply_rate_of_change_recurring(
+    bcn: Bcn,
+    initial_occurrence: i32,
+    recurring: &Option<Recurring>,
+    rate_of_change: &Option<NumberOrArray>,
+    project: &Project,
+) -> Bcn {
+    let recur = recurrence_for(initial_occurrence, recurring, project);
+    apply_rate_of_change_value(bcn, recur, rate_of_change, project)
+}
+
+fn apply_rate_of_change_units(bcn: &mut Bcn, rate_of_change_units: &Option<NumberOrArray>) {
+    if let Some(rate_of_change_units) = rate_of_change_units {
+        bcn.quantity_var_rate = Some(VarRate::YearByYear);
+        bcn.quantity_var_value = index_array(rate_of_change_units);
+    }
+}
+
+fn apply_rate_of_change_value(
+    bcn: Bcn,
+    mut recur: Recur,
+    rate_of_change: &Option<NumberOrArray>,
+    project: &Project,
+) -> Bcn {
+    if let Some(rate_of_change) = rate_of_change {
+        recur = recur.rate(VarRate::PercentDelta, to_nominal(project, rate_array(rate_of_change)));
+    }
+
+    bcn.recur(recur)
+}
+
+fn residual_value_bcn(
+    project: &Project,
+    initial_occurrence: i32,
+    expected_life: Option<i32>,
+    rate_of_change: &Option<NumberOrArray>,
+    value: f64,
+    residual_value: &ResidualValue,
+    study_period: i32,
+) -> Bcn {
+    let quantity = match residual_value.approach {
+        DollarOrPercent::Percent => match rate_of_change {
+            Some(NumberOrArray::Array(_)) => value * -residual_value.value,
+            Some(NumberOrArray::Number(rate)) => value * (1.0 + rate).powi(study_period) * -residual_value.value,
+            None => value * -residual_value.value,
+        },
+        DollarOrPercent::Dollar => -residual_value.value,
+    };
+
+    let occurrence = match expected_life {
+        Some(life) if life + initial_occurrence < study_period => life,
+        _ => study_period,
+    };
+
+    let rates = match rate_of_change {
+        Some(rate_of_change) => rate_array(rate_of_change),
+        None => vec![0.0],
+    };
+
+    let mut bcn = Bcn::new(BcnType::Cost, occurrence)
+        .tag(["LCC", "Residual Value"])
+        .quantity(quantity, 1.0);
+
+    if project.discounting_method == Some(DiscountingMethod::MidYear) {
+        bcn.timestep_offset = Some(0.0);
+    }
+
+    bcn.quantity_var_rate = Some(VarRate::PercentDelta);
+    bcn.quantity_var_value = to_nominal(project, rates);
+    bcn
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use serde_json::Value;
+
+    use super::*;
+
+    const TOLERANCE: f64 = 1e-6;
+
+    /// The reference result is stored as a JavaScript object literal, so the keys are quoted and trailing commas
+    /// dropped to read it as JSON.
+    pub fn reference_output() -> Value {
+        let source = include_str!("../../docs/FederalFinancedE3Result.ts");
+        let body = source
+            .trim_start_matches("const json = ")
+            .trim_end()
+            .trim_end_matches("export { json };")
+            .trim_end()
+            .trim_end_matches(';');
+
+        let mut json = String::with_capacity(body.len());
+        for line in body.lines() {
+            let trimmed = line.trim_start();
+            match trimmed.split_once(':') {
+                Some((key, rest)) if key.chars().all(|c| c.is_ascii_alphanumeric()) && !key.is_empty() => {
+                    json.push_str(&format!("\"{}\":{}", key, rest));
+                }
+                _ => json.push_str(trimmed),
+            }
+            json.push('\n');
+        }
+        let json = json.replace(",\n]", "\n]").replace(",\n}", "\n}");
+
+        serde_json::from_str(&json).expect("reference result should be valid JSON")
+    }
+
+    fn assert_close(actual: &[f64], expected: &[f64], what: &str) {
+        assert_eq!(actual.len(), expected.len(), "{} length", what);
+        for (year, (actual, expected)) in actual.iter().zip(expected).enumerate() {
+            let scale = expected.abs().max(1.0);
+            assert!((actual - expected).abs() / scale < TOLERANCE, "{} year {}: {} != {}", what, year, actual, expected);
+        }
+    }
+
+    fn assert_option_close(actual: Option<f64>, expected: Option<f64>, what: &str) {
+        match (actual, expected) {
+            (Some(actual), Some(expected)) => assert_close(&[actual], &[expected], what),
+            (actual, expected) => assert_eq!(actual, expected, "{}", what),
+        }
+    }
+
+    /// The numbers of a list field. E3 writes undefined values as strings, which are never expected in a list.
+    fn numbers(value: &Value, key: &str) -> Vec<f64> {
+        value[key]
+            .as_array()
+            .unwrap_or_else(|| panic!("{} should be a list", key))
+            .iter()
+            .map(|value| value.as_f64().unwrap_or(f64::NAN))
+            .collect()
+    }
+
+    /// E3 writes infinite measures as strings, which the engine leaves out.
+    fn number(value: &Value, key: &str) -> Option<f64> {
+        value[key].as_f64()
+    }
+
+    /// Finds the result of the same alternative, and tag if it has one, in a list of results.
+    fn matching<'a>(actual: &'a Value, expected: &Value) -> &'a Value {
+        actual
+            .as_array()
+            .and_then(|entries| {
+                entries.iter().find(|entry| entry["altId"] == expected["altId"] && entry["tag"] == expected["tag"])
+            })
+            .unwrap_or_else(|| panic!("missing result for alternative {} tag {}", expected["altId"], expected["tag"]))
+    }
+
+    /// The project the reference result was produced from: the lighting retrofit from the BLCC 5.3 federal financed
+    /// example, analyzed in constant dollars.
+    pub fn federal_financed_project() -> ProjectData {
+        serde_json::from_value(serde_json::json!({
+            "project": {
+                "id": 1,
+                "version": "1.0.0",
+                "name": "Lighting/Daylighting",
+                "analysisType": "Federal Analysis, Financed Project",
+                "dollarMethod": "Constant",
+                "case": "REF",
+                "studyPeriod": 15,
+                "constructionPeriod": 0,
+                "discountingMethod": "End of Year",
+                "realDiscountRate": 0.03,
+                "location": { "country": "United States of America", "state": "AZ" },
+                "alternatives": [0, 1],
+                "costs": [0, 1, 2, 3, 4, 5, 6],
+                "ghg": { "dataSource": "NIST NETL", "emissionsRateType": "Average" },
+                "releaseYear": 2024
+            },
+            "alternatives": [
+                { "id": 0, "name": "Existing", "baseline": true, "costs": [0, 1, 2] },
+                { "id": 1, "name": "Lighting Retrofit", "costs": [3, 4, 5, 6] }
+            ],
+            "costs": [
+                { "type": "Capital Investment Cost", "id": 0, "name": "Existing System", "initialCost": 0.0 },
+                {
+                    "type": "OMR", "id": 1, "name": "Existing System cost", "initialCost": 5600.0,
+                    "initialOccurrence": 1, "recurring": { "rateOfRecurrence": 1, "duration": 15 }
+                },
+                {
+                    "type": "Energy", "id": 2, "name": "Electricity", "fuelType": "Electricity",
+                    "customerSector": "Commercial", "costPerUnit": 0.046, "annualConsumption": 1082633.0, "unit": "kWh"
+                },
+                { "type": "Capital Investment Cost", "id": 3, "name": "New System", "initialCost": 0.0 },
+                {
+                    "type": "OMR", "id": 4, "name": "New System Post-Contract OM Costs", "initialCost": 3000.0,
+                    "initialOccurrence": 11, "recurring": { "rateOfRecurrence": 1, "duration": 5 }
+                },
+                {
+                    "type": "Energy", "id": 5, "name": "Electricity", "fuelType": "Electricity",
+                    "customerSector": "Commercial", "costPerUnit": 0.046, "annualConsumption": 206911.0, "unit": "kWh"
+                },
+                {
+                    "type": "Recurring Contract", "id": 6, "name": "Annual Contract Payment", "initialCost": 2.0,
+                    "initialOccurrence": 1, "recurring": { "rateOfRecurrence": 1 }
+                }
+            ]
+        }))
+        .expect("project should deserialize")
+    }
+
+    #[test]
+    fn federal_financed_matches_e3() {
+        let output = analyze(&federal_financed_project(), None).expect("project should analyze");
+        let actual = serde_json::to_value(&output).unwrap();
+        let expected = reference_output();
+
+        for expected in expected["optional"].as_array().unwrap() {
+            let actual = matching(&actual["optional"], expected);
+            let what = format!("alternative {} tag {}", expected["altId"], expected["tag"]);
+
+            for key in ["totalTagCashflowDiscounted", "totalTagCashflowNonDiscounted", "totalTagQuantity"] {
+                assert_close(&numbers(actual, key), &numbers(expected, key), &what);
+            }
+        }
+
+        for expected in expected["required"].as_array().unwrap() {
+            let actual = matching(&actual["required"], expected);
+            let what = format!("alternative {} required", expected["altId"]);
+
+            for key in [
+                "totalCostsDiscounted",
+                "totalCostsNonDiscounted",
+                "totalCostsDiscountedInvest",
+                "totalCostsDiscountedNonInvest",
+                "totalBenefitsDiscounted",
+            ] {
+                assert_close(&numbers(actual, key), &numbers(expected, key), &what);
+            }
+        }
+
+        for expected in expected["measure"].as_array().unwrap() {
+            let actual = matching(&actual["measure"], expected);
+            let what = |name: &str| format!("alternative {} {}", expected["altId"], name);
+
+            for key in ["totalCosts", "totalCostsInvest", "totalCostNonInvest"] {
+                assert_close(&[number(actual, key).unwrap()], &[number(expected, key).unwrap()], &what(key));
+            }
+            for key in ["netSavings", "sir", "airr", "dpp", "spp"] {
+                assert_option_close(number(actual, key), number(expected, key), &what(key));
+            }
+
+            for (tag, value) in expected["totalTagFlows"].as_object().unwrap() {
+                let flow = actual["totalTagFlows"][tag].as_f64().unwrap_or_default();
+                assert_close(&[flow], &[value.as_f64().unwrap()], &what(tag));
+            }
+        }
+    }
+}
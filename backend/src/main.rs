@@ -2,6 +2,8 @@ extern crate diesel;
 extern crate diesel_migrations;
 
 use crate::api::config_api;
+use crate::cache::E3Cache;
+use crate::e3::E3Config;
 use crate::paginated::config_paginated;
 use actix_cors::Cors;
 use actix_files::{Files, NamedFile};
@@ -10,7 +12,7 @@ use actix_web::middleware::Logger;
 use actix_web::web::{resource, Data};
 use actix_web::{
     http::header::{ContentDisposition, DispositionType},
-    middleware, web, App, HttpRequest, HttpServer, Responder,
+    middleware, web, App, HttpServer, Responder,
 };
 use diesel::pg::Pg;
 use diesel::r2d2::ConnectionManager;
@@ -18,15 +20,32 @@ use diesel::PgConnection;
 use diesel_migrations::{embed_migrations, EmbeddedMigrations, MigrationHarness};
 use dotenvy::dotenv;
 use r2d2::Pool;
-use reqwest::{Client, ClientBuilder};
+use reqwest::Client;
 use std::env;
 use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
 use mime::Mime;
 
 mod api;
+mod bundle;
+mod cache;
+mod conversion;
+mod diff;
+mod e3;
+mod html;
+mod lcc;
 mod models;
+mod pdf;
+mod project;
+mod release_years;
+mod report;
 mod schema;
 mod paginated;
+mod series;
+mod validate;
+mod xml_export;
+mod xml_import;
 
 type DbPool = Pool<ConnectionManager<PgConnection>>;
 
@@ -62,13 +81,17 @@ async fn pdf(path: web::Path<String>) -> impl Responder {
                 ),
             )
         }
-        Err(e) => Err(ErrorBadRequest("Could not find file")),
+        Err(_) => Err(ErrorBadRequest("Could not find file")),
     }
 }
 
 struct AppData {
     client: Client,
     pool: DbPool,
+    e3: E3Config,
+    cache: Arc<E3Cache>,
+    /// How long stored report requests can be fetched by URL.
+    report_retention: Duration,
 }
 
 #[actix_web::main]
@@ -91,9 +114,35 @@ async fn main() -> std::io::Result<()> {
         .expect("Could not get postgres connection for migrations.");
     run_migrations(&mut connection);
 
+    // Subcommands work on the database and exit instead of starting the server
+    let args: Vec<String> = env::args().skip(1).collect();
+    if let Some(command) = args.first() {
+        let code = match command.as_str() {
+            "import" => bundle::command(&args[1..], &mut connection),
+            "validate" => validate::command(&args[1..], &mut connection),
+            "release-year" => release_years::command(&args[1..], &mut connection),
+            _ => {
+                eprintln!("Unknown command {}, expected import, validate or release-year", command);
+                2
+            }
+        };
+        std::process::exit(code);
+    }
+
     let public_folder = env::var("PUBLIC_FOLDER")
         .unwrap_or_else(|_| { "public/" }.parse().unwrap());
 
+    let e3 = E3Config::from_env();
+    let cache = Arc::new(E3Cache::from_env());
+    let report_retention = env::var("REPORT_RETENTION_DAYS")
+        .ok()
+        .and_then(|value| value.parse::<u64>().ok())
+        .map(|days| Duration::from_secs(days * 24 * 60 * 60))
+        .unwrap_or(Duration::from_secs(90 * 24 * 60 * 60));
+    if e3.url.is_none() || e3.api_key.is_none() {
+        log::warn!("E3_URL or E3_API_KEY not set, E3 requests will fail");
+    }
+
     HttpServer::new(move || {
         // Set up cors middleware
         let cors = env::var("ALLOWED_ORIGIN")
@@ -101,20 +150,23 @@ async fn main() -> std::io::Result<()> {
             .split(",")
             .fold(
                 Cors::default().allowed_methods(vec!["GET", "POST"]),
-                |cors, origin| cors.allowed_origin(&*origin),
+                |cors, origin| cors.allowed_origin(origin),
             );
 
         // Set up reqwest client
-        let client = ClientBuilder::new()
-            .use_rustls_tls()
-            .build()
-            .unwrap();
+        let client = e3::client(&e3);
 
         App::new()
             .app_data(web::JsonConfig::default().error_handler(|_, _| {
                ErrorBadRequest("Could not parse json")
             }))
-            .app_data(Data::new(AppData { client, pool: pool.clone() }))
+            .app_data(Data::new(AppData {
+                client,
+                pool: pool.clone(),
+                e3: e3.clone(),
+                cache: cache.clone(),
+                report_retention,
+            }))
             .wrap(cors)
             .wrap(
                 middleware::DefaultHeaders::new()
@@ -1,7 +1,10 @@
+use std::time::SystemTime;
+
+use chrono::NaiveDate;
 use diesel::prelude::*;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
-#[derive(Queryable, Selectable, Serialize)]
+#[derive(Queryable, Selectable, Insertable, Serialize, Deserialize)]
 #[diesel(table_name = crate::schema::discount_rates)]
 #[diesel(check_for_backend(diesel::pg::Pg))]
 #[serde(rename_all = "camelCase")]
@@ -14,7 +17,16 @@ pub struct DiscountRates {
     pub inflation: f64
 }
 
-#[derive(Queryable, Selectable, Serialize)]
+#[derive(Queryable, Selectable, Insertable)]
+#[diesel(table_name = crate::schema::e3_cache)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct E3CacheEntry {
+    pub key: String,
+    pub response: serde_json::Value,
+    pub created_at: SystemTime,
+}
+
+#[derive(Queryable, Selectable, Insertable, Serialize, Deserialize)]
 #[diesel(table_name = crate::schema::state_division_region)]
 #[diesel(check_for_backend(diesel::pg::Pg))]
 pub struct Division {
@@ -23,7 +35,7 @@ pub struct Division {
     pub region: String
 }
 
-#[derive(Queryable, Selectable, Serialize)]
+#[derive(Queryable, Selectable, Insertable, Serialize, Deserialize)]
 #[diesel(table_name = crate::schema::scc)]
 #[diesel(check_for_backend(diesel::pg::Pg))]
 #[serde(rename_all = "camelCase")]
@@ -35,7 +47,7 @@ pub struct Scc {
     pub three_percent_average: f64,
 }
 
-#[derive(Queryable, Selectable, Serialize)]
+#[derive(Queryable, Selectable, Insertable, Serialize, Deserialize)]
 #[diesel(table_name = crate::schema::zip_info)]
 #[diesel(check_for_backend(diesel::pg::Pg))]
 #[serde(rename_all = "camelCase")]
@@ -49,7 +61,7 @@ pub struct ZipInfo {
     pub reeds_ba: Option<String>
 }
 
-#[derive(Queryable, Selectable, Serialize)]
+#[derive(Queryable, Selectable, Insertable, Serialize, Deserialize)]
 #[diesel(table_name = crate::schema::region_case_ba)]
 #[diesel(check_for_backend(diesel::pg::Pg))]
 #[serde(rename_all = "camelCase")]
@@ -62,7 +74,7 @@ pub struct RegionCaseBA {
     pub kg_co2_per_mwh: f64,
 }
 
-#[derive(Queryable, Selectable, Serialize)]
+#[derive(Queryable, Selectable, Insertable, Serialize, Deserialize, Clone)]
 #[diesel(table_name = crate::schema::escalation_rates)]
 #[diesel(check_for_backend(diesel::pg::Pg))]
 #[serde(rename_all = "camelCase")]
@@ -81,7 +93,7 @@ pub struct EscalationRate {
     pub coal: Option<f64>,
 }
 
-#[derive(Queryable, Selectable, Serialize)]
+#[derive(Queryable, Selectable, Insertable, Serialize, Deserialize)]
 #[diesel(table_name = crate::schema::region_case_oil)]
 #[diesel(check_for_backend(diesel::pg::Pg))]
 #[serde(rename_all = "camelCase")]
@@ -94,7 +106,7 @@ pub struct RegionCaseOil {
     pub kg_co2_per_mj: f64,
 }
 
-#[derive(Queryable, Selectable, Serialize)]
+#[derive(Queryable, Selectable, Insertable, Serialize, Deserialize)]
 #[diesel(table_name = crate::schema::region_case_propane_lng)]
 #[diesel(check_for_backend(diesel::pg::Pg))]
 #[serde(rename_all = "camelCase")]
@@ -107,7 +119,7 @@ pub struct RegionCasePropaneLNG {
     pub kg_co2_per_mj: f64,
 }
 
-#[derive(Queryable, Selectable, Serialize)]
+#[derive(Queryable, Selectable, Insertable, Serialize, Deserialize)]
 #[diesel(table_name = crate::schema::region_case_reeds)]
 #[diesel(check_for_backend(diesel::pg::Pg))]
 #[serde(rename_all = "camelCase")]
@@ -120,7 +132,7 @@ pub struct RegionCaseReeds {
     pub kg_co2_per_mwh: f64,
 }
 
-#[derive(Queryable, Selectable, Serialize)]
+#[derive(Queryable, Selectable, Insertable, Serialize, Deserialize)]
 #[diesel(table_name = crate::schema::region_natgas)]
 #[diesel(check_for_backend(diesel::pg::Pg))]
 #[serde(rename_all = "camelCase")]
@@ -133,7 +145,7 @@ pub struct RegionCaseNatgas {
     pub kg_co2_per_mj: f64,
 }
 
-#[derive(Queryable, Selectable, Serialize)]
+#[derive(Queryable, Selectable, Insertable, Serialize, Deserialize)]
 #[diesel(table_name = crate::schema::energy_prices)]
 #[diesel(check_for_backend(diesel::pg::Pg))]
 #[serde(rename_all = "camelCase")]
@@ -152,7 +164,7 @@ pub struct EnergyPrices {
     pub coal: Option<f64>,
 }
 
-#[derive(Queryable, Selectable, Serialize)]
+#[derive(Queryable, Selectable, Insertable, Serialize, Deserialize)]
 #[diesel(table_name = crate::schema::energy_price_indices)]
 #[diesel(check_for_backend(diesel::pg::Pg))]
 #[serde(rename_all = "camelCase")]
@@ -169,4 +181,25 @@ pub struct EnergyPriceIndices {
     pub natural_gas: Option<f64>,
     pub electricity: Option<f64>,
     pub coal: Option<f64>,
-}
\ No newline at end of file
+}
+
+#[derive(Queryable, Selectable, Insertable)]
+#[diesel(table_name = crate::schema::stored_reports)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct StoredReport {
+    pub key: String,
+    pub request: serde_json::Value,
+    pub created_at: SystemTime,
+}
+
+#[derive(Queryable, Selectable, Insertable, AsChangeset, Serialize)]
+#[diesel(table_name = crate::schema::release_years)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+#[serde(rename_all = "camelCase")]
+pub struct ReleaseYearInfo {
+    pub release_year: i32,
+    pub publication_date: Option<NaiveDate>,
+    pub sources: Vec<String>,
+    pub status: String,
+    pub is_default: bool,
+}
@@ -1,85 +1,246 @@
+use std::future::Future;
 use std::ops::Add;
+use std::pin::Pin;
 
-use actix_web::{HttpResponse, post, Responder};
-use actix_web::web::{Data, Json, scope, ServiceConfig};
-use diesel::{QueryDsl, QueryResult, RunQueryDsl, TextExpressionMethods};
-use diesel::dsl::sql;
+use actix_web::dev::Payload;
+use actix_web::error::InternalError;
+use actix_web::web::{scope, Data, Json, ServiceConfig};
+use actix_web::{post, FromRequest, HttpRequest, HttpResponse, Responder};
+use diesel::dsl::{count_star, sql};
 use diesel::sql_types::{Bool, Text};
+use diesel::{ExpressionMethods, QueryDsl, QueryResult, RunQueryDsl, TextExpressionMethods};
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 
-use crate::api::ErrorResponse;
-use crate::DbPool;
+use crate::api::{offered_release_years, ErrorResponse, ReleaseYearResponse};
+use crate::AppData;
 
 const PAGE_LIMIT: i64 = 100;
 
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PageRequest<T, K> {
+    #[serde(flatten)]
+    filter: T,
+    after: Option<K>,
+    page: Option<i64>,
+    limit: Option<i64>,
+}
+
+/// Extracts a keyset page request from the JSON body. The body holds the fields of the filter `T` next to `after`, the
+/// key of the last value of the previous page, and `limit`, the page size which defaults to and may not exceed
+/// `PAGE_LIMIT`. Older clients send the zero based `page` number instead of `after`, which is still supported.
+pub struct Paginated<T, K = i32> {
+    pub filter: T,
+    pub after: Option<K>,
+    pub page: Option<i64>,
+    pub limit: i64,
+}
+
+impl<T, K> Paginated<T, K> {
+    /// The number of values to skip for a `page` request. Requests continuing `after` a key never skip any. Pages too
+    /// large to skip are rejected when the request is extracted.
+    fn offset(&self) -> i64 {
+        match self.after {
+            Some(_) => 0,
+            None => self.page.unwrap_or(0).saturating_mul(self.limit),
+        }
+    }
+
+    /// Builds the response from values loaded with a limit of `self.limit + 1`, where the extra value only signals that
+    /// another page exists.
+    fn page<V: Serialize>(&self, mut values: Vec<V>, total: i64, key: impl Fn(&V) -> K) -> Page<V, K> {
+        let has_next = values.len() as i64 > self.limit;
+        values.truncate(self.limit as usize);
+
+        Page {
+            next: if has_next { values.last().map(key) } else { None },
+            values,
+            total,
+            page: match self.after {
+                Some(_) => None,
+                None => Some(self.page.unwrap_or(0)),
+            },
+            has_next,
+        }
+    }
+}
+
+impl<T: DeserializeOwned + 'static, K: DeserializeOwned + 'static> FromRequest for Paginated<T, K> {
+    type Error = actix_web::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self, Self::Error>>>>;
+
+    fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
+        let json = Json::<PageRequest<T, K>>::from_request(req, payload);
+
+        Box::pin(async move {
+            let request = json.await?.into_inner();
+            let limit = request.limit.unwrap_or(PAGE_LIMIT);
+
+            let error = if !(1..=PAGE_LIMIT).contains(&limit) {
+                Some(format!("Page limit must be between 1 and {}", PAGE_LIMIT))
+            } else if request.page.is_some_and(|page| page < 0) {
+                Some("Page must not be negative".to_string())
+            } else if request.page.is_some_and(|page| page.checked_mul(limit).is_none()) {
+                Some(format!("Page must be at most {} with a limit of {}", i64::MAX / limit, limit))
+            } else if request.page.is_some() && request.after.is_some() {
+                Some("Request either a page or the values after a key, not both".to_string())
+            } else {
+                None
+            };
+
+            if let Some(error) = error {
+                let response = HttpResponse::BadRequest().json(ErrorResponse { error: error.clone() });
+                return Err(InternalError::from_response(error, response).into());
+            }
+
+            Ok(Paginated {
+                filter: request.filter,
+                after: request.after,
+                page: request.page,
+                limit,
+            })
+        })
+    }
+}
+
 #[derive(Serialize)]
-pub struct Paginated<T> {
-    values: Vec<T>,
-    page: i64,
+#[serde(rename_all = "camelCase")]
+pub struct Page<V, K> {
+    values: Vec<V>,
+    /// The number of values matching the filter across all pages.
+    total: i64,
+    /// The `after` key for the next page, or `None` if this is the last page.
+    next: Option<K>,
+    /// The requested page number, kept for clients that page by number. Not sent for requests continuing `after` a key.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    page: Option<i64>,
+    #[serde(rename = "has_next")]
     has_next: bool,
 }
 
 #[derive(Deserialize)]
-struct ZipcodeRequest {
+#[serde(rename_all = "camelCase")]
+struct ZipcodeFilter {
+    #[serde(alias = "partial_zip")]
     partial_zip: Option<String>,
     state: Option<String>,
-    page: Option<i64>,
 }
 
 #[post("/zipcodes")]
-async fn post_zipcodes(request: Json<ZipcodeRequest>, data: Data<DbPool>) -> impl Responder {
+async fn post_zipcodes(request: Paginated<ZipcodeFilter>, data: Data<AppData>) -> impl Responder {
     use crate::schema::zip_info::dsl::zip_info;
     use crate::schema::zip_info::*;
 
-    let mut db = data.get().expect("Failed to get db connection");
+    let mut db = data.pool.get().expect("Failed to get db connection");
 
     let mut query = zip_info.into_boxed();
-    let mut next_query = zip_info.into_boxed();
+    let mut count_query = zip_info.into_boxed();
 
     // Apply state filter if we have it
-    if let Some(state_abbreviation) = request.state.clone() {
+    if let Some(state_abbreviation) = request.filter.state.clone() {
         query = query.filter(state.like(state_abbreviation.clone()));
-        next_query = next_query.filter(state.like(state_abbreviation));
+        count_query = count_query.filter(state.like(state_abbreviation));
     }
 
     // Apply partial zipcode filter if we have it
-    if let Some(partial_zip) = request.partial_zip.clone() {
+    if let Some(partial_zip) = request.filter.partial_zip.clone() {
         let pattern = partial_zip.add("%");
 
         query = query.filter(sql::<Bool>("zip::text LIKE ").bind::<Text, _>(pattern.clone()));
-        next_query = next_query.filter(sql::<Bool>("zip::text LIKE ").bind::<Text, _>(pattern));
+        count_query = count_query.filter(sql::<Bool>("zip::text LIKE ").bind::<Text, _>(pattern));
     }
 
-    // Get the page to load or default to the first (0 index) page
-    let page = request.page.unwrap_or(0);
+    // Continue after the last zipcode of the previous page
+    if let Some(after) = request.after {
+        query = query.filter(zip.gt(after));
+    }
 
-    // Get the results
     let result: QueryResult<Vec<i32>> = query
-        .limit(PAGE_LIMIT)
-        .offset(page * PAGE_LIMIT)
+        .order_by(zip)
+        .offset(request.offset())
+        .limit(request.limit + 1)
         .select(zip)
         .load(&mut db);
+    let total: QueryResult<i64> = count_query.select(count_star()).first(&mut db);
 
-    // Test if the next page exists
-    let next_result: QueryResult<Vec<i32>> = next_query
-        .limit(1)
-        .offset((page + 1) * PAGE_LIMIT)
-        .select(zip)
+    match (result, total) {
+        (Ok(zipcodes), Ok(total)) => HttpResponse::Ok().json(request.page(zipcodes, total, |zipcode| *zipcode)),
+        _ => HttpResponse::BadRequest().json(ErrorResponse {
+            error: "Could not find zipcodes".to_string()
+        })
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct StateFilter {
+    division: Option<String>,
+    region: Option<String>,
+}
+
+#[post("/states")]
+async fn post_states(request: Paginated<StateFilter, String>, data: Data<AppData>) -> impl Responder {
+    use crate::schema::state_division_region::dsl::state_division_region;
+    use crate::schema::state_division_region::*;
+
+    let mut db = data.pool.get().expect("Failed to get db connection");
+
+    let mut query = state_division_region.into_boxed();
+    let mut count_query = state_division_region.into_boxed();
+
+    if let Some(some_division) = request.filter.division.clone() {
+        query = query.filter(division.eq(some_division.clone()));
+        count_query = count_query.filter(division.eq(some_division));
+    }
+
+    if let Some(some_region) = request.filter.region.clone() {
+        query = query.filter(region.eq(some_region.clone()));
+        count_query = count_query.filter(region.eq(some_region));
+    }
+
+    if let Some(after) = request.after.clone() {
+        query = query.filter(state.gt(after));
+    }
+
+    let result: QueryResult<Vec<String>> = query
+        .order_by(state)
+        .offset(request.offset())
+        .limit(request.limit + 1)
+        .select(state)
         .load(&mut db);
+    let total: QueryResult<i64> = count_query.select(count_star()).first(&mut db);
+
+    match (result, total) {
+        (Ok(states), Ok(total)) => HttpResponse::Ok().json(request.page(states, total, |value| value.clone())),
+        _ => HttpResponse::BadRequest().json(ErrorResponse {
+            error: "Could not get states".to_string()
+        })
+    }
+}
 
-    let has_next = match next_result {
-        Ok(result) => !result.is_empty(),
-        Err(_) => false
-    };
+#[derive(Deserialize)]
+struct ReleaseYearFilter {}
 
-    match result {
-        Ok(zipcodes) => HttpResponse::Ok().json(Paginated {
-            values: zipcodes,
-            page,
-            has_next,
-        }),
+#[post("/release_years")]
+async fn post_release_years(request: Paginated<ReleaseYearFilter>, data: Data<AppData>) -> impl Responder {
+    let mut db = data.pool.get().expect("Failed to get db connection");
+
+    // There are only a handful of release years, so they are paged in memory
+    match offered_release_years(&mut db) {
+        Ok(offered) => {
+            let total = offered.len() as i64;
+            let page = offered
+                .into_iter()
+                .filter(|release| request.after.is_none_or(|after| release.year > after))
+                .skip(request.offset() as usize)
+                .take(request.limit as usize + 1)
+                .collect();
+
+            HttpResponse::Ok().json(request.page(page, total, |value: &ReleaseYearResponse| value.year))
+        }
         Err(_) => HttpResponse::BadRequest().json(ErrorResponse {
-            error: "Could not find zipcodes".to_string()
+            error: "Could not get release years".to_string()
         })
     }
 }
@@ -88,5 +249,94 @@ pub fn config_paginated(config: &mut ServiceConfig) {
     config.service(
         scope("/paginated")
             .service(post_zipcodes)
+            .service(post_states)
+            .service(post_release_years)
     );
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::http::StatusCode;
+    use actix_web::test::TestRequest;
+    use serde_json::{json, Value};
+
+    use super::*;
+
+    #[derive(Deserialize)]
+    struct Filter {
+        state: Option<String>,
+    }
+
+    async fn extract(body: Value) -> Result<Paginated<Filter>, (StatusCode, String)> {
+        let (request, mut payload) = TestRequest::post().set_json(body).to_http_parts();
+
+        Paginated::<Filter>::from_request(&request, &mut payload).await.map_err(|err| {
+            let response = err.as_response_error().error_response();
+            (response.status(), err.to_string())
+        })
+    }
+
+    fn paginated(after: Option<i32>, page: Option<i64>, limit: i64) -> Paginated<(), i32> {
+        Paginated { filter: (), after, page, limit }
+    }
+
+    #[actix_web::test]
+    async fn extracts_page_requests() {
+        let request = extract(json!({ "state": "MD", "after": 20899, "limit": 2 })).await.unwrap();
+        assert_eq!(request.filter.state.as_deref(), Some("MD"));
+        assert_eq!((request.after, request.page, request.limit), (Some(20899), None, 2));
+
+        let request = extract(json!({})).await.unwrap();
+        assert_eq!((request.after, request.page, request.limit), (None, None, PAGE_LIMIT));
+    }
+
+    #[actix_web::test]
+    async fn rejects_invalid_page_requests() {
+        let error = |message: &str| Some((StatusCode::BAD_REQUEST, message.to_string()));
+
+        assert_eq!(extract(json!({ "limit": 0 })).await.err(), error("Page limit must be between 1 and 100"));
+        assert_eq!(extract(json!({ "page": -1 })).await.err(), error("Page must not be negative"));
+        assert_eq!(
+            extract(json!({ "page": 1, "after": 5 })).await.err(),
+            error("Request either a page or the values after a key, not both")
+        );
+        assert_eq!(
+            extract(json!({ "page": i64::MAX / 10, "limit": 20 })).await.err(),
+            error(&format!("Page must be at most {} with a limit of 20", i64::MAX / 20))
+        );
+    }
+
+    #[test]
+    fn skips_pages_only_without_a_key() {
+        assert_eq!(paginated(None, None, 10).offset(), 0);
+        assert_eq!(paginated(None, Some(3), 10).offset(), 30);
+        assert_eq!(paginated(Some(5), None, 10).offset(), 0);
+    }
+
+    #[test]
+    fn continues_after_the_last_key() {
+        // Values are loaded with one extra to tell whether another page follows
+        let request = paginated(Some(20850), None, 2);
+        let page = request.page(vec![20851, 20852, 20853], 40, |zip| *zip);
+
+        assert_eq!(page.values, [20851, 20852]);
+        assert_eq!(page.next, Some(20852));
+        assert!(page.has_next);
+        assert_eq!(page.page, None);
+        assert_eq!(page.total, 40);
+
+        let last = request.page(vec![20851, 20852], 40, |zip| *zip);
+        assert_eq!(last.next, None);
+        assert!(!last.has_next);
+    }
+
+    #[test]
+    fn numbers_pages_for_page_requests() {
+        let page = paginated(None, Some(1), 2).page(vec![3, 4, 5], 5, |value| *value);
+        assert_eq!(page.page, Some(1));
+        assert_eq!(page.next, Some(4));
+
+        let json = serde_json::to_value(paginated(Some(1), None, 2).page(vec![2], 2, |value| *value)).unwrap();
+        assert_eq!(json, json!({ "values": [2], "total": 2, "next": null, "has_next": false }));
+    }
+}
@@ -0,0 +1,555 @@
+use pdf_writer::{Content, Name, Pdf, Rect, Ref, Str, TextStr};
+
+use crate::report::{Block, Report, RowStyle, Table};
+
+/*
+ * Renders reports to PDF using the standard Helvetica fonts, so no font files have to be embedded. Text is encoded
+ * with WinAnsiEncoding and measured with the Helvetica metrics below.
+ */
+
+const PAGE_WIDTH: f32 = 612.0;
+const PAGE_HEIGHT: f32 = 792.0;
+const MARGIN: f32 = 48.0;
+const CONTENT_WIDTH: f32 = PAGE_WIDTH - 2.0 * MARGIN;
+const BOTTOM: f32 = MARGIN + 16.0;
+
+const REGULAR: Name = Name(b"F1");
+const BOLD: Name = Name(b"F2");
+
+const TEXT_SIZE: f32 = 9.0;
+const TABLE_SIZE: f32 = 8.0;
+const CELL_PADDING: f32 = 4.0;
+/// Allowance for rounding when checking whether measured text fits a width computed from the same measurement.
+const TOLERANCE: f32 = 0.01;
+
+/// Advance widths of the printable ASCII characters in Helvetica, in thousandths of the font size.
+const HELVETICA_WIDTHS: [u16; 95] = [
+    278, 278, 355, 556, 556, 889, 667, 191, 333, 333, 389, 584, 278, 333, 278, 278, 556, 556, 556, 556, 556, 556, 556,
+    556, 556, 556, 278, 278, 584, 584, 584, 556, 1015, 667, 667, 722, 722, 667, 611, 778, 722, 278, 500, 667, 556, 833,
+    722, 778, 667, 778, 722, 667, 611, 722, 667, 944, 667, 667, 611, 278, 278, 278, 469, 556, 333, 556, 556, 500, 556,
+    556, 278, 556, 556, 222, 222, 500, 222, 833, 556, 556, 556, 556, 333, 500, 278, 556, 500, 722, 500, 500, 500, 334,
+    260, 334, 584,
+];
+
+/// Advance widths of the printable ASCII characters in Helvetica-Bold.
+const HELVETICA_BOLD_WIDTHS: [u16; 95] = [
+    278, 333, 474, 556, 556, 889, 722, 238, 333, 333, 389, 584, 278, 333, 278, 278, 556, 556, 556, 556, 556, 556, 556,
+    556, 556, 556, 333, 333, 584, 584, 584, 611, 975, 722, 722, 722, 722, 667, 611, 778, 722, 278, 556, 722, 611, 833,
+    722, 778, 667, 778, 722, 667, 611, 722, 667, 944, 667, 667, 611, 333, 278, 333, 584, 556, 333, 556, 611, 556, 611,
+    556, 333, 611, 611, 278, 278, 556, 278, 889, 611, 611, 611, 611, 389, 556, 333, 611, 556, 778, 556, 556, 500, 389,
+    280, 389, 584,
+];
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Font {
+    Regular,
+    Bold,
+}
+
+impl Font {
+    fn name(&self) -> Name<'static> {
+        match self {
+            Font::Regular => REGULAR,
+            Font::Bold => BOLD,
+        }
+    }
+
+    fn width(&self, text: &str, size: f32) -> f32 {
+        let widths = match self {
+            Font::Regular => &HELVETICA_WIDTHS,
+            Font::Bold => &HELVETICA_BOLD_WIDTHS,
+        };
+
+        let units: u32 = text
+            .chars()
+            .map(|c| match c {
+                ' '..='~' => widths[c as usize - 32] as u32,
+                _ => 556,
+            })
+            .sum();
+
+        units as f32 * size / 1000.0
+    }
+}
+
+/// Encodes text as WinAnsiEncoding. Characters outside the encoding are replaced with a question mark.
+fn encode(text: &str) -> Vec<u8> {
+    text.chars()
+        .map(|c| match c {
+            ' '..='~' | '\u{a0}'..='\u{ff}' => c as u8,
+            '\u{2013}' => 0x96,
+            '\u{2014}' => 0x97,
+            '\u{2018}' => 0x91,
+            '\u{2019}' => 0x92,
+            '\u{201c}' => 0x93,
+            '\u{201d}' => 0x94,
+            '\u{2022}' => 0x95,
+            '\u{20ac}' => 0x80,
+            _ => b'?',
+        })
+        .collect()
+}
+
+/// Splits text into lines that fit within the given width, breaking on whitespace. Words longer than the width are
+/// left on a line of their own.
+fn wrap(text: &str, font: Font, size: f32, width: f32) -> Vec<String> {
+    let mut lines = vec![];
+
+    for paragraph in text.lines() {
+        let mut line = String::new();
+        for word in paragraph.split_whitespace() {
+            let candidate = if line.is_empty() { word.to_string() } else { format!("{} {}", line, word) };
+
+            if !line.is_empty() && font.width(&candidate, size) > width + TOLERANCE {
+                lines.push(std::mem::replace(&mut line, word.to_string()));
+            } else {
+                line = candidate;
+            }
+        }
+        lines.push(line);
+    }
+
+    if lines.is_empty() {
+        lines.push(String::new());
+    }
+
+    lines
+}
+
+/// Lays out report content top to bottom, starting a new page whenever the next element does not fit.
+struct Layout {
+    pages: Vec<Content>,
+    y: f32,
+}
+
+impl Layout {
+    fn new() -> Self {
+        Layout { pages: vec![Content::new()], y: PAGE_HEIGHT - MARGIN }
+    }
+
+    fn content(&mut self) -> &mut Content {
+        self.pages.last_mut().expect("Layout always has a page")
+    }
+
+    /// Starts a new page unless `height` still fits on the current one. Returns whether a page was added.
+    fn reserve(&mut self, height: f32) -> bool {
+        if self.y - height >= BOTTOM {
+            return false;
+        }
+
+        self.pages.push(Content::new());
+        self.y = PAGE_HEIGHT - MARGIN;
+        true
+    }
+
+    fn text(&mut self, x: f32, y: f32, font: Font, size: f32, text: &str) {
+        let encoded = encode(text);
+        self.content()
+            .begin_text()
+            .set_font(font.name(), size)
+            .next_line(x, y)
+            .show(Str(&encoded))
+            .end_text();
+    }
+
+    fn rule(&mut self, x: f32, y: f32, width: f32, thickness: f32) {
+        self.content()
+            .set_line_width(thickness)
+            .move_to(x, y)
+            .line_to(x + width, y)
+            .stroke();
+    }
+
+    /// Writes wrapped text, moving down one line per wrapped line.
+    fn paragraph(&mut self, text: &str, font: Font, size: f32) {
+        let leading = size * 1.3;
+        for line in wrap(text, font, size, CONTENT_WIDTH) {
+            self.reserve(leading);
+            self.y -= leading;
+            self.text(MARGIN, self.y + size * 0.25, font, size, &line);
+        }
+    }
+
+    fn space(&mut self, height: f32) {
+        self.y -= height;
+    }
+
+    fn heading(&mut self, text: &str) {
+        // Keep headings together with at least a few lines of what follows them
+        self.reserve(60.0);
+        self.space(8.0);
+        self.paragraph(text, Font::Bold, 11.0);
+        self.rule(MARGIN, self.y - 2.0, CONTENT_WIDTH, 0.75);
+        self.space(6.0);
+    }
+
+    fn fields(&mut self, fields: &[(String, String)]) {
+        let label_width = fields
+            .iter()
+            .map(|(label, _)| Font::Bold.width(label, TEXT_SIZE))
+            .fold(0.0, f32::max)
+            .min(CONTENT_WIDTH * 0.45)
+            + 8.0;
+        let leading = TEXT_SIZE * 1.35;
+
+        for (label, value) in fields {
+            let lines = wrap(value, Font::Regular, TEXT_SIZE, CONTENT_WIDTH - label_width);
+            self.reserve(leading * lines.len() as f32);
+
+            self.text(MARGIN, self.y - leading + 2.5, Font::Bold, TEXT_SIZE, label);
+            for line in lines {
+                self.y -= leading;
+                self.text(MARGIN + label_width, self.y + 2.5, Font::Regular, TEXT_SIZE, &line);
+            }
+        }
+        self.space(4.0);
+    }
+
+    fn table(&mut self, table: &Table) {
+        let count = table.columns.len().max(1);
+        let widths = column_widths(table, count);
+        let row_height = TABLE_SIZE + 2.0 * CELL_PADDING;
+
+        if let Some(caption) = &table.caption {
+            self.reserve(row_height * 3.0);
+            self.space(2.0);
+            self.paragraph(caption, Font::Bold, TEXT_SIZE);
+            self.space(2.0);
+        } else {
+            self.reserve(row_height * 2.0);
+        }
+
+        self.table_header(table, &widths, row_height);
+
+        for row in table.rows.iter() {
+            let font = match row.style {
+                RowStyle::Normal => Font::Regular,
+                RowStyle::Group | RowStyle::Total => Font::Bold,
+            };
+
+            // Repeat the header at the top of every page the table continues on
+            if self.reserve(row_height) {
+                self.table_header(table, &widths, row_height);
+            }
+
+            if row.style == RowStyle::Total {
+                self.rule(MARGIN, self.y, widths.iter().sum(), 0.5);
+            }
+
+            self.y -= row_height;
+            let baseline = self.y + CELL_PADDING + 1.0;
+            let mut x = MARGIN;
+            for (i, width) in widths.iter().enumerate() {
+                let cell = row.cells.get(i).map(String::as_str).unwrap_or_default();
+                // Group rows only have a label, which may use the whole width of the table
+                let available = if row.style == RowStyle::Group { widths[i..].iter().sum() } else { *width };
+                let cell = truncate(cell, font, TABLE_SIZE, available - 2.0 * CELL_PADDING);
+
+                // The first column holds labels, every other column holds values which are right aligned
+                let offset = if i == 0 || row.style == RowStyle::Group {
+                    CELL_PADDING
+                } else {
+                    width - CELL_PADDING - font.width(&cell, TABLE_SIZE)
+                };
+                self.text(x + offset, baseline, font, TABLE_SIZE, &cell);
+                x += width;
+            }
+        }
+
+        self.space(8.0);
+    }
+
+    fn table_header(&mut self, table: &Table, widths: &[f32], row_height: f32) {
+        if table.columns.iter().all(|column| column.is_empty()) {
+            return;
+        }
+
+        let lines: Vec<Vec<String>> = table
+            .columns
+            .iter()
+            .zip(widths)
+            .map(|(column, width)| wrap(column, Font::Bold, TABLE_SIZE, width - 2.0 * CELL_PADDING))
+            .collect();
+        let count = lines.iter().map(Vec::len).max().unwrap_or(1);
+        let leading = TABLE_SIZE * 1.2;
+
+        self.y -= row_height + leading * (count - 1) as f32;
+        let mut x = MARGIN;
+        for (i, (column, width)) in lines.iter().zip(widths).enumerate() {
+            // Headers are aligned to the bottom, so single line headers sit next to the last line of longer ones
+            let mut baseline = self.y + CELL_PADDING + 1.0 + leading * (column.len() - 1) as f32;
+            for line in column {
+                let offset = if i == 0 { CELL_PADDING } else { width - CELL_PADDING - Font::Bold.width(line, TABLE_SIZE) };
+                self.text(x + offset, baseline, Font::Bold, TABLE_SIZE, line);
+                baseline -= leading;
+            }
+            x += width;
+        }
+        self.rule(MARGIN, self.y, widths.iter().sum(), 0.5);
+    }
+}
+
+/// Sizes columns to their widest cell. Headers are kept on one line if the table still fits on the page, otherwise
+/// they wrap and only their longest word has to fit. Columns are shrunk proportionally if that is still too wide.
+fn column_widths(table: &Table, count: usize) -> Vec<f32> {
+    let mut cells = vec![0.0f32; count];
+    for row in table.rows.iter() {
+        // Group rows span the table, so they do not widen the first column
+        if row.style == RowStyle::Group {
+            continue;
+        }
+        let font = if row.style == RowStyle::Normal { Font::Regular } else { Font::Bold };
+        for (width, cell) in cells.iter_mut().zip(row.cells.iter()) {
+            *width = width.max(font.width(cell, TABLE_SIZE) + 2.0 * CELL_PADDING);
+        }
+    }
+
+    let header = |measure: &dyn Fn(&str) -> f32| -> Vec<f32> {
+        cells
+            .iter()
+            .enumerate()
+            .map(|(i, width)| {
+                let column = table.columns.get(i).map(String::as_str).unwrap_or_default();
+                width.max(measure(column) + 2.0 * CELL_PADDING)
+            })
+            .collect()
+    };
+
+    let mut widths = header(&|column| Font::Bold.width(column, TABLE_SIZE));
+    if widths.iter().sum::<f32>() > CONTENT_WIDTH {
+        widths = header(&|column| {
+            column
+                .split_whitespace()
+                .map(|word| Font::Bold.width(word, TABLE_SIZE))
+                .fold(0.0, f32::max)
+        });
+    }
+
+    // Shorten the label column first, since values are more important than complete labels
+    let overflow = widths.iter().sum::<f32>() - CONTENT_WIDTH;
+    if overflow > 0.0 && count > 1 {
+        widths[0] = (widths[0] - overflow).max(widths[0].min(CONTENT_WIDTH * 0.25));
+    }
+
+    let total: f32 = widths.iter().sum();
+    if total > CONTENT_WIDTH {
+        widths.iter_mut().for_each(|width| *width *= CONTENT_WIDTH / total);
+    }
+
+    widths
+}
+
+/// Shortens text with an ellipsis so it fits within the given width.
+fn truncate(text: &str, font: Font, size: f32, width: f32) -> String {
+    if font.width(text, size) <= width + TOLERANCE {
+        return text.to_string();
+    }
+
+    let mut truncated: String = text.to_string();
+    while !truncated.is_empty() && font.width(&format!("{}...", truncated), size) > width {
+        truncated.pop();
+    }
+
+    format!("{}...", truncated)
+}
+
+/// Renders a report to a PDF document.
+pub fn render(report: &Report) -> Vec<u8> {
+    let mut layout = Layout::new();
+
+    layout.paragraph(&report.title, Font::Bold, 14.0);
+    layout.space(2.0);
+    layout.paragraph(&report.subtitle, Font::Regular, TEXT_SIZE);
+    layout.space(6.0);
+
+    for section in report.sections.iter() {
+        layout.heading(&section.heading);
+
+        for block in section.blocks.iter() {
+            match block {
+                Block::Fields(fields) => layout.fields(fields),
+                Block::Table(table) => layout.table(table),
+                Block::Note(note) => {
+                    layout.paragraph(note, Font::Regular, TEXT_SIZE);
+                    layout.space(4.0);
+                }
+            }
+        }
+    }
+
+    let count = layout.pages.len();
+    for (i, page) in layout.pages.iter_mut().enumerate() {
+        let footer = encode(&format!("Page {} of {}", i + 1, count));
+        let width = Font::Regular.width(&format!("Page {} of {}", i + 1, count), TABLE_SIZE);
+        page.begin_text()
+            .set_font(REGULAR, TABLE_SIZE)
+            .next_line(PAGE_WIDTH - MARGIN - width, MARGIN - 12.0)
+            .show(Str(&footer))
+            .end_text();
+    }
+
+    // Fixed objects first, then a page and content stream per page
+    let catalog_id = Ref::new(1);
+    let tree_id = Ref::new(2);
+    let regular_id = Ref::new(3);
+    let bold_id = Ref::new(4);
+    let info_id = Ref::new(5);
+    let page_ids: Vec<Ref> = (0..count).map(|i| Ref::new(6 + 2 * i as i32)).collect();
+
+    let mut pdf = Pdf::new();
+    pdf.catalog(catalog_id).pages(tree_id);
+    pdf.pages(tree_id).kids(page_ids.iter().copied()).count(count as i32);
+    pdf.type1_font(regular_id)
+        .base_font(Name(b"Helvetica"))
+        .encoding_predefined(Name(b"WinAnsiEncoding"));
+    pdf.type1_font(bold_id)
+        .base_font(Name(b"Helvetica-Bold"))
+        .encoding_predefined(Name(b"WinAnsiEncoding"));
+    pdf.document_info(info_id)
+        .title(TextStr(&report.title))
+        .producer(TextStr("BLCC"));
+
+    for (page, id) in layout.pages.into_iter().zip(page_ids) {
+        let content_id = Ref::new(id.get() + 1);
+
+        let mut writer = pdf.page(id);
+        writer
+            .parent(tree_id)
+            .media_box(Rect::new(0.0, 0.0, PAGE_WIDTH, PAGE_HEIGHT))
+            .contents(content_id);
+        writer.resources().fonts().pair(REGULAR, regular_id).pair(BOLD, bold_id);
+        drop(writer);
+
+        pdf.stream(content_id, &page.finish());
+    }
+
+    pdf.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::report::tests::federal_financed_report;
+    use crate::report::{ReportKind, Row, Section};
+
+    fn table(columns: &[&str], rows: Vec<Row>) -> Table {
+        Table { caption: None, columns: columns.iter().map(|column| column.to_string()).collect(), rows }
+    }
+
+    fn row(cells: &[&str], style: RowStyle) -> Row {
+        Row { cells: cells.iter().map(|cell| cell.to_string()).collect(), style }
+    }
+
+    /// The document as text, keeping byte offsets by replacing every non-ASCII byte.
+    fn ascii(pdf: &[u8]) -> String {
+        pdf.iter().map(|&byte| if byte.is_ascii() { byte as char } else { '?' }).collect()
+    }
+
+    /// Checks the structure a reader relies on: the header, the trailer and a cross-reference table whose offsets
+    /// each point at the object they list. Returns the number of objects.
+    fn parse(pdf: &[u8]) -> usize {
+        let text = ascii(pdf);
+        assert!(text.starts_with("%PDF-"));
+        assert!(text.trim_end().ends_with("%%EOF"));
+
+        let startxref = text.rfind("startxref").expect("missing startxref");
+        let offset: usize = text[startxref + "startxref".len()..].split_whitespace().next().unwrap().parse().unwrap();
+        let mut lines = text[offset..].lines();
+        assert_eq!(lines.next(), Some("xref"));
+
+        let (first, count) = lines.next().unwrap().split_once(' ').unwrap();
+        assert_eq!(first, "0");
+        let count: usize = count.parse().unwrap();
+        for id in 1..count {
+            let entry = lines.nth(if id == 1 { 1 } else { 0 }).unwrap();
+            let offset: usize = entry[..10].parse().unwrap();
+            assert!(text[offset..].starts_with(&format!("{} 0 obj", id)), "object {} is not at {}", id, offset);
+        }
+
+        count - 1
+    }
+
+    #[test]
+    fn encodes_win_ansi() {
+        assert_eq!(encode("Café – 5€"), b"Caf\xe9 \x96 5\x80");
+        assert_eq!(encode("CO₂ ✓"), b"CO? ?");
+    }
+
+    #[test]
+    fn wraps_on_whitespace() {
+        let width = Font::Regular.width("Energy Consumption", TEXT_SIZE);
+
+        assert_eq!(wrap("Energy Consumption Costs", Font::Regular, TEXT_SIZE, width), ["Energy Consumption", "Costs"]);
+        assert_eq!(wrap("Longest Costs", Font::Regular, TEXT_SIZE, 10.0), ["Longest", "Costs"]);
+        assert_eq!(wrap("First\nSecond", Font::Regular, TEXT_SIZE, CONTENT_WIDTH), ["First", "Second"]);
+        assert_eq!(wrap("", Font::Regular, TEXT_SIZE, CONTENT_WIDTH), [""]);
+        for line in wrap(&"word ".repeat(200), Font::Bold, TEXT_SIZE, CONTENT_WIDTH) {
+            assert!(Font::Bold.width(&line, TEXT_SIZE) <= CONTENT_WIDTH + TOLERANCE);
+        }
+    }
+
+    #[test]
+    fn sizes_columns_to_their_cells() {
+        let narrow = table(
+            &["", "Present Value"],
+            vec![
+                row(&["A label that is only in a group row and would be very wide", ""], RowStyle::Group),
+                row(&["Energy", "$594,523"], RowStyle::Normal),
+                row(&["Total Life-Cycle Cost", "$661,375"], RowStyle::Total),
+            ],
+        );
+        let widths = column_widths(&narrow, 2);
+        assert_eq!(widths[0], Font::Bold.width("Total Life-Cycle Cost", TABLE_SIZE) + 2.0 * CELL_PADDING);
+        assert_eq!(widths[1], Font::Bold.width("Present Value", TABLE_SIZE) + 2.0 * CELL_PADDING);
+
+        let columns: Vec<String> = (0..12).map(|i| format!("Average Annual Savings {}", i)).collect();
+        let columns: Vec<&str> = columns.iter().map(String::as_str).collect();
+        let wide = table(&columns, vec![row(&["Electricity"; 12], RowStyle::Normal)]);
+        let widths = column_widths(&wide, 12);
+        assert!(widths.iter().sum::<f32>() <= CONTENT_WIDTH + TOLERANCE);
+    }
+
+    #[test]
+    fn truncates_with_an_ellipsis() {
+        assert_eq!(truncate("Energy", Font::Regular, TABLE_SIZE, 100.0), "Energy");
+
+        let truncated = truncate("Energy Consumption Costs", Font::Regular, TABLE_SIZE, 50.0);
+        assert!(truncated.ends_with("...") && truncated.len() < 24);
+        assert!(Font::Regular.width(&truncated, TABLE_SIZE) <= 50.0);
+    }
+
+    #[test]
+    fn renders_a_valid_document() {
+        let pdf = render(&federal_financed_report(ReportKind::SummaryLcc));
+        let text = ascii(&pdf);
+
+        // Catalog, page tree, two fonts and the info dictionary, then a page and content stream per page
+        let objects = parse(&pdf);
+        assert_eq!((objects - 5) % 2, 0);
+        assert!(text.contains(&format!("/Count {}", (objects - 5) / 2)));
+        assert!(text.contains("(Total Life-Cycle Cost) Tj"));
+        assert!(text.contains("($661,375) Tj"));
+    }
+
+    #[test]
+    fn breaks_long_reports_into_pages() {
+        let rows = (0..200).map(|i| row(&[&i.to_string(), "$1"], RowStyle::Normal)).collect();
+        let report = Report {
+            title: "Title".to_string(),
+            subtitle: "Subtitle".to_string(),
+            sections: vec![Section {
+                heading: "Cash Flow".to_string(),
+                blocks: vec![Block::Table(table(&["Year", "Total"], rows))],
+            }],
+        };
+
+        let pdf = render(&report);
+        let text = ascii(&pdf);
+
+        let pages = (parse(&pdf) - 5) / 2;
+        assert!(pages > 1);
+        assert!(text.contains(&format!("(Page {} of {}) Tj", pages, pages)));
+        // The header is repeated on every page
+        assert_eq!(text.matches("(Year) Tj").count(), pages);
+    }
+}
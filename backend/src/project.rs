@@ -0,0 +1,563 @@
+use serde::{Deserialize, Serialize};
+
+pub type ID = i32;
+
+/// A complete BLCC project: the project settings along with every alternative and cost it references.
+#[derive(Deserialize, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectData {
+    pub project: Project,
+    pub alternatives: Vec<Alternative>,
+    pub costs: Vec<Cost>,
+}
+
+#[derive(Deserialize, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Project {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<ID>,
+    pub version: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub analyst: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub analysis_type: Option<AnalysisType>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub purpose: Option<Purpose>,
+    pub dollar_method: DollarMethod,
+    pub case: Case,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub study_period: Option<i32>,
+    pub construction_period: i32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub discounting_method: Option<DiscountingMethod>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub real_discount_rate: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub nominal_discount_rate: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub inflation_rate: Option<f64>,
+    pub location: Location,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub project_escalation_rates: Option<Vec<ProjectEscalationRate>>,
+    pub alternatives: Vec<ID>,
+    pub costs: Vec<ID>,
+    pub ghg: Ghg,
+    pub release_year: i32,
+}
+
+/// Escalation rates stored on the project, in the same shape returned by `/api/escalation_rates`.
+#[derive(Deserialize, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectEscalationRate {
+    pub release_year: i32,
+    pub year: i32,
+    pub division: String,
+    pub sector: String,
+    pub case: String,
+    pub region: String,
+    pub propane: Option<f64>,
+    pub distillate_fuel_oil: Option<f64>,
+    pub residual_fuel_oil: Option<f64>,
+    pub natural_gas: Option<f64>,
+    pub electricity: Option<f64>,
+    pub coal: Option<f64>,
+}
+
+#[derive(Deserialize, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Ghg {
+    pub data_source: GhgDataSource,
+    pub emissions_rate_type: EmissionsRateType,
+}
+
+#[derive(Deserialize, Serialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct Location {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub country: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub state: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub state_province: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub city: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub zipcode: Option<String>,
+}
+
+#[derive(Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+pub enum DiscountingMethod {
+    #[serde(rename = "End of Year")]
+    EndOfYear,
+    #[serde(rename = "Mid Year")]
+    MidYear,
+}
+
+#[derive(Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+pub enum DollarMethod {
+    Constant,
+    Current,
+}
+
+#[derive(Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+pub enum AnalysisType {
+    #[serde(rename = "Federal Analysis, Financed Project")]
+    FederalFinanced,
+    #[serde(rename = "FEMP Analysis, Energy Project")]
+    FempEnergy,
+    #[serde(rename = "OMB Analysis, Non-Energy Project")]
+    OmbNonEnergy,
+    #[serde(rename = "MILCON Analysis, Energy Project")]
+    MilconEnergy,
+    #[serde(rename = "MILCON Analysis, Non-Energy Project")]
+    MilconNonEnergy,
+    #[serde(rename = "MILCON Analysis, ERCIP (formerly ECIP) Project")]
+    MilconEcip,
+}
+
+#[derive(Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+pub enum Purpose {
+    #[serde(rename = "Public investment and regulatory analyses")]
+    InvestRegulation,
+    #[serde(rename = "Cost-effectiveness, lease-purchase, internal government investment, and asset sales")]
+    CostLease,
+}
+
+#[derive(Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+pub enum Case {
+    #[serde(rename = "REF")]
+    Ref,
+    #[serde(rename = "lowZTC")]
+    LowZtc,
+}
+
+#[derive(Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+pub enum GhgDataSource {
+    #[serde(rename = "NIST NETL")]
+    NistNetl,
+    #[serde(rename = "NREL Cambium")]
+    NrelCambium,
+}
+
+#[derive(Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+pub enum EmissionsRateType {
+    Average,
+    #[serde(rename = "Long-Run Marginal (lrm)")]
+    LongRunMarginal,
+}
+
+#[derive(Deserialize, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Alternative {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<ID>,
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub baseline: Option<bool>,
+    #[serde(rename = "ERCIPBaseCase", skip_serializing_if = "Option::is_none")]
+    pub ercip_base_case: Option<bool>,
+    pub costs: Vec<ID>,
+}
+
+/// A value that is either constant over the study period or given year by year.
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+#[serde(untagged)]
+pub enum NumberOrArray {
+    Number(f64),
+    Array(Vec<f64>),
+}
+
+#[derive(Deserialize, Serialize, Clone)]
+#[serde(tag = "type")]
+pub enum Cost {
+    #[serde(rename = "Capital Investment Cost")]
+    Capital(CapitalCost),
+    #[serde(rename = "Energy")]
+    Energy(EnergyCost),
+    #[serde(rename = "Water")]
+    Water(WaterCost),
+    #[serde(rename = "Capital Replacement Cost")]
+    ReplacementCapital(ReplacementCapitalCost),
+    #[serde(rename = "OMR")]
+    Omr(OmrCost),
+    #[serde(rename = "Non-Recurring Contract")]
+    ImplementationContract(ImplementationContractCost),
+    #[serde(rename = "Recurring Contract")]
+    RecurringContract(RecurringContractCost),
+    #[serde(rename = "Other Monetary")]
+    Other(OtherCost),
+    #[serde(rename = "Other Non-Monetary")]
+    OtherNonMonetary(OtherNonMonetaryCost),
+    #[serde(rename = "ERCIP")]
+    Ercip(ErcipCost),
+}
+
+impl Cost {
+    pub fn id(&self) -> ID {
+        self.base().id
+    }
+
+    pub fn base(&self) -> &BaseCost {
+        match self {
+            Cost::Capital(cost) => &cost.base,
+            Cost::Energy(cost) => &cost.base,
+            Cost::Water(cost) => &cost.base,
+            Cost::ReplacementCapital(cost) => &cost.base,
+            Cost::Omr(cost) => &cost.base,
+            Cost::ImplementationContract(cost) => &cost.base,
+            Cost::RecurringContract(cost) => &cost.base,
+            Cost::Other(cost) => &cost.base,
+            Cost::OtherNonMonetary(cost) => &cost.base,
+            Cost::Ercip(cost) => &cost.base,
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct BaseCost {
+    pub id: ID,
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub location: Option<Location>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cost_savings: Option<bool>,
+}
+
+impl BaseCost {
+    pub fn is_savings(&self) -> bool {
+        self.cost_savings.unwrap_or(false)
+    }
+}
+
+#[derive(Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+pub enum DollarOrPercent {
+    #[serde(rename = "%")]
+    Percent,
+    #[serde(rename = "$")]
+    Dollar,
+}
+
+#[derive(Deserialize, Serialize, Clone)]
+pub struct ResidualValue {
+    pub approach: DollarOrPercent,
+    pub value: f64,
+}
+
+#[derive(Deserialize, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Recurring {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rate_of_recurrence: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub duration: Option<i32>,
+}
+
+#[derive(Deserialize, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct CapitalCost {
+    #[serde(flatten)]
+    pub base: BaseCost,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub residual_value: Option<ResidualValue>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub initial_cost: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub amount_financed: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rate_of_change_value: Option<NumberOrArray>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expected_life: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cost_adjustment: Option<f64>,
+    /// Percent of initial cost paid per year. Must add up to 100%.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub phase_in: Option<Vec<f64>>,
+}
+
+#[derive(Deserialize, Serialize, Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum FuelType {
+    Electricity,
+    #[serde(rename = "Distillate Fuel Oil (#1, #2)")]
+    DistillateOil,
+    #[serde(rename = "Residual Fuel Oil (#4, #5, #6)")]
+    ResidualOil,
+    #[serde(rename = "Natural Gas")]
+    NaturalGas,
+    #[serde(rename = "Liquefied Petroleum Gas / Propane")]
+    Propane,
+    Coal,
+    #[serde(rename = "Other (Steam, etc.)")]
+    Other,
+}
+
+impl FuelType {
+    /// The display name used as a result tag, identical to the serialized name.
+    pub fn label(&self) -> &'static str {
+        match self {
+            FuelType::Electricity => "Electricity",
+            FuelType::DistillateOil => "Distillate Fuel Oil (#1, #2)",
+            FuelType::ResidualOil => "Residual Fuel Oil (#4, #5, #6)",
+            FuelType::NaturalGas => "Natural Gas",
+            FuelType::Propane => "Liquefied Petroleum Gas / Propane",
+            FuelType::Coal => "Coal",
+            FuelType::Other => "Other (Steam, etc.)",
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+pub enum CustomerSector {
+    Residential,
+    Commercial,
+    Industrial,
+    Transportation,
+}
+
+impl CustomerSector {
+    pub fn label(&self) -> &'static str {
+        match self {
+            CustomerSector::Residential => "Residential",
+            CustomerSector::Commercial => "Commercial",
+            CustomerSector::Industrial => "Industrial",
+            CustomerSector::Transportation => "Transportation",
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Unit {
+    #[serde(rename = "kWh")]
+    Kwh,
+    Therm,
+    #[serde(rename = "MBtu")]
+    Mbtu,
+    #[serde(rename = "Mj")]
+    Mj,
+    #[serde(rename = "Gj")]
+    Gj,
+    #[serde(rename = "Cubic meters")]
+    CubicMeters,
+    #[serde(rename = "Cubic feet")]
+    CubicFeet,
+    Liter,
+    #[serde(rename = "1000 liters")]
+    KLiter,
+    Gallon,
+    #[serde(rename = "1000 gallons")]
+    KGallon,
+    #[serde(rename = "kg")]
+    Kg,
+    Pound,
+    Ton,
+}
+
+impl Unit {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Unit::Kwh => "kWh",
+            Unit::Therm => "Therm",
+            Unit::Mbtu => "MBtu",
+            Unit::Mj => "Mj",
+            Unit::Gj => "Gj",
+            Unit::CubicMeters => "Cubic meters",
+            Unit::CubicFeet => "Cubic feet",
+            Unit::Liter => "Liter",
+            Unit::KLiter => "1000 liters",
+            Unit::Gallon => "Gallon",
+            Unit::KGallon => "1000 gallons",
+            Unit::Kg => "kg",
+            Unit::Pound => "Pound",
+            Unit::Ton => "Ton",
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct EnergyCost {
+    #[serde(flatten)]
+    pub base: BaseCost,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub use_index: Option<NumberOrArray>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub custom_escalation: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub escalation: Option<NumberOrArray>,
+    pub fuel_type: FuelType,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub customer_sector: Option<CustomerSector>,
+    pub cost_per_unit: f64,
+    pub annual_consumption: f64,
+    pub unit: Unit,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub demand_charge: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rebate: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub emissions: Option<Vec<f64>>,
+}
+
+#[derive(Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+pub enum Season {
+    Spring,
+    Summer,
+    Autumn,
+    Winter,
+}
+
+#[derive(Deserialize, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SeasonUsage {
+    pub season: Season,
+    pub amount: f64,
+    pub cost_per_unit: f64,
+}
+
+#[derive(Deserialize, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct WaterCost {
+    #[serde(flatten)]
+    pub base: BaseCost,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub use_index: Option<NumberOrArray>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub custom_escalation: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub escalation: Option<NumberOrArray>,
+    pub unit: Unit,
+    pub usage: Vec<SeasonUsage>,
+    pub disposal: Vec<SeasonUsage>,
+}
+
+#[derive(Deserialize, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ReplacementCapitalCost {
+    #[serde(flatten)]
+    pub base: BaseCost,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub residual_value: Option<ResidualValue>,
+    pub initial_occurrence: i32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub initial_cost: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rate_of_change_value: Option<NumberOrArray>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expected_life: Option<i32>,
+}
+
+#[derive(Deserialize, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct OmrCost {
+    #[serde(flatten)]
+    pub base: BaseCost,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub initial_cost: Option<f64>,
+    pub initial_occurrence: i32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub recurring: Option<Recurring>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rate_of_change_value: Option<NumberOrArray>,
+}
+
+#[derive(Deserialize, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ImplementationContractCost {
+    #[serde(flatten)]
+    pub base: BaseCost,
+    pub initial_occurrence: i32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cost: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rate_of_change_value: Option<NumberOrArray>,
+}
+
+#[derive(Deserialize, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct RecurringContractCost {
+    #[serde(flatten)]
+    pub base: BaseCost,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub initial_cost: Option<f64>,
+    pub initial_occurrence: i32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rate_of_change_value: Option<NumberOrArray>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub recurring: Option<Recurring>,
+}
+
+/// The unit of an other cost, either one of the known units or a free text unit entered by the user.
+#[derive(Deserialize, Serialize, Clone)]
+#[serde(untagged)]
+pub enum OtherUnit {
+    Known(Unit),
+    Custom(String),
+}
+
+impl OtherUnit {
+    pub fn label(&self) -> &str {
+        match self {
+            OtherUnit::Known(unit) => unit.label(),
+            OtherUnit::Custom(unit) => unit,
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct OtherCost {
+    #[serde(flatten)]
+    pub base: BaseCost,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tags: Option<Vec<String>>,
+    pub initial_occurrence: i32,
+    pub value_per_unit: f64,
+    pub number_of_units: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub unit: Option<OtherUnit>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub recurring: Option<Recurring>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rate_of_change_value: Option<NumberOrArray>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rate_of_change_units: Option<NumberOrArray>,
+}
+
+#[derive(Deserialize, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct OtherNonMonetaryCost {
+    #[serde(flatten)]
+    pub base: BaseCost,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tags: Option<Vec<String>>,
+    pub initial_occurrence: i32,
+    pub number_of_units: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub unit: Option<OtherUnit>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub recurring: Option<Recurring>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rate_of_change_units: Option<NumberOrArray>,
+}
+
+#[derive(Deserialize, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ErcipCost {
+    #[serde(flatten)]
+    pub base: BaseCost,
+    pub construction_cost: f64,
+    #[serde(rename = "SIOH")]
+    pub sioh: f64,
+    pub design_cost: f64,
+    pub salvage_value: f64,
+    pub public_utility_rebate: f64,
+    pub cybersecurity: f64,
+}
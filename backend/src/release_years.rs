@@ -0,0 +1,255 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use chrono::NaiveDate;
+use diesel::prelude::*;
+use diesel::sql_query;
+use diesel::sql_types::{BigInt, Integer, Nullable, Text};
+use serde::Serialize;
+
+use crate::models::ReleaseYearInfo;
+
+/// The tables with data for each release year. A release year is only complete once all of them have rows for it.
+pub const RELEASE_YEAR_TABLES: [&str; 10] = [
+    "discount_rates",
+    "energy_price_indices",
+    "energy_prices",
+    "escalation_rates",
+    "region_case_ba",
+    "region_case_oil",
+    "region_case_propane_lng",
+    "region_case_reeds",
+    "region_natgas",
+    "scc",
+];
+
+pub const STATUSES: [&str; 3] = ["draft", "published", "deprecated"];
+
+/// Statuses of release years projects may use. Deprecated release years stay usable by existing projects, only
+/// published ones are offered for new projects. Only complete release years can be given either status.
+pub const USABLE_STATUSES: [&str; 2] = ["published", "deprecated"];
+
+#[derive(QueryableByName)]
+struct TableCount {
+    #[diesel(sql_type = Text)]
+    name: String,
+    #[diesel(sql_type = Integer)]
+    release_year: i32,
+    #[diesel(sql_type = BigInt)]
+    rows: i64,
+    #[diesel(sql_type = Nullable<Integer>)]
+    min_year: Option<i32>,
+    #[diesel(sql_type = Nullable<Integer>)]
+    max_year: Option<i32>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TableCoverage {
+    pub table: &'static str,
+    pub rows: i64,
+    /// The first year of the table, which is the shortest study period for `discount_rates`.
+    pub min_year: Option<i32>,
+    pub max_year: Option<i32>,
+}
+
+/// A release year with its metadata and the data loaded for it. Release years with data but no metadata have no status.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReleaseYearCoverage {
+    pub release_year: i32,
+    pub status: Option<String>,
+    pub publication_date: Option<NaiveDate>,
+    pub sources: Vec<String>,
+    pub is_default: bool,
+    /// Whether every release year table has data.
+    pub complete: bool,
+    pub tables: Vec<TableCoverage>,
+}
+
+/// Lists every release year with metadata or data in any release year table, in ascending order.
+pub fn coverage(db: &mut PgConnection) -> QueryResult<Vec<ReleaseYearCoverage>> {
+    // The table names are constants, so they are safe to put in the query
+    let query = RELEASE_YEAR_TABLES
+        .iter()
+        .map(|table| {
+            format!(
+                "SELECT '{table}' AS name, release_year, COUNT(*) AS rows, MIN(year) AS min_year, MAX(year) AS max_year \
+                 FROM {table} GROUP BY release_year"
+            )
+        })
+        .collect::<Vec<_>>()
+        .join(" UNION ALL ");
+    let counts: Vec<TableCount> = sql_query(query).load(db)?;
+
+    let metadata: Vec<ReleaseYearInfo> = {
+        use crate::schema::release_years::dsl::*;
+        release_years.select(ReleaseYearInfo::as_select()).load(db)?
+    };
+
+    let mut counts_by_year: BTreeMap<i32, Vec<TableCount>> = BTreeMap::new();
+    for count in counts {
+        counts_by_year.entry(count.release_year).or_default().push(count);
+    }
+    let mut metadata_by_year: BTreeMap<i32, ReleaseYearInfo> =
+        metadata.into_iter().map(|info| (info.release_year, info)).collect();
+
+    let years: BTreeSet<i32> = counts_by_year.keys().chain(metadata_by_year.keys()).copied().collect();
+
+    Ok(years
+        .into_iter()
+        .map(|year| {
+            let counts = counts_by_year.remove(&year).unwrap_or_default();
+            let tables: Vec<TableCoverage> = RELEASE_YEAR_TABLES
+                .iter()
+                .map(|table| match counts.iter().find(|count| count.name == *table) {
+                    Some(count) => TableCoverage {
+                        table,
+                        rows: count.rows,
+                        min_year: count.min_year,
+                        max_year: count.max_year,
+                    },
+                    None => TableCoverage { table, rows: 0, min_year: None, max_year: None },
+                })
+                .collect();
+            let info = metadata_by_year.remove(&year);
+
+            ReleaseYearCoverage {
+                release_year: year,
+                complete: tables.iter().all(|table| table.rows > 0),
+                status: info.as_ref().map(|info| info.status.clone()),
+                publication_date: info.as_ref().and_then(|info| info.publication_date),
+                sources: info.as_ref().map(|info| info.sources.clone()).unwrap_or_default(),
+                is_default: info.is_some_and(|info| info.is_default),
+                tables,
+            }
+        })
+        .collect())
+}
+
+/// Adds draft metadata for release years that have none, such as those created by an import.
+pub fn add_drafts(years: &BTreeSet<i32>, db: &mut PgConnection) -> QueryResult<usize> {
+    use crate::schema::release_years::dsl::*;
+
+    let drafts: Vec<_> = years.iter().map(|year| release_year.eq(*year)).collect();
+
+    diesel::insert_into(release_years).values(drafts).on_conflict_do_nothing().execute(db)
+}
+
+const USAGE: &str = "Usage: backend release-year <year> [--status draft|published|deprecated] [--default] \
+                     [--publication-date YYYY-MM-DD] [--source <document>]...";
+
+/// Runs `backend release-year`, which creates or updates the metadata of a release year. Sources replace the existing
+/// list if any are given.
+pub fn command(args: &[String], db: &mut PgConnection) -> i32 {
+    let Some(year) = args.first().and_then(|year| year.parse::<i32>().ok()) else {
+        eprintln!("{}", USAGE);
+        return 2;
+    };
+
+    let mut new_status = None;
+    let mut new_default = false;
+    let mut new_publication_date = None;
+    let mut new_sources = Vec::new();
+
+    let mut rest = args[1..].iter();
+    while let Some(flag) = rest.next() {
+        match (flag.as_str(), rest.clone().next()) {
+            ("--status", Some(value)) if STATUSES.contains(&value.as_str()) => new_status = Some(value.clone()),
+            ("--publication-date", Some(value)) => match NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+                Ok(date) => new_publication_date = Some(date),
+                Err(_) => {
+                    eprintln!("Invalid publication date {}\n{}", value, USAGE);
+                    return 2;
+                }
+            },
+            ("--source", Some(value)) => new_sources.push(value.clone()),
+            ("--default", _) => {
+                new_default = true;
+                continue;
+            }
+            _ => {
+                eprintln!("{}", USAGE);
+                return 2;
+            }
+        }
+        rest.next();
+    }
+
+    let current_status = {
+        use crate::schema::release_years::dsl::*;
+        release_years.find(year).select(status).first::<String>(db).optional()
+    };
+    let resulting_status = match current_status {
+        Ok(current) => new_status.clone().or(current).unwrap_or_else(|| "draft".to_string()),
+        Err(err) => {
+            eprintln!("Could not check release year {}: {}", year, err);
+            return 1;
+        }
+    };
+
+    // The default is offered to new projects, which are only offered published release years
+    if new_default && resulting_status != "published" {
+        eprintln!("Release year {} is {}, only a published release year can be the default", year, resulting_status);
+        return 1;
+    }
+
+    // Projects trust the status alone, so a release year missing data can not be made available to them
+    if new_default || new_status.as_deref().is_some_and(|value| USABLE_STATUSES.contains(&value)) {
+        let missing = match coverage(db) {
+            Ok(years) => years
+                .into_iter()
+                .find(|release| release.release_year == year)
+                .map(|release| release.tables.iter().filter(|table| table.rows == 0).map(|table| table.table).collect())
+                .unwrap_or_else(|| RELEASE_YEAR_TABLES.to_vec()),
+            Err(err) => {
+                eprintln!("Could not check release year {}: {}", year, err);
+                return 1;
+            }
+        };
+
+        if !missing.is_empty() {
+            eprintln!("Release year {} has no data in {}", year, missing.join(", "));
+            return 1;
+        }
+    }
+
+    let result = db.transaction(|db| {
+        use crate::schema::release_years::dsl::*;
+
+        add_drafts(&BTreeSet::from([year]), db)?;
+        let mut info = release_years.find(year).select(ReleaseYearInfo::as_select()).first(db)?;
+
+        if let Some(value) = new_status {
+            info.status = value;
+        }
+        if new_publication_date.is_some() {
+            info.publication_date = new_publication_date;
+        }
+        if !new_sources.is_empty() {
+            info.sources = new_sources;
+        }
+        if new_default {
+            diesel::update(release_years.filter(is_default)).set(is_default.eq(false)).execute(db)?;
+            info.is_default = true;
+        }
+
+        diesel::update(release_years.find(year)).set(&info).execute(db)?;
+        Ok::<_, diesel::result::Error>(info)
+    });
+
+    match result {
+        Ok(info) => {
+            println!(
+                "Release year {}: {}{}",
+                info.release_year,
+                info.status,
+                if info.is_default { ", default" } else { "" }
+            );
+            0
+        }
+        Err(err) => {
+            eprintln!("Could not update release year {}: {}", year, err);
+            1
+        }
+    }
+}
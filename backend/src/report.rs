@@ -0,0 +1,1656 @@
+use std::collections::HashMap;
+
+use serde::Deserialize;
+
+use crate::e3::{Measures, Optional, Output};
+use crate::lcc::discount_rate;
+use crate::project::*;
+use crate::xml_import::state_name;
+
+/// The reports produced by BLCC 5.3. Each one is built into a [`Report`] which is then rendered to the requested
+/// format.
+#[derive(Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ReportKind {
+    CashFlow,
+    ComparativeAnalysis,
+    DetailedLcc,
+    Ecip,
+    Input,
+    LowestLcc,
+    SummaryLcc,
+}
+
+impl ReportKind {
+    pub fn title(&self) -> &'static str {
+        match self {
+            ReportKind::CashFlow => "Cash Flow Analysis",
+            ReportKind::ComparativeAnalysis => "Comparative Analysis",
+            ReportKind::DetailedLcc => "Detailed LCC Analysis",
+            ReportKind::Ecip => "ECIP Report",
+            ReportKind::Input => "Input Data Listing",
+            ReportKind::LowestLcc => "Lowest LCC",
+            ReportKind::SummaryLcc => "Summary LCC",
+        }
+    }
+
+    /// The name used in URLs, the same as the serialized name.
+    pub fn slug(&self) -> &'static str {
+        match self {
+            ReportKind::CashFlow => "cash_flow",
+            ReportKind::ComparativeAnalysis => "comparative_analysis",
+            ReportKind::DetailedLcc => "detailed_lcc",
+            ReportKind::Ecip => "ecip",
+            ReportKind::Input => "input",
+            ReportKind::LowestLcc => "lowest_lcc",
+            ReportKind::SummaryLcc => "summary_lcc",
+        }
+    }
+
+    /// The name used for downloaded files, matching the legacy report file names.
+    pub fn file_name(&self) -> &'static str {
+        match self {
+            ReportKind::CashFlow => "BLCC Cash Flow Report",
+            ReportKind::ComparativeAnalysis => "BLCC Comparative Analysis Report",
+            ReportKind::DetailedLcc => "BLCC Detailed LCC Report",
+            ReportKind::Ecip => "BLCC ECIP Report",
+            ReportKind::Input => "BLCC Input Report",
+            ReportKind::LowestLcc => "BLCC Lowest LCC Report",
+            ReportKind::SummaryLcc => "BLCC Summary LCC Report",
+        }
+    }
+}
+
+/// A format independent report: a title followed by sections of label/value fields, tables and notes.
+pub struct Report {
+    pub title: String,
+    pub subtitle: String,
+    pub sections: Vec<Section>,
+}
+
+pub struct Section {
+    pub heading: String,
+    pub blocks: Vec<Block>,
+}
+
+pub enum Block {
+    Fields(Vec<(String, String)>),
+    Table(Table),
+    Note(String),
+}
+
+pub struct Table {
+    pub caption: Option<String>,
+    pub columns: Vec<String>,
+    pub rows: Vec<Row>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum RowStyle {
+    Normal,
+    /// A row introducing the rows below it, usually without values.
+    Group,
+    /// A subtotal or total of the rows above it.
+    Total,
+}
+
+pub struct Row {
+    pub cells: Vec<String>,
+    pub style: RowStyle,
+}
+
+impl Row {
+    fn new(cells: Vec<String>) -> Self {
+        Row { cells, style: RowStyle::Normal }
+    }
+
+    fn group(label: &str) -> Self {
+        Row { cells: vec![label.to_string()], style: RowStyle::Group }
+    }
+
+    fn total(cells: Vec<String>) -> Self {
+        Row { cells, style: RowStyle::Total }
+    }
+}
+
+/// The result tags summed into each line of the LCC summary, in the order of the legacy reports.
+const SUMMARY_LINES: &[(&str, &str)] = &[
+    ("Initial Cost", "Initial Investment"),
+    ("Energy Consumption Costs", "Energy"),
+    ("Energy Demand Costs", "Demand Charge"),
+    ("Energy Utility Rebates", "Rebate"),
+    ("Water Usage Costs", "Usage"),
+    ("Water Disposal Costs", "Disposal"),
+    ("Annually Recurring OM&R Costs", "OMR Recurring"),
+    ("Non-Annually Recurring OM&R Costs", "OMR Non-Recurring"),
+    ("Replacement Costs", "Replacement Capital"),
+    ("Non-Recurring Contract Costs", "Implementation Contract Cost"),
+    ("Recurring Contract Costs", "Recurring Contract Cost"),
+    ("Other Monetary Costs", "Other"),
+    ("Less Remaining Value", "Residual Value"),
+];
+
+const FUEL_TYPES: [FuelType; 7] = [
+    FuelType::Electricity,
+    FuelType::NaturalGas,
+    FuelType::DistillateOil,
+    FuelType::ResidualOil,
+    FuelType::Propane,
+    FuelType::Coal,
+    FuelType::Other,
+];
+
+/// Builds the given report from a project and its results. Fails if the report does not apply to the project.
+pub fn build(kind: ReportKind, data: &ProjectData, output: &Output) -> Result<Report, String> {
+    let context = Context::new(data, output)?;
+
+    let sections = match kind {
+        ReportKind::CashFlow => context.cash_flow(),
+        ReportKind::ComparativeAnalysis => context.comparative_analysis()?,
+        ReportKind::DetailedLcc => context.detailed_lcc(),
+        ReportKind::Ecip => context.ecip()?,
+        ReportKind::Input => context.input(),
+        ReportKind::LowestLcc => context.lowest_lcc(),
+        ReportKind::SummaryLcc => context.summary_lcc(),
+    };
+
+    Ok(Report {
+        title: format!("NIST BLCC: {}", kind.title()),
+        subtitle: "Consistent with Federal Life Cycle Cost Methodology and Procedures, 10 CFR, Part 436, Subpart A"
+            .to_string(),
+        sections,
+    })
+}
+
+struct Context<'a> {
+    data: &'a ProjectData,
+    output: &'a Output,
+    costs: HashMap<ID, &'a Cost>,
+    study_period: i32,
+    discount_rate: f64,
+}
+
+impl<'a> Context<'a> {
+    fn new(data: &'a ProjectData, output: &'a Output) -> Result<Self, String> {
+        let study_period = data
+            .project
+            .study_period
+            .ok_or_else(|| "Project does not have a study period".to_string())?;
+
+        Ok(Context {
+            data,
+            output,
+            costs: data.costs.iter().map(|cost| (cost.id(), cost)).collect(),
+            study_period,
+            discount_rate: discount_rate(&data.project),
+        })
+    }
+
+    fn project(&self) -> &Project {
+        &self.data.project
+    }
+
+    /// The alternatives with the ID their results are reported under.
+    fn alternatives(&self) -> impl Iterator<Item = (ID, &'a Alternative)> {
+        self.data
+            .alternatives
+            .iter()
+            .enumerate()
+            .map(|(index, alternative)| (alternative.id.unwrap_or(index as ID), alternative))
+    }
+
+    fn baseline(&self) -> Option<(ID, &'a Alternative)> {
+        self.alternatives()
+            .find(|(_, alternative)| alternative.baseline.unwrap_or(false))
+            .or_else(|| self.alternatives().next())
+    }
+
+    fn alternative_costs(&self, alternative: &Alternative) -> Vec<&'a Cost> {
+        alternative.costs.iter().filter_map(|id| self.costs.get(id).copied()).collect()
+    }
+
+    fn measure(&self, alt_id: ID) -> Option<&'a Measures> {
+        self.output.measure.iter().find(|measure| measure.alt_id == alt_id)
+    }
+
+    fn optional(&self, alt_id: ID, tag: &str) -> Option<&'a Optional> {
+        self.output
+            .optional
+            .iter()
+            .find(|optional| optional.alt_id == alt_id && optional.tag == tag)
+    }
+
+    /// The present value of all flows with the given tag.
+    fn present_value(&self, alt_id: ID, tag: &str) -> f64 {
+        self.measure(alt_id)
+            .and_then(|measure| measure.total_tag_flows.get(tag).copied())
+            .unwrap_or(0.0)
+    }
+
+    fn quantity(&self, alt_id: ID, tag: &str) -> f64 {
+        self.measure(alt_id)
+            .and_then(|measure| measure.quantity_sum.get(tag).copied())
+            .unwrap_or(0.0)
+    }
+
+    fn quantity_unit(&self, alt_id: ID, tag: &str) -> Option<String> {
+        self.measure(alt_id)
+            .and_then(|measure| measure.quantity_units.get(tag).cloned())
+            .flatten()
+    }
+
+    /// The non-discounted value of a tag in the given year of the study period.
+    fn yearly(&self, alt_id: ID, tag: &str, year: usize) -> f64 {
+        self.optional(alt_id, tag)
+            .and_then(|optional| optional.total_tag_cashflow_non_discounted.get(year).copied())
+            .unwrap_or(0.0)
+    }
+
+    fn service_years(&self) -> f64 {
+        (self.study_period - self.project().construction_period).max(1) as f64
+    }
+
+    /// Converts a present value into an equivalent uniform annual value over the study period.
+    fn annual_value(&self, present_value: f64) -> f64 {
+        let offset = match self.project().discounting_method {
+            Some(DiscountingMethod::MidYear) => 0.5,
+            _ => 0.0,
+        };
+        let factor: f64 = (1..=self.study_period)
+            .map(|t| (1.0 + self.discount_rate).powf(-(t as f64 - offset)))
+            .sum();
+
+        if factor > 0.0 {
+            present_value / factor
+        } else {
+            present_value
+        }
+    }
+
+    fn base_year(&self) -> i32 {
+        self.project().release_year
+    }
+
+    fn location(&self) -> String {
+        let location = &self.project().location;
+        let state = location
+            .state
+            .as_deref()
+            .map(|state| state_name(state).unwrap_or(state))
+            .or(location.state_province.as_deref());
+
+        [location.city.as_deref(), state, location.country.as_deref()]
+            .into_iter()
+            .flatten()
+            .filter(|part| !part.is_empty())
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    fn general_information(&self, kind_specific: Vec<(String, String)>) -> Section {
+        let project = self.project();
+        let base_year = self.base_year();
+        let service_year = base_year + project.construction_period;
+
+        let mut fields = vec![
+            field("Project Name:", project.name.clone().unwrap_or_default()),
+            field("Analysis Type:", project.analysis_type.map(analysis_type).unwrap_or_default()),
+            field("Project Location:", self.location()),
+            field("Analyst:", project.analyst.clone().unwrap_or_default()),
+            field("Comment:", project.description.clone().unwrap_or_default()),
+            field("Base Date:", format!("January 1, {}", base_year)),
+            field("Service Date:", format!("January 1, {}", service_year)),
+            field(
+                "Study Period:",
+                format!(
+                    "{} (January 1, {} through December 31, {})",
+                    years(self.study_period),
+                    base_year,
+                    base_year + self.study_period - 1
+                ),
+            ),
+            field("Discount Rate:", percent(self.discount_rate)),
+            field(
+                "Discounting Convention:",
+                match project.discounting_method {
+                    Some(DiscountingMethod::MidYear) => "Mid-Year",
+                    _ => "End-of-Year",
+                },
+            ),
+        ];
+        fields.extend(kind_specific);
+
+        let note = match project.dollar_method {
+            DollarMethod::Constant => "Discount and Escalation Rates are REAL (exclusive of general inflation)",
+            DollarMethod::Current => "Discount and Escalation Rates are NOMINAL (inclusive of general inflation)",
+        };
+
+        Section {
+            heading: "General Information".to_string(),
+            blocks: vec![Block::Fields(fields), Block::Note(note.to_string())],
+        }
+    }
+
+    /*
+     * Summary LCC
+     */
+
+    fn summary_lcc(&self) -> Vec<Section> {
+        let mut sections = vec![self.general_information(vec![])];
+
+        for (alt_id, alternative) in self.alternatives() {
+            let mut rows: Vec<Row> = SUMMARY_LINES
+                .iter()
+                .map(|(label, tag)| self.value_row(label, self.present_value(alt_id, tag)))
+                .collect();
+            rows.push(Row::total(self.value_row("Total Life-Cycle Cost", self.present_value(alt_id, "LCC")).cells));
+
+            sections.push(Section {
+                heading: format!("Alternative: {}", alternative.name),
+                blocks: vec![Block::Table(Table {
+                    caption: Some("LCC Summary".to_string()),
+                    columns: columns(&["", "Present Value", "Annual Value"]),
+                    rows,
+                })],
+            });
+        }
+
+        sections
+    }
+
+    /// A row with a present value and the equivalent annual value.
+    fn value_row(&self, label: &str, present_value: f64) -> Row {
+        Row::new(vec![label.to_string(), dollars(present_value), dollars(self.annual_value(present_value))])
+    }
+
+    /*
+     * Lowest LCC
+     */
+
+    fn lowest_lcc(&self) -> Vec<Section> {
+        let mut alternatives: Vec<(String, f64, f64)> = self
+            .alternatives()
+            .map(|(alt_id, alternative)| {
+                (
+                    alternative.name.clone(),
+                    self.present_value(alt_id, "Initial Investment"),
+                    self.present_value(alt_id, "LCC"),
+                )
+            })
+            .collect();
+        alternatives.sort_by(|a, b| a.1.total_cmp(&b.1));
+
+        let lowest = alternatives.iter().map(|(_, _, lcc)| *lcc).fold(f64::INFINITY, f64::min);
+        let rows = alternatives
+            .into_iter()
+            .map(|(name, investment, lcc)| {
+                let marker = if lcc == lowest { "*" } else { "" };
+                Row::new(vec![name, dollars(investment), dollars(lcc), marker.to_string()])
+            })
+            .collect();
+
+        vec![
+            self.general_information(vec![]),
+            Section {
+                heading: "Lowest LCC".to_string(),
+                blocks: vec![
+                    Block::Note(
+                        "Comparative Present-Value Costs of Alternatives (Shown in Ascending Order of Initial Cost, * = \
+                         Lowest LCC)"
+                            .to_string(),
+                    ),
+                    Block::Table(Table {
+                        caption: None,
+                        columns: columns(&["Alternative", "Initial Cost (PV)", "Life Cycle Cost (PV)", ""]),
+                        rows,
+                    }),
+                ],
+            },
+        ]
+    }
+
+    /*
+     * Comparative Analysis
+     */
+
+    fn comparative_analysis(&self) -> Result<Vec<Section>, String> {
+        let (base_id, base) = self.baseline().ok_or_else(|| "Project does not have any alternatives".to_string())?;
+        let mut sections = vec![];
+
+        for (alt_id, alternative) in self.alternatives().filter(|(alt_id, _)| *alt_id != base_id) {
+            sections.push(self.general_information(vec![
+                field("Base Case:", base.name.clone()),
+                field("Alternative:", alternative.name.clone()),
+            ]));
+
+            let compare = |label: &str, tags: &[&str]| -> Row {
+                let base_value: f64 = tags.iter().map(|tag| self.present_value(base_id, tag)).sum();
+                let alt_value: f64 = tags.iter().map(|tag| self.present_value(alt_id, tag)).sum();
+
+                Row::new(vec![
+                    label.to_string(),
+                    dollars(base_value),
+                    dollars(alt_value),
+                    dollars(base_value - alt_value),
+                ])
+            };
+
+            let future = |id: ID| self.present_value(id, "LCC") - self.present_value(id, "Initial Investment");
+            let (base_future, alt_future) = (future(base_id), future(alt_id));
+            let (base_lcc, alt_lcc) = (self.present_value(base_id, "LCC"), self.present_value(alt_id, "LCC"));
+
+            let rows = vec![
+                Row::group("Initial Investment Costs:"),
+                compare("Capital Requirements as of Base Date", &["Initial Investment"]),
+                Row::group("Future Costs:"),
+                compare("Energy Consumption Costs", &["Energy"]),
+                compare("Energy Demand Charges", &["Demand Charge"]),
+                compare("Energy Utility Rebates", &["Rebate"]),
+                compare("Water Costs", &["Usage", "Disposal"]),
+                compare("Recurring and Non-Recurring OM&R Costs", &["OMR"]),
+                compare("Capital Replacements", &["Replacement Capital"]),
+                compare("Contract Costs", &["Implementation Contract Cost", "Recurring Contract Cost"]),
+                compare("Other Monetary Costs", &["Other"]),
+                compare("Residual Value at End of Study Period", &["Residual Value"]),
+                Row::total(vec![
+                    "Subtotal (for Future Cost Items)".to_string(),
+                    dollars(base_future),
+                    dollars(alt_future),
+                    dollars(base_future - alt_future),
+                ]),
+                Row::total(vec![
+                    "Total PV Life-Cycle Cost".to_string(),
+                    dollars(base_lcc),
+                    dollars(alt_lcc),
+                    dollars(base_lcc - alt_lcc),
+                ]),
+            ];
+
+            let measure = self.measure(alt_id);
+            let base_measure = self.measure(base_id);
+            let non_investment_savings = base_measure.map(|m| m.total_cost_non_invest).unwrap_or(0.0)
+                - measure.map(|m| m.total_cost_non_invest).unwrap_or(0.0);
+            let increased_investment = measure.map(|m| m.total_costs_invest).unwrap_or(0.0)
+                - base_measure.map(|m| m.total_costs_invest).unwrap_or(0.0);
+
+            let payback = |value: Option<f64>| match value {
+                Some(year) => format!("{}", year as i32),
+                None => "Not Reached".to_string(),
+            };
+
+            sections.push(Section {
+                heading: "Comparison of Present-Value Costs".to_string(),
+                blocks: vec![Block::Table(Table {
+                    caption: None,
+                    columns: columns(&["PV Life-Cycle Cost", "Base Case", "Alternative", "Savings from Alternative"]),
+                    rows,
+                })],
+            });
+
+            sections.push(Section {
+                heading: "Net Savings from Alternative Compared with Base Case".to_string(),
+                blocks: vec![
+                    Block::Fields(vec![
+                        field("PV of Non-Investment Savings", dollars(non_investment_savings)),
+                        field("- Increased Total Investment", dollars(increased_investment)),
+                        field("Net Savings", dollars(measure.and_then(|m| m.net_savings).unwrap_or(0.0))),
+                        field("Savings-to-Investment Ratio (SIR)", ratio(measure.and_then(|m| m.sir))),
+                        field(
+                            "Adjusted Internal Rate of Return (AIRR)",
+                            measure.and_then(|m| m.airr).map(percent).unwrap_or_else(|| "Not Computable".to_string()),
+                        ),
+                        field("Simple Payback occurs in year", payback(measure.and_then(|m| m.spp))),
+                        field("Discounted Payback occurs in year", payback(measure.and_then(|m| m.dpp))),
+                    ]),
+                    Block::Note("Estimated Years to Payback (from beginning of Service Period)".to_string()),
+                ],
+            });
+
+            sections.push(Section {
+                heading: "Energy Savings Summary".to_string(),
+                blocks: vec![Block::Table(self.energy_savings(base_id, alt_id))],
+            });
+
+            sections.push(Section {
+                heading: "Emissions Reduction Summary".to_string(),
+                blocks: vec![Block::Table(self.emissions_reduction(base_id, alt_id))],
+            });
+        }
+
+        if sections.is_empty() {
+            return Err("A comparative analysis requires at least two alternatives".to_string());
+        }
+
+        Ok(sections)
+    }
+
+    fn fuels(&self, alt_ids: &[ID]) -> Vec<FuelType> {
+        FUEL_TYPES
+            .into_iter()
+            .filter(|fuel| alt_ids.iter().any(|id| self.optional(*id, fuel.label()).is_some()))
+            .collect()
+    }
+
+    fn energy_savings(&self, base_id: ID, alt_id: ID) -> Table {
+        let years = self.service_years();
+
+        let rows = self
+            .fuels(&[base_id, alt_id])
+            .into_iter()
+            .map(|fuel| {
+                let tag = fuel.label();
+                let unit = self
+                    .quantity_unit(base_id, tag)
+                    .or_else(|| self.quantity_unit(alt_id, tag))
+                    .unwrap_or_default();
+                let (base, alt) = (self.quantity(base_id, tag), self.quantity(alt_id, tag));
+
+                Row::new(vec![
+                    tag.to_string(),
+                    quantity(base / years, 1, &unit),
+                    quantity(alt / years, 1, &unit),
+                    quantity((base - alt) / years, 1, &unit),
+                    quantity(base - alt, 1, &unit),
+                ])
+            })
+            .collect();
+
+        Table {
+            caption: Some("Energy Savings Summary (in stated units)".to_string()),
+            columns: columns(&[
+                "Energy Type",
+                "Base Case Average Annual",
+                "Alternative Average Annual",
+                "Average Annual Savings",
+                "Life-Cycle Savings",
+            ]),
+            rows,
+        }
+    }
+
+    fn emissions_reduction(&self, base_id: ID, alt_id: ID) -> Table {
+        let years = self.service_years();
+        let row = |label: &str, tag: &str| {
+            let (base, alt) = (self.quantity(base_id, tag), self.quantity(alt_id, tag));
+
+            Row::new(vec![
+                label.to_string(),
+                quantity(base / years, 2, "kg CO2e"),
+                quantity(alt / years, 2, "kg CO2e"),
+                quantity((base - alt) / years, 2, "kg CO2e"),
+                quantity(base - alt, 2, "kg CO2e"),
+            ])
+        };
+
+        let mut rows: Vec<Row> = self
+            .fuels(&[base_id, alt_id])
+            .into_iter()
+            .map(|fuel| row(fuel.label(), &format!("{} Emissions", fuel.label())))
+            .collect();
+        rows.push(Row::total(row("Total", "Emissions").cells));
+
+        Table {
+            caption: None,
+            columns: columns(&[
+                "Energy Type",
+                "Base Case Average Annual",
+                "Alternative Average Annual",
+                "Average Annual Reduction",
+                "Life-Cycle Reduction",
+            ]),
+            rows,
+        }
+    }
+
+    /*
+     * Detailed LCC
+     */
+
+    fn detailed_lcc(&self) -> Vec<Section> {
+        let mut sections = vec![self.general_information(vec![])];
+
+        for (alt_id, alternative) in self.alternatives() {
+            let costs = self.alternative_costs(alternative);
+            let mut blocks = vec![];
+
+            let capital: Vec<&CapitalCost> = costs
+                .iter()
+                .filter_map(|cost| match cost {
+                    Cost::Capital(cost) => Some(cost),
+                    _ => None,
+                })
+                .collect();
+            if !capital.is_empty() {
+                let mut rows: Vec<Row> = capital
+                    .iter()
+                    .map(|cost| {
+                        Row::new(vec![
+                            cost.base.name.clone(),
+                            dollars(cost.initial_cost.unwrap_or(0.0)),
+                            dollars(cost.amount_financed.unwrap_or(0.0)),
+                            cost.expected_life.map(years).unwrap_or_default(),
+                        ])
+                    })
+                    .collect();
+                rows.push(Row::total(vec![
+                    "Total".to_string(),
+                    dollars(capital.iter().map(|cost| cost.initial_cost.unwrap_or(0.0)).sum()),
+                    dollars(capital.iter().map(|cost| cost.amount_financed.unwrap_or(0.0)).sum()),
+                    String::new(),
+                ]));
+
+                blocks.push(Block::Table(Table {
+                    caption: Some("Initial Cost Data (not Discounted)".to_string()),
+                    columns: columns(&["Component", "Paid By Agency", "Financed", "Expected Life"]),
+                    rows,
+                }));
+            }
+
+            let energy: Vec<Row> = costs
+                .iter()
+                .filter_map(|cost| match cost {
+                    Cost::Energy(cost) => Some(Row::new(vec![
+                        if cost.base.name == cost.fuel_type.label() {
+                            cost.base.name.clone()
+                        } else {
+                            format!("{}: {}", cost.fuel_type.label(), cost.base.name)
+                        },
+                        quantity(cost.annual_consumption, 1, cost.unit.label()),
+                        unit_price(cost.cost_per_unit),
+                        dollars(cost.annual_consumption * cost.cost_per_unit),
+                        dollars(cost.demand_charge.unwrap_or(0.0)),
+                        dollars(cost.rebate.unwrap_or(0.0)),
+                    ])),
+                    _ => None,
+                })
+                .collect();
+            if !energy.is_empty() {
+                blocks.push(Block::Table(Table {
+                    caption: Some("Energy Costs (base-year dollars)".to_string()),
+                    columns: columns(&[
+                        "Energy",
+                        "Annual Usage",
+                        "Price/Unit",
+                        "Annual Cost",
+                        "Annual Demand",
+                        "Annual Rebate",
+                    ]),
+                    rows: energy,
+                }));
+            }
+
+            blocks.push(Block::Table(self.detailed_lcc_table(alt_id, &costs)));
+            blocks.push(Block::Table(self.emissions_summary(alt_id)));
+
+            sections.push(Section { heading: format!("Alternative: {}", alternative.name), blocks });
+        }
+
+        sections
+    }
+
+    fn detailed_lcc_table(&self, alt_id: ID, costs: &[&Cost]) -> Table {
+        let mut rows = vec![];
+        let group = |rows: &mut Vec<Row>, heading: &str, lines: Vec<(String, f64)>, subtotal: &str| {
+            if lines.is_empty() {
+                return;
+            }
+
+            rows.push(Row::group(heading));
+            let total: f64 = lines.iter().map(|(_, value)| value).sum();
+            for (label, value) in lines {
+                rows.push(self.value_row(&format!("   {}", label), value));
+            }
+            rows.push(Row::total(self.value_row(subtotal, total).cells));
+        };
+
+        rows.push(self.value_row("Initial Capital Costs", self.present_value(alt_id, "Initial Investment")));
+
+        let tags = |tags: &[(&str, &str)]| -> Vec<(String, f64)> {
+            tags.iter()
+                .map(|(label, tag)| (label.to_string(), self.present_value(alt_id, tag)))
+                .collect()
+        };
+        let by_cost = |filter: fn(&Cost) -> bool| -> Vec<(String, f64)> {
+            costs
+                .iter()
+                .filter(|cost| filter(cost))
+                .map(|cost| (cost.base().name.clone(), self.present_value(alt_id, &cost.id().to_string())))
+                .collect()
+        };
+
+        group(
+            &mut rows,
+            "Contract-Related Costs",
+            tags(&[
+                ("Annually Recurring Contract Costs", "Recurring Contract Cost"),
+                ("Non-Annually Recurring Contract Costs", "Implementation Contract Cost"),
+            ]),
+            "Subtotal (for Contract):",
+        );
+        group(
+            &mut rows,
+            "Energy Costs",
+            tags(&[
+                ("Energy Consumption Costs", "Energy"),
+                ("Energy Demand Charges", "Demand Charge"),
+                ("Energy Utility Rebates", "Rebate"),
+            ]),
+            "Subtotal (for Energy):",
+        );
+        group(
+            &mut rows,
+            "Water Costs",
+            tags(&[("Water Usage Costs", "Usage"), ("Water Disposal Costs", "Disposal")]),
+            "Subtotal (for Water):",
+        );
+        group(
+            &mut rows,
+            "Operating, Maintenance & Repair Costs",
+            by_cost(|cost| matches!(cost, Cost::Omr(_))),
+            "Subtotal (for OM&R):",
+        );
+        group(
+            &mut rows,
+            "Replacements to Capital Components",
+            by_cost(|cost| matches!(cost, Cost::ReplacementCapital(_))),
+            "Subtotal (for Replacements):",
+        );
+        group(
+            &mut rows,
+            "Other Costs",
+            by_cost(|cost| matches!(cost, Cost::Other(_))),
+            "Subtotal (for Other):",
+        );
+        rows.push(self.value_row("Residual Value", self.present_value(alt_id, "Residual Value")));
+        rows.push(Row::total(self.value_row("Total Life-Cycle Cost", self.present_value(alt_id, "LCC")).cells));
+
+        Table {
+            caption: Some("Life-Cycle Cost Analysis".to_string()),
+            columns: columns(&["", "Present Value", "Annual Value"]),
+            rows,
+        }
+    }
+
+    fn emissions_summary(&self, alt_id: ID) -> Table {
+        let years = self.service_years();
+        let row = |label: &str, tag: &str| {
+            let total = self.quantity(alt_id, tag);
+            Row::new(vec![
+                label.to_string(),
+                quantity(total / years, 2, "kg CO2e"),
+                quantity(total, 2, "kg CO2e"),
+            ])
+        };
+
+        let mut rows: Vec<Row> = self
+            .fuels(&[alt_id])
+            .into_iter()
+            .map(|fuel| row(fuel.label(), &format!("{} Emissions", fuel.label())))
+            .collect();
+        rows.push(Row::total(row("Total", "Emissions").cells));
+
+        Table {
+            caption: Some("Emissions Summary".to_string()),
+            columns: columns(&["Energy", "Annual", "Life-Cycle"]),
+            rows,
+        }
+    }
+
+    /*
+     * Cash Flow
+     */
+
+    fn cash_flow(&self) -> Vec<Section> {
+        let dollars_note = match self.project().dollar_method {
+            DollarMethod::Constant => "All costs in constant dollars (excluding general inflation)",
+            DollarMethod::Current => "All costs in current dollars (including general inflation)",
+        };
+        let mut sections = vec![self.general_information(vec![])];
+
+        let categories: &[(&str, &[&str])] = &[
+            ("Initial", &["Initial Investment"]),
+            ("Energy", &["Energy", "Demand Charge", "Rebate"]),
+            ("Water", &["Usage", "Disposal"]),
+            ("OM&R", &["OMR"]),
+            ("Replacement", &["Replacement Capital"]),
+            ("Residual", &["Residual Value"]),
+            ("Contract", &["Implementation Contract Cost", "Recurring Contract Cost"]),
+            ("Other", &["Other"]),
+        ];
+
+        for (alt_id, alternative) in self.alternatives() {
+            let length = self.study_period.max(0) as usize + 1;
+            let values: Vec<(&str, Vec<f64>)> = categories
+                .iter()
+                .map(|(label, tags)| {
+                    let yearly = (0..length)
+                        .map(|year| tags.iter().map(|tag| self.yearly(alt_id, tag, year)).sum())
+                        .collect();
+                    (*label, yearly)
+                })
+                // Only show the kinds of costs the alternative has
+                .filter(|(_, yearly): &(&str, Vec<f64>)| yearly.iter().any(|value| *value != 0.0))
+                .collect();
+            let totals: Vec<f64> = (0..length)
+                .map(|year| values.iter().map(|(_, yearly)| yearly[year]).sum())
+                .collect();
+
+            let mut header = vec!["Year Beginning"];
+            header.extend(values.iter().map(|(label, _)| *label));
+            header.push("Total");
+
+            let mut rows: Vec<Row> = (0..length)
+                .map(|year| {
+                    // Initial costs occur on the base date, every later year is labeled by the year it begins
+                    let label = match year {
+                        0 => "Base Date".to_string(),
+                        _ => (self.base_year() + year as i32 - 1).to_string(),
+                    };
+                    let mut cells = vec![label];
+                    cells.extend(values.iter().map(|(_, yearly)| dollars(yearly[year])));
+                    cells.push(dollars(totals[year]));
+                    Row::new(cells)
+                })
+                .collect();
+
+            let mut total = vec!["Total".to_string()];
+            total.extend(values.iter().map(|(_, yearly)| dollars(yearly.iter().sum())));
+            total.push(dollars(totals.iter().sum()));
+            rows.push(Row::total(total));
+
+            sections.push(Section {
+                heading: format!("Alternative: {}", alternative.name),
+                blocks: vec![
+                    Block::Note(dollars_note.to_string()),
+                    Block::Table(Table { caption: Some("Sum of All Cash Flows".to_string()), columns: columns(&header), rows }),
+                ],
+            });
+        }
+
+        sections
+    }
+
+    /*
+     * ECIP
+     */
+
+    fn ecip(&self) -> Result<Vec<Section>, String> {
+        if self.project().analysis_type != Some(AnalysisType::MilconEcip) {
+            return Err("The ECIP report is only available for MILCON ERCIP analyses".to_string());
+        }
+
+        // ERCIP alternatives enter their energy and non-energy costs as savings against the ERCIP base case, which
+        // is the baseline of the project
+        let (base_id, _) = self.baseline().ok_or_else(|| "Project does not have any alternatives".to_string())?;
+
+        let project = self.project();
+        let mut sections = vec![];
+
+        for (alt_id, alternative) in self.alternatives().filter(|(alt_id, _)| *alt_id != base_id) {
+            let ercip: Vec<&ErcipCost> = self
+                .alternative_costs(alternative)
+                .into_iter()
+                .filter_map(|cost| match cost {
+                    Cost::Ercip(cost) => Some(cost),
+                    _ => None,
+                })
+                .collect();
+            let total = |value: fn(&ErcipCost) -> f64| -> f64 { ercip.iter().map(|cost| value(cost)).sum() };
+
+            let construction = total(|cost| cost.construction_cost);
+            let sioh = total(|cost| cost.sioh);
+            let design = total(|cost| cost.design_cost);
+            let salvage = total(|cost| cost.salvage_value);
+            let rebate = total(|cost| cost.public_utility_rebate);
+            let cybersecurity = total(|cost| cost.cybersecurity);
+            let total_cost = construction + sioh + design;
+            let investment = total_cost - salvage - rebate - cybersecurity;
+
+            sections.push(Section {
+                heading: format!("ECIP Report: {}", alternative.name),
+                blocks: vec![
+                    Block::Note(format!(
+                        "The LCC calculations are based on the FEMP discount rates and energy price escalation rates \
+                         of the {} release.",
+                        project.release_year
+                    )),
+                    Block::Fields(vec![
+                        field("Location:", self.location()),
+                        field("Discount Rate:", percent(self.discount_rate)),
+                        field("Project Title:", project.name.clone().unwrap_or_default()),
+                        field("Analyst:", project.analyst.clone().unwrap_or_default()),
+                        field("Base Date:", format!("January 1, {}", self.base_year())),
+                        field("BOD:", format!("January 1, {}", self.base_year() + project.construction_period)),
+                        field("Economic Life:", years(self.study_period - project.construction_period)),
+                    ]),
+                ],
+            });
+
+            sections.push(Section {
+                heading: "1. Investment".to_string(),
+                blocks: vec![Block::Table(Table {
+                    caption: None,
+                    columns: columns(&["", ""]),
+                    rows: vec![
+                        Row::new(vec!["Construction Cost".to_string(), dollars(construction)]),
+                        Row::new(vec!["SIOH".to_string(), dollars(sioh)]),
+                        Row::new(vec!["Design Cost".to_string(), dollars(design)]),
+                        Row::total(vec!["Total Cost".to_string(), dollars(total_cost)]),
+                        Row::new(vec!["Salvage Value of Existing Equipment".to_string(), dollars(salvage)]),
+                        Row::new(vec!["Public Utility Company".to_string(), dollars(rebate)]),
+                        Row::new(vec!["Cybersecurity".to_string(), dollars(cybersecurity)]),
+                        Row::total(vec!["Total Investment".to_string(), dollars(investment)]),
+                    ],
+                })],
+            });
+
+            // Savings in the first year of the service period
+            let first_year = (project.construction_period + 1).min(self.study_period).max(0) as usize;
+            let savings = |tags: &[&str]| -> (f64, f64) {
+                let annual = tags
+                    .iter()
+                    .map(|tag| self.yearly(base_id, tag, first_year) - self.yearly(alt_id, tag, first_year))
+                    .sum();
+                let discounted = tags
+                    .iter()
+                    .map(|tag| self.present_value(base_id, tag) - self.present_value(alt_id, tag))
+                    .sum();
+                (annual, discounted)
+            };
+            let usage = |tag: &str| -> f64 {
+                let quantity = |id: ID| {
+                    self.optional(id, tag)
+                        .and_then(|optional| optional.total_tag_quantity.get(first_year).copied())
+                        .unwrap_or(0.0)
+                };
+                quantity(base_id) - quantity(alt_id)
+            };
+
+            let mut energy_rows = vec![];
+            let (mut energy_annual, mut energy_discounted) = (0.0, 0.0);
+            for fuel in self.fuels(&[base_id, alt_id]) {
+                let tag = fuel.label();
+                let (annual, discounted) = savings(&[tag]);
+                let unit = self
+                    .quantity_unit(base_id, tag)
+                    .or_else(|| self.quantity_unit(alt_id, tag))
+                    .unwrap_or_default();
+                energy_annual += annual;
+                energy_discounted += discounted;
+
+                energy_rows.push(Row::new(vec![
+                    tag.to_string(),
+                    quantity(usage(tag), 1, &unit),
+                    dollars(annual),
+                    factor(discounted, annual),
+                    dollars(discounted),
+                ]));
+            }
+            energy_rows.push(Row::total(vec![
+                "Energy Subtotal".to_string(),
+                String::new(),
+                dollars(energy_annual),
+                String::new(),
+                dollars(energy_discounted),
+            ]));
+
+            let (water_annual, water_discounted) = savings(&["Usage", "Disposal"]);
+            energy_rows.push(Row::total(vec![
+                "Water Subtotal".to_string(),
+                quantity(usage("Usage"), 1, "Liter"),
+                dollars(water_annual),
+                factor(water_discounted, water_annual),
+                dollars(water_discounted),
+            ]));
+            energy_rows.push(Row::total(vec![
+                "Total".to_string(),
+                String::new(),
+                dollars(energy_annual + water_annual),
+                String::new(),
+                dollars(energy_discounted + water_discounted),
+            ]));
+
+            sections.push(Section {
+                heading: "2. Energy and Water Savings (+) or Cost (-)".to_string(),
+                blocks: vec![Block::Table(Table {
+                    caption: Some("Base Date Savings, unit costs, & discounted savings".to_string()),
+                    columns: columns(&["Item", "Usage Savings", "Annual Savings", "Discount Factor", "Discounted Savings"]),
+                    rows: energy_rows,
+                })],
+            });
+
+            let (recurring_annual, recurring_discounted) = savings(&["OMR Recurring"]);
+            let (non_recurring_annual, non_recurring_discounted) = savings(&["OMR Non-Recurring"]);
+            let non_energy_annual = recurring_annual + non_recurring_annual;
+            let non_energy_discounted = recurring_discounted + non_recurring_discounted;
+
+            sections.push(Section {
+                heading: "3. Non-Energy Savings (+) or Cost (-)".to_string(),
+                blocks: vec![Block::Table(Table {
+                    caption: None,
+                    columns: columns(&["Item", "Savings/Cost", "Discount Factor", "Discounted Savings/Cost"]),
+                    rows: vec![
+                        Row::new(vec![
+                            "Annually Recurring".to_string(),
+                            dollars(recurring_annual),
+                            factor(recurring_discounted, recurring_annual),
+                            dollars(recurring_discounted),
+                        ]),
+                        Row::new(vec![
+                            "Non-Annually Recurring".to_string(),
+                            dollars(non_recurring_annual),
+                            String::new(),
+                            dollars(non_recurring_discounted),
+                        ]),
+                        Row::total(vec![
+                            "Total".to_string(),
+                            dollars(non_energy_annual),
+                            String::new(),
+                            dollars(non_energy_discounted),
+                        ]),
+                    ],
+                })],
+            });
+
+            let first_year_savings = energy_annual + water_annual + non_energy_annual;
+            let operational_savings = energy_discounted + water_discounted + non_energy_discounted;
+            let sir = Some(operational_savings / investment).filter(|sir| sir.is_finite());
+            let airr = sir
+                .filter(|sir| *sir > 0.0 && self.study_period > 0)
+                .map(|sir| (1.0 + self.discount_rate) * sir.powf(1.0 / self.study_period as f64) - 1.0);
+
+            sections.push(Section {
+                heading: "Summary".to_string(),
+                blocks: vec![Block::Fields(vec![
+                    field("4. First year savings", dollars(first_year_savings)),
+                    field(
+                        "5. Simple Payback Period (in years)",
+                        ratio(Some(investment / first_year_savings).filter(|spp| spp.is_finite())),
+                    ),
+                    field("6. Total Discounted Operational Savings", dollars(operational_savings)),
+                    field("7. Savings to Investment Ratio (SIR)", ratio(sir)),
+                    field(
+                        "8. Adjusted Internal Rate of Return (AIRR)",
+                        airr.map(percent).unwrap_or_else(|| "Not Computable".to_string()),
+                    ),
+                ])],
+            });
+        }
+
+        if sections.is_empty() {
+            return Err("The ECIP report requires a proposed alternative".to_string());
+        }
+
+        Ok(sections)
+    }
+
+    /*
+     * Input
+     */
+
+    fn input(&self) -> Vec<Section> {
+        let mut sections = vec![self.general_information(vec![])];
+
+        let baseline = self.baseline().map(|(id, _)| id);
+
+        for (alt_id, alternative) in self.alternatives() {
+            let mut blocks = vec![Block::Fields(vec![
+                field("Comment:", alternative.description.clone().unwrap_or_default()),
+                field("Baseline:", if Some(alt_id) == baseline { "Yes" } else { "No" }),
+            ])];
+
+            for cost in self.alternative_costs(alternative) {
+                blocks.push(Block::Note(format!("{}: {}", cost_type(cost), cost.base().name)));
+                blocks.extend(self.cost_input(cost));
+            }
+
+            sections.push(Section { heading: format!("Alternative: {}", alternative.name), blocks });
+        }
+
+        sections
+    }
+
+    fn cost_input(&self, cost: &Cost) -> Vec<Block> {
+        let mut fields = vec![];
+        let mut tables = vec![];
+
+        if let Some(description) = cost.base().description.as_ref().filter(|text| !text.is_empty()) {
+            fields.push(field("Comment:", description.clone()));
+        }
+
+        let mut rate_of_change = |fields: &mut Vec<(String, String)>, label: &str, value: &Option<NumberOrArray>| {
+            if let Some(value) = value {
+                fields.push(field(label, rate(value)));
+                if let NumberOrArray::Array(values) = value {
+                    tables.push(self.varying_table(label, values, 1, percent));
+                }
+            }
+        };
+
+        match cost {
+            Cost::Capital(cost) => {
+                fields.push(field("Initial Cost Paid By Agency:", dollars(cost.initial_cost.unwrap_or(0.0))));
+                fields.push(field("Initial Cost Financed:", dollars(cost.amount_financed.unwrap_or(0.0))));
+                rate_of_change(&mut fields, "Annual Rate of Increase:", &cost.rate_of_change_value);
+                if let Some(life) = cost.expected_life {
+                    fields.push(field("Expected Asset Life:", years(life)));
+                }
+                if let Some(adjustment) = cost.cost_adjustment {
+                    fields.push(field("Cost Adjustment Factor:", percent(adjustment)));
+                }
+                if let Some(residual_value) = &cost.residual_value {
+                    fields.push(field("Residual Value:", residual(residual_value)));
+                }
+                if let Some(phase_in) = &cost.phase_in {
+                    tables.push(Table {
+                        caption: Some("Cost-Phasing".to_string()),
+                        columns: columns(&["Year", "Portion"]),
+                        rows: phase_in
+                            .iter()
+                            .enumerate()
+                            .map(|(i, portion)| Row::new(vec![(self.base_year() + i as i32).to_string(), percent(*portion)]))
+                            .collect(),
+                    });
+                }
+            }
+            Cost::Energy(cost) => {
+                fields.push(field("Fuel Type:", cost.fuel_type.label()));
+                if let Some(sector) = cost.customer_sector {
+                    fields.push(field("Rate Schedule:", sector.label()));
+                }
+                fields.push(field("Annual Consumption:", quantity(cost.annual_consumption, 1, cost.unit.label())));
+                fields.push(field("Price per Unit:", unit_price(cost.cost_per_unit)));
+                fields.push(field("Demand Charge:", dollars(cost.demand_charge.unwrap_or(0.0))));
+                fields.push(field("Utility Rebate:", dollars(cost.rebate.unwrap_or(0.0))));
+                self.indices(&mut fields, &mut tables, &cost.use_index, &cost.escalation);
+            }
+            Cost::Water(cost) => {
+                let seasons = |usage: &[SeasonUsage]| -> Vec<Row> {
+                    usage
+                        .iter()
+                        .map(|usage| {
+                            Row::new(vec![
+                                season(usage.season).to_string(),
+                                quantity(usage.amount, 1, cost.unit.label()),
+                                unit_price(usage.cost_per_unit),
+                            ])
+                        })
+                        .collect()
+                };
+                tables.push(Table {
+                    caption: Some("Usage".to_string()),
+                    columns: columns(&["Season", "Amount", "Price per Unit"]),
+                    rows: seasons(&cost.usage),
+                });
+                tables.push(Table {
+                    caption: Some("Disposal".to_string()),
+                    columns: columns(&["Season", "Amount", "Price per Unit"]),
+                    rows: seasons(&cost.disposal),
+                });
+                self.indices(&mut fields, &mut tables, &cost.use_index, &cost.escalation);
+            }
+            Cost::ReplacementCapital(cost) => {
+                fields.push(field("Initial Cost:", dollars(cost.initial_cost.unwrap_or(0.0))));
+                fields.push(field("Initial Occurrence:", years(cost.initial_occurrence)));
+                rate_of_change(&mut fields, "Annual Rate of Increase:", &cost.rate_of_change_value);
+                if let Some(life) = cost.expected_life {
+                    fields.push(field("Expected Asset Life:", years(life)));
+                }
+                if let Some(residual_value) = &cost.residual_value {
+                    fields.push(field("Residual Value:", residual(residual_value)));
+                }
+            }
+            Cost::Omr(cost) => {
+                fields.push(field("Amount:", dollars(cost.initial_cost.unwrap_or(0.0))));
+                fields.push(field("Initial Occurrence:", years(cost.initial_occurrence)));
+                fields.extend(recurrence(&cost.recurring));
+                rate_of_change(&mut fields, "Annual Rate of Increase:", &cost.rate_of_change_value);
+            }
+            Cost::ImplementationContract(cost) => {
+                fields.push(field("Amount:", dollars(cost.cost.unwrap_or(0.0))));
+                fields.push(field("Initial Occurrence:", years(cost.initial_occurrence)));
+                rate_of_change(&mut fields, "Annual Rate of Increase:", &cost.rate_of_change_value);
+            }
+            Cost::RecurringContract(cost) => {
+                fields.push(field("Annual Contract Payment Amount:", dollars(cost.initial_cost.unwrap_or(0.0))));
+                fields.push(field("Initial Occurrence:", years(cost.initial_occurrence)));
+                fields.extend(recurrence(&cost.recurring));
+                rate_of_change(&mut fields, "Annual Rate of Increase:", &cost.rate_of_change_value);
+            }
+            Cost::Other(cost) => {
+                let unit = cost.unit.as_ref().map(|unit| unit.label()).unwrap_or_default();
+                fields.push(field("Value per Unit:", unit_price(cost.value_per_unit)));
+                fields.push(field("Number of Units:", quantity(cost.number_of_units, 1, unit)));
+                if let Some(tags) = &cost.tags {
+                    fields.push(field("Tags:", tags.join(", ")));
+                }
+                fields.push(field("Initial Occurrence:", years(cost.initial_occurrence)));
+                fields.extend(recurrence(&cost.recurring));
+                rate_of_change(&mut fields, "Rate of Change of Value:", &cost.rate_of_change_value);
+                rate_of_change(&mut fields, "Rate of Change of Units:", &cost.rate_of_change_units);
+            }
+            Cost::OtherNonMonetary(cost) => {
+                let unit = cost.unit.as_ref().map(|unit| unit.label()).unwrap_or_default();
+                fields.push(field("Number of Units:", quantity(cost.number_of_units, 1, unit)));
+                if let Some(tags) = &cost.tags {
+                    fields.push(field("Tags:", tags.join(", ")));
+                }
+                fields.push(field("Initial Occurrence:", years(cost.initial_occurrence)));
+                fields.extend(recurrence(&cost.recurring));
+                rate_of_change(&mut fields, "Rate of Change of Units:", &cost.rate_of_change_units);
+            }
+            Cost::Ercip(cost) => {
+                fields.push(field("Construction Cost:", dollars(cost.construction_cost)));
+                fields.push(field("SIOH:", dollars(cost.sioh)));
+                fields.push(field("Design Cost:", dollars(cost.design_cost)));
+                fields.push(field("Salvage Value of Existing Equipment:", dollars(cost.salvage_value)));
+                fields.push(field("Public Utility Rebate:", dollars(cost.public_utility_rebate)));
+                fields.push(field("Cybersecurity:", dollars(cost.cybersecurity)));
+            }
+        }
+
+        let mut blocks = vec![Block::Fields(fields)];
+        blocks.extend(tables.into_iter().map(Block::Table));
+        blocks
+    }
+
+    /// Adds the usage index and escalation rates of an energy or water cost.
+    fn indices(
+        &self,
+        fields: &mut Vec<(String, String)>,
+        tables: &mut Vec<Table>,
+        use_index: &Option<NumberOrArray>,
+        escalation: &Option<NumberOrArray>,
+    ) {
+        match use_index {
+            Some(NumberOrArray::Array(values)) => tables.push(self.varying_table("Usage Indices", values, 0, percent)),
+            Some(NumberOrArray::Number(value)) => fields.push(field("Usage Index:", percent(*value))),
+            None => {}
+        }
+
+        match escalation {
+            Some(NumberOrArray::Array(values)) => {
+                tables.push(self.varying_table("Escalation Rates", values, 1, percent))
+            }
+            Some(NumberOrArray::Number(value)) => fields.push(field("Escalation Rate:", percent(*value))),
+            None => {}
+        }
+    }
+
+    /// Lists year by year values as runs of equal values, the way the legacy reports show them. `start` is the
+    /// offset of the first value from the base year.
+    fn varying_table(&self, caption: &str, values: &[f64], start: i32, format: fn(f64) -> String) -> Table {
+        let mut runs: Vec<(i32, i32, f64)> = vec![];
+        for (i, value) in values.iter().enumerate() {
+            match runs.last_mut() {
+                Some((_, count, last)) if *last == *value => *count += 1,
+                _ => runs.push((i as i32, 1, *value)),
+            }
+        }
+
+        let count = runs.len();
+        let rows = runs
+            .into_iter()
+            .enumerate()
+            .map(|(i, (from, length, value))| {
+                let duration = if i + 1 == count { "Remaining".to_string() } else { years(length) };
+                Row::new(vec![format!("January 1, {}", self.base_year() + start + from), duration, format(value)])
+            })
+            .collect();
+
+        Table {
+            caption: Some(caption.trim_end_matches(':').to_string()),
+            columns: columns(&["From Date", "Duration", "Value"]),
+            rows,
+        }
+    }
+}
+
+fn field(label: &str, value: impl Into<String>) -> (String, String) {
+    (label.to_string(), value.into())
+}
+
+fn columns(labels: &[&str]) -> Vec<String> {
+    labels.iter().map(|label| label.to_string()).collect()
+}
+
+fn analysis_type(analysis_type: AnalysisType) -> String {
+    match analysis_type {
+        AnalysisType::FederalFinanced => "Federal Analysis, Financed Project",
+        AnalysisType::FempEnergy => "FEMP Analysis, Energy Project",
+        AnalysisType::OmbNonEnergy => "OMB Analysis, Non-Energy Project",
+        AnalysisType::MilconEnergy => "MILCON Analysis, Energy Project",
+        AnalysisType::MilconNonEnergy => "MILCON Analysis, Non-Energy Project",
+        AnalysisType::MilconEcip => "MILCON Analysis, ERCIP Project",
+    }
+    .to_string()
+}
+
+fn cost_type(cost: &Cost) -> &'static str {
+    match cost {
+        Cost::Capital(_) => "Capital Component",
+        Cost::Energy(_) => "Energy",
+        Cost::Water(_) => "Water",
+        Cost::ReplacementCapital(_) => "Capital Replacement",
+        Cost::Omr(_) => "OM&R",
+        Cost::ImplementationContract(_) => "Non-Recurring Contract",
+        Cost::RecurringContract(_) => "Recurring Contract",
+        Cost::Other(_) => "Other Monetary",
+        Cost::OtherNonMonetary(_) => "Other Non-Monetary",
+        Cost::Ercip(_) => "ERCIP",
+    }
+}
+
+fn season(season: Season) -> &'static str {
+    match season {
+        Season::Spring => "Spring",
+        Season::Summer => "Summer",
+        Season::Autumn => "Autumn",
+        Season::Winter => "Winter",
+    }
+}
+
+fn recurrence(recurring: &Option<Recurring>) -> Vec<(String, String)> {
+    match recurring {
+        Some(recurring) => {
+            let mut fields = vec![field("Recurs Every:", years(recurring.rate_of_recurrence.unwrap_or(1)))];
+            if let Some(duration) = recurring.duration {
+                fields.push(field("Duration:", years(duration)));
+            }
+            fields
+        }
+        None => vec![field("Recurs Every:", "Non-Recurring")],
+    }
+}
+
+fn rate(value: &NumberOrArray) -> String {
+    match value {
+        NumberOrArray::Number(value) => percent(*value),
+        NumberOrArray::Array(_) => "Varying".to_string(),
+    }
+}
+
+fn residual(residual_value: &ResidualValue) -> String {
+    match residual_value.approach {
+        DollarOrPercent::Percent => percent(residual_value.value),
+        DollarOrPercent::Dollar => dollars(residual_value.value),
+    }
+}
+
+fn years(years: i32) -> String {
+    match years {
+        1 => "1 year 0 months".to_string(),
+        _ => format!("{} years 0 months", years),
+    }
+}
+
+/// Inserts thousands separators into the integer part of a formatted number.
+fn group_digits(formatted: &str) -> String {
+    let (integer, fraction) = match formatted.find('.') {
+        Some(index) => formatted.split_at(index),
+        None => (formatted, ""),
+    };
+
+    let mut grouped = String::new();
+    for (i, digit) in integer.chars().enumerate() {
+        if i > 0 && (integer.len() - i) % 3 == 0 {
+            grouped.push(',');
+        }
+        grouped.push(digit);
+    }
+
+    grouped + fraction
+}
+
+fn signed(value: f64, decimals: usize, prefix: &str) -> String {
+    let formatted = format!("{:.*}", decimals, value.abs());
+    // Avoid showing values that round to zero as negative
+    let negative = value < 0.0 && formatted.chars().any(|c| c.is_ascii_digit() && c != '0');
+
+    format!("{}{}{}", if negative { "-" } else { "" }, prefix, group_digits(&formatted))
+}
+
+fn dollars(value: f64) -> String {
+    signed(value, 0, "$")
+}
+
+fn unit_price(value: f64) -> String {
+    signed(value, 5, "$")
+}
+
+fn quantity(value: f64, decimals: usize, unit: &str) -> String {
+    format!("{} {}", signed(value, decimals, ""), unit).trim_end().to_string()
+}
+
+/// Formats a rate as a percent with at most two decimals, dropping trailing zeros.
+fn percent(value: f64) -> String {
+    let formatted = format!("{:.2}", value * 100.0);
+    let trimmed = formatted.trim_end_matches('0').trim_end_matches('.');
+
+    format!("{}%", if trimmed == "-0" { "0" } else { trimmed })
+}
+
+fn ratio(value: Option<f64>) -> String {
+    value.map(|value| format!("{:.2}", value)).unwrap_or_else(|| "Not Computable".to_string())
+}
+
+fn factor(discounted: f64, annual: f64) -> String {
+    if annual == 0.0 {
+        String::new()
+    } else {
+        format!("{:.3}", discounted / annual)
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::lcc::analyze;
+    use crate::lcc::tests::federal_financed_project;
+
+    /// Builds a report of the federal financed example. The native engine matches the E3 reference result, but
+    /// unlike the stored result it carries the LCC and OM&R tags the frontend adds to current requests.
+    pub fn federal_financed_report(kind: ReportKind) -> Report {
+        let data = federal_financed_project();
+        let output = analyze(&data, None).expect("project should analyze");
+
+        build(kind, &data, &output).expect("report should build")
+    }
+
+    /// The example as an ERCIP project, with the retrofit entered as an ERCIP cost.
+    fn ercip_project() -> ProjectData {
+        let mut value = serde_json::to_value(federal_financed_project()).unwrap();
+        value["project"]["analysisType"] = "MILCON Analysis, ERCIP (formerly ECIP) Project".into();
+        value["project"]["costs"].as_array_mut().unwrap().push(7.into());
+        value["alternatives"][1]["costs"].as_array_mut().unwrap().push(7.into());
+        value["costs"].as_array_mut().unwrap().push(serde_json::json!({
+            "type": "ERCIP", "id": 7, "name": "Retrofit", "constructionCost": 100000.0, "SIOH": 5000.0,
+            "designCost": 8000.0, "salvageValue": 1000.0, "publicUtilityRebate": 2000.0, "cybersecurity": 500.0
+        }));
+
+        serde_json::from_value(value).expect("project should deserialize")
+    }
+
+    fn section<'a>(report: &'a Report, heading: &str) -> &'a Section {
+        report
+            .sections
+            .iter()
+            .find(|section| section.heading == heading)
+            .unwrap_or_else(|| panic!("missing section {}", heading))
+    }
+
+    /// The n-th table of a section.
+    fn table<'a>(report: &'a Report, heading: &str, n: usize) -> &'a Table {
+        section(report, heading)
+            .blocks
+            .iter()
+            .filter_map(|block| match block {
+                Block::Table(table) => Some(table),
+                _ => None,
+            })
+            .nth(n)
+            .unwrap_or_else(|| panic!("missing table {} in {}", n, heading))
+    }
+
+    /// The cells after the label of the row with the given label.
+    fn row<'a>(table: &'a Table, label: &str) -> &'a [String] {
+        table
+            .rows
+            .iter()
+            .find(|row| row.cells[0] == label)
+            .map(|row| &row.cells[1..])
+            .unwrap_or_else(|| panic!("missing row {}", label))
+    }
+
+    fn fields(report: &Report, heading: &str) -> HashMap<String, String> {
+        section(report, heading)
+            .blocks
+            .iter()
+            .filter_map(|block| match block {
+                Block::Fields(fields) => Some(fields.iter().cloned()),
+                _ => None,
+            })
+            .flatten()
+            .collect()
+    }
+
+    #[test]
+    fn summary_lcc() {
+        let report = federal_financed_report(ReportKind::SummaryLcc);
+        assert_eq!(report.title, "NIST BLCC: Summary LCC");
+
+        let general = fields(&report, "General Information");
+        assert_eq!(general["Project Location:"], "Arizona, United States of America");
+        assert_eq!(general["Study Period:"], "15 years 0 months (January 1, 2024 through December 31, 2038)");
+        assert_eq!(general["Discount Rate:"], "3%");
+
+        let existing = table(&report, "Alternative: Existing", 0);
+        assert_eq!(row(existing, "Energy Consumption Costs"), ["$594,523", "$49,801"]);
+        assert_eq!(row(existing, "Annually Recurring OM&R Costs"), ["$66,852", "$5,600"]);
+        assert_eq!(row(existing, "Total Life-Cycle Cost"), ["$661,375", "$55,401"]);
+        assert!(existing.rows.last().unwrap().style == RowStyle::Total);
+
+        let retrofit = table(&report, "Alternative: Lighting Retrofit", 0);
+        assert_eq!(row(retrofit, "Recurring Contract Costs"), ["$24", "$2"]);
+        assert_eq!(row(retrofit, "Total Life-Cycle Cost"), ["$123,871", "$10,376"]);
+    }
+
+    #[test]
+    fn lowest_lcc() {
+        let report = federal_financed_report(ReportKind::LowestLcc);
+        let table = table(&report, "Lowest LCC", 0);
+
+        assert_eq!(row(table, "Existing"), ["$0", "$661,375", ""]);
+        assert_eq!(row(table, "Lighting Retrofit"), ["$0", "$123,871", "*"]);
+    }
+
+    #[test]
+    fn comparative_analysis() {
+        let report = federal_financed_report(ReportKind::ComparativeAnalysis);
+
+        let costs = table(&report, "Comparison of Present-Value Costs", 0);
+        assert_eq!(row(costs, "Energy Consumption Costs"), ["$594,523", "$113,624", "$480,898"]);
+        assert_eq!(row(costs, "Contract Costs"), ["$0", "$24", "-$24"]);
+        assert_eq!(row(costs, "Total PV Life-Cycle Cost"), ["$661,375", "$123,871", "$537,504"]);
+
+        let savings = fields(&report, "Net Savings from Alternative Compared with Base Case");
+        assert_eq!(savings["Net Savings"], "$537,504");
+        assert_eq!(savings["Savings-to-Investment Ratio (SIR)"], "22513.42");
+        assert_eq!(savings["Adjusted Internal Rate of Return (AIRR)"], "95.02%");
+
+        let energy = table(&report, "Energy Savings Summary", 0);
+        assert_eq!(
+            row(energy, "Electricity"),
+            ["1,082,633.0 kWh", "206,911.0 kWh", "875,722.0 kWh", "13,135,830.0 kWh"]
+        );
+    }
+
+    #[test]
+    fn detailed_lcc() {
+        let report = federal_financed_report(ReportKind::DetailedLcc);
+
+        let energy = table(&report, "Alternative: Existing", 1);
+        assert_eq!(row(energy, "Electricity"), ["1,082,633.0 kWh", "$0.04600", "$49,801", "$0", "$0"]);
+
+        let lcc = table(&report, "Alternative: Lighting Retrofit", 2);
+        assert_eq!(row(lcc, "   New System Post-Contract OM Costs"), ["$10,223", "$856"]);
+        assert_eq!(row(lcc, "Subtotal (for Contract):"), ["$24", "$2"]);
+        assert_eq!(row(lcc, "Total Life-Cycle Cost"), ["$123,871", "$10,376"]);
+        assert!(row(lcc, "Energy Costs").is_empty());
+    }
+
+    #[test]
+    fn cash_flow() {
+        let report = federal_financed_report(ReportKind::CashFlow);
+
+        let existing = table(&report, "Alternative: Existing", 0);
+        assert_eq!(existing.columns, ["Year Beginning", "Energy", "OM&R", "Total"]);
+        assert_eq!(existing.rows.len(), 17);
+        assert_eq!(row(existing, "Total"), ["$747,017", "$84,000", "$831,017"]);
+
+        let retrofit = table(&report, "Alternative: Lighting Retrofit", 0);
+        assert_eq!(row(retrofit, "2033"), ["$9,518", "$0", "$2", "$9,520"]);
+        assert_eq!(row(retrofit, "2034"), ["$9,518", "$3,000", "$2", "$12,520"]);
+        assert_eq!(row(retrofit, "Total"), ["$142,769", "$15,000", "$30", "$157,799"]);
+    }
+
+    #[test]
+    fn input() {
+        let report = federal_financed_report(ReportKind::Input);
+        let blocks = &section(&report, "Alternative: Lighting Retrofit").blocks;
+
+        let heading = "Recurring Contract: Annual Contract Payment";
+        let contract = blocks
+            .iter()
+            .position(|block| matches!(block, Block::Note(note) if note == heading))
+            .expect("contract should be listed");
+        let Block::Fields(fields) = &blocks[contract + 1] else { panic!("contract should have fields") };
+        assert_eq!(fields[0], field("Annual Contract Payment Amount:", "$2"));
+    }
+
+    #[test]
+    fn ecip() {
+        let data = ercip_project();
+        let output = analyze(&data, None).unwrap();
+        let report = build(ReportKind::Ecip, &data, &output).unwrap();
+
+        let investment = table(&report, "1. Investment", 0);
+        assert_eq!(row(investment, "Total Cost"), ["$113,000"]);
+        assert_eq!(row(investment, "Total Investment"), ["$109,500"]);
+
+        let energy = table(&report, "2. Energy and Water Savings (+) or Cost (-)", 0);
+        assert_eq!(row(energy, "Electricity"), ["875,722.0 kWh", "$40,283", "11.938", "$480,898"]);
+
+        let non_energy = table(&report, "3. Non-Energy Savings (+) or Cost (-)", 0);
+        assert_eq!(row(non_energy, "Annually Recurring"), ["$5,600", "10.112", "$56,629"]);
+
+        let summary = fields(&report, "Summary");
+        assert_eq!(summary["6. Total Discounted Operational Savings"], "$537,528");
+        assert_eq!(summary["7. Savings to Investment Ratio (SIR)"], "4.91");
+    }
+
+    #[test]
+    fn ecip_requires_an_ercip_analysis() {
+        let data = federal_financed_project();
+        let output = analyze(&data, None).unwrap();
+
+        assert_eq!(
+            build(ReportKind::Ecip, &data, &output).err().unwrap(),
+            "The ECIP report is only available for MILCON ERCIP analyses"
+        );
+    }
+
+    #[test]
+    fn formats_values() {
+        assert_eq!(dollars(1234567.4), "$1,234,567");
+        assert_eq!(dollars(-0.4), "$0");
+        assert_eq!(dollars(-24.0), "-$24");
+        assert_eq!(unit_price(0.046), "$0.04600");
+        assert_eq!(percent(0.0325), "3.25%");
+        assert_eq!(percent(0.03), "3%");
+        assert_eq!(percent(-0.00001), "0%");
+        assert_eq!(ratio(None), "Not Computable");
+        assert_eq!(factor(1.0, 0.0), "");
+        assert_eq!(years(1), "1 year 0 months");
+    }
+}
@@ -11,6 +11,14 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    e3_cache (key) {
+        key -> Text,
+        response -> Jsonb,
+        created_at -> Timestamp,
+    }
+}
+
 diesel::table! {
     energy_price_indices (release_year, year, division, sector, case) {
         release_year -> Int4,
@@ -117,6 +125,16 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    release_years (release_year) {
+        release_year -> Int4,
+        publication_date -> Nullable<Date>,
+        sources -> Array<Text>,
+        status -> Text,
+        is_default -> Bool,
+    }
+}
+
 diesel::table! {
     scc (release_year, year) {
         year -> Int4,
@@ -135,6 +153,14 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    stored_reports (key) {
+        key -> Text,
+        request -> Jsonb,
+        created_at -> Timestamp,
+    }
+}
+
 diesel::table! {
     zip_info (zip) {
         zip -> Int4,
@@ -150,6 +176,7 @@ diesel::table! {
 
 diesel::allow_tables_to_appear_in_same_query!(
     discount_rates,
+    e3_cache,
     energy_price_indices,
     energy_prices,
     escalation_rates,
@@ -158,7 +185,9 @@ diesel::allow_tables_to_appear_in_same_query!(
     region_case_propane_lng,
     region_case_reeds,
     region_natgas,
+    release_years,
     scc,
     state_division_region,
+    stored_reports,
     zip_info,
 );
@@ -0,0 +1,245 @@
+use serde::{Deserialize, Serialize};
+
+/// The number of years at the end of a series that trends are fitted to.
+const TREND_YEARS: usize = 10;
+
+/// How to fill the years of a requested range after the last year with data.
+#[derive(Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum Extrapolation {
+    /// Reject ranges that go past the data.
+    Error,
+    /// Repeat the last value.
+    HoldLast,
+    /// Continue the least squares line through the last years. The line is not clamped, so a falling series can go
+    /// negative, which is expected of rates like escalation rates but not of prices or emission rates.
+    Linear,
+    /// Continue the compound annual growth rate of the last years.
+    ConstantGrowth,
+}
+
+#[derive(Serialize, Clone, Copy)]
+pub struct YearValue {
+    pub year: i32,
+    pub value: Option<f64>,
+}
+
+/// The values of every year of a range in order, with `null` for years without data.
+#[derive(Serialize)]
+pub struct YearSeries {
+    pub values: Vec<YearValue>,
+    /// The years whose values were extrapolated rather than read from the data.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub extrapolated: Vec<i32>,
+}
+
+impl YearSeries {
+    /// The values from `from` to `to` of rows in any order, with `null` for missing years.
+    pub fn new(rows: &[(i32, Option<f64>)], from: i32, to: i32) -> Self {
+        let values = (from..=to)
+            .map(|year| YearValue {
+                year,
+                value: rows.iter().find(|(row, _)| *row == year).and_then(|(_, value)| *value),
+            })
+            .collect();
+
+        YearSeries { values, extrapolated: Vec::new() }
+    }
+}
+
+/// Takes the values from `from` to `to` out of a series ordered by year, which may start before `from`, extrapolating
+/// every year after its last year. Years without a value are skipped when fitting.
+fn extrapolate(
+    rows: &[(i32, Option<f64>)],
+    from: i32,
+    to: i32,
+    mode: Extrapolation,
+) -> Result<YearSeries, String> {
+    let known: Vec<(i32, f64)> = rows.iter().filter_map(|(year, value)| Some((*year, (*value)?))).collect();
+    let Some(&(last_year, last_value)) = known.last() else {
+        return Err("No data to extrapolate from".to_string());
+    };
+
+    let mut series = YearSeries::new(rows, from, to);
+    let extrapolated: Vec<i32> = (from.max(last_year + 1)..=to).collect();
+    if extrapolated.is_empty() {
+        return Ok(series);
+    }
+
+    let trend = &known[known.len().saturating_sub(TREND_YEARS)..];
+    let fill: Box<dyn Fn(i32) -> f64> = match mode {
+        Extrapolation::Error => {
+            return Err(format!("No data after {}, but the range ends in {}", last_year, to));
+        }
+        Extrapolation::HoldLast => Box::new(move |_| last_value),
+        Extrapolation::Linear => {
+            let (slope, intercept) = least_squares(trend);
+            Box::new(move |year| intercept + slope * year as f64)
+        }
+        Extrapolation::ConstantGrowth => {
+            let (first_year, first_value) = trend[0];
+            if first_year == last_year {
+                Box::new(move |_| last_value)
+            } else if first_value <= 0.0 || last_value <= 0.0 {
+                return Err("Cannot extrapolate constant growth from values that are not positive".to_string());
+            } else {
+                let growth = (last_value / first_value).powf(1.0 / (last_year - first_year) as f64);
+                Box::new(move |year| last_value * growth.powi(year - last_year))
+            }
+        }
+    };
+
+    for value in series.values.iter_mut().filter(|value| value.year > last_year) {
+        value.value = Some(fill(value.year));
+    }
+    series.extrapolated = extrapolated;
+
+    Ok(series)
+}
+
+/// The first year of rows to load for a range starting at `from`. Every mode except [`Extrapolation::Error`] may fill
+/// the range from years before it, so those load the whole history up to the end of the range.
+pub fn first_year(from: i32, mode: Option<Extrapolation>) -> i32 {
+    match mode {
+        None | Some(Extrapolation::Error) => from,
+        Some(Extrapolation::HoldLast | Extrapolation::Linear | Extrapolation::ConstantGrowth) => i32::MIN,
+    }
+}
+
+/// Takes the values from `from` to `to` out of a series, extrapolating past its last year if a mode is given.
+pub fn year_series(
+    rows: &[(i32, Option<f64>)],
+    from: i32,
+    to: i32,
+    mode: Option<Extrapolation>,
+) -> Result<YearSeries, String> {
+    match mode {
+        Some(mode) => extrapolate(rows, from, to, mode),
+        None => Ok(YearSeries::new(rows, from, to)),
+    }
+}
+
+/// Fits a line to the points, returning its slope and intercept. A single point gives a flat line.
+fn least_squares(points: &[(i32, f64)]) -> (f64, f64) {
+    let n = points.len() as f64;
+    let mean_x = points.iter().map(|(x, _)| *x as f64).sum::<f64>() / n;
+    let mean_y = points.iter().map(|(_, y)| y).sum::<f64>() / n;
+
+    let covariance: f64 = points.iter().map(|(x, y)| (*x as f64 - mean_x) * (y - mean_y)).sum();
+    let variance: f64 = points.iter().map(|(x, _)| (*x as f64 - mean_x).powi(2)).sum();
+    let slope = if variance == 0.0 { 0.0 } else { covariance / variance };
+
+    (slope, mean_y - slope * mean_x)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn values(series: &YearSeries) -> Vec<Option<f64>> {
+        series.values.iter().map(|value| value.value).collect()
+    }
+
+    fn assert_close(actual: Option<f64>, expected: f64) {
+        let actual = actual.expect("value should be present");
+        assert!((actual - expected).abs() < 1e-9, "{} != {}", actual, expected);
+    }
+
+    #[test]
+    fn fits_a_line() {
+        assert_eq!(least_squares(&[(2020, 1.0), (2021, 3.0), (2022, 5.0)]), (2.0, -4039.0));
+        // Noise around y = x - 2000 keeps the slope
+        let (slope, intercept) = least_squares(&[(2020, 20.5), (2021, 20.5), (2022, 22.5), (2023, 22.5)]);
+        assert!((slope - 0.8).abs() < 1e-9);
+        assert!((intercept + 0.8 * 2021.5 - 21.5).abs() < 1e-6);
+        assert_eq!(least_squares(&[(2020, 4.0)]), (0.0, 4.0));
+    }
+
+    #[test]
+    fn keeps_gaps_in_place() {
+        let rows = [(2021, Some(1.0)), (2023, None), (2022, Some(2.0)), (2025, Some(4.0))];
+        let series = year_series(&rows, 2020, 2025, None).unwrap();
+
+        let years: Vec<i32> = series.values.iter().map(|value| value.year).collect();
+        assert_eq!(years, [2020, 2021, 2022, 2023, 2024, 2025]);
+        assert_eq!(values(&series), [None, Some(1.0), Some(2.0), None, None, Some(4.0)]);
+        assert!(series.extrapolated.is_empty());
+    }
+
+    #[test]
+    fn holds_the_last_value() {
+        let rows = [(2020, Some(1.0)), (2021, None), (2022, Some(2.0)), (2023, None)];
+        let series = year_series(&rows, 2020, 2024, Some(Extrapolation::HoldLast)).unwrap();
+
+        // A missing year inside the data stays a gap, every year after the last value is filled
+        assert_eq!(values(&series), [Some(1.0), None, Some(2.0), Some(2.0), Some(2.0)]);
+        assert_eq!(series.extrapolated, [2023, 2024]);
+    }
+
+    #[test]
+    fn continues_a_line() {
+        let rows = [(2020, Some(1.0)), (2021, None), (2022, Some(3.0)), (2023, Some(4.0))];
+        let series = year_series(&rows, 2023, 2025, Some(Extrapolation::Linear)).unwrap();
+
+        assert_eq!(series.extrapolated, [2024, 2025]);
+        assert_close(series.values[1].value, 5.0);
+        assert_close(series.values[2].value, 6.0);
+
+        // Falling series are not clamped
+        let falling = [(2020, Some(2.0)), (2021, Some(1.0))];
+        let series = year_series(&falling, 2022, 2023, Some(Extrapolation::Linear)).unwrap();
+        assert_close(series.values[1].value, -1.0);
+    }
+
+    #[test]
+    fn fits_trends_to_the_last_years() {
+        // Only the last ten years count, so the early outlier does not change the line
+        let rows: Vec<(i32, Option<f64>)> =
+            [(2000, Some(100.0))].into_iter().chain((2010..2020).map(|year| (year, Some(1.0)))).collect();
+        let series = year_series(&rows, 2020, 2020, Some(Extrapolation::Linear)).unwrap();
+
+        assert_close(series.values[0].value, 1.0);
+    }
+
+    #[test]
+    fn continues_constant_growth() {
+        let rows = [(2020, Some(100.0)), (2021, Some(110.0)), (2022, Some(121.0))];
+        let series = year_series(&rows, 2022, 2024, Some(Extrapolation::ConstantGrowth)).unwrap();
+
+        assert_close(series.values[1].value, 133.1);
+        assert_close(series.values[2].value, 146.41);
+
+        let single = year_series(&[(2020, Some(5.0))], 2021, 2021, Some(Extrapolation::ConstantGrowth)).unwrap();
+        assert_eq!(values(&single), [Some(5.0)]);
+
+        let negative = [(2020, Some(-1.0)), (2021, Some(2.0))];
+        assert_eq!(
+            year_series(&negative, 2022, 2022, Some(Extrapolation::ConstantGrowth)).err().unwrap(),
+            "Cannot extrapolate constant growth from values that are not positive"
+        );
+    }
+
+    #[test]
+    fn rejects_ranges_past_the_data() {
+        let rows = [(2020, Some(1.0)), (2021, Some(2.0))];
+
+        assert_eq!(
+            year_series(&rows, 2020, 2022, Some(Extrapolation::Error)).err().unwrap(),
+            "No data after 2021, but the range ends in 2022"
+        );
+        assert_eq!(values(&year_series(&rows, 2020, 2021, Some(Extrapolation::Error)).unwrap()), [Some(1.0), Some(2.0)]);
+        assert_eq!(
+            year_series(&[(2020, None)], 2020, 2021, Some(Extrapolation::HoldLast)).err().unwrap(),
+            "No data to extrapolate from"
+        );
+    }
+
+    #[test]
+    fn loads_history_only_when_needed() {
+        assert_eq!(first_year(2030, None), 2030);
+        assert_eq!(first_year(2030, Some(Extrapolation::Error)), 2030);
+        assert_eq!(first_year(2030, Some(Extrapolation::HoldLast)), i32::MIN);
+        assert_eq!(first_year(2030, Some(Extrapolation::Linear)), i32::MIN);
+        assert_eq!(first_year(2030, Some(Extrapolation::ConstantGrowth)), i32::MIN);
+    }
+}
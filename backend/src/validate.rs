@@ -0,0 +1,451 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use diesel::prelude::*;
+use serde::Serialize;
+
+/// The result of validating a release year. The release year is valid if there are no issues.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Validation {
+    pub release_year: i32,
+    pub valid: bool,
+    pub issues: Vec<Issue>,
+}
+
+#[derive(Serialize)]
+pub struct Issue {
+    pub table: &'static str,
+    pub message: String,
+}
+
+/// The years of a table in a release year, by the columns that identify a series such as division, sector and case.
+struct Coverage {
+    table: &'static str,
+    series: BTreeMap<Vec<String>, BTreeSet<i32>>,
+}
+
+impl Coverage {
+    fn new(table: &'static str, rows: Vec<(Vec<String>, i32)>) -> Self {
+        let mut series: BTreeMap<Vec<String>, BTreeSet<i32>> = BTreeMap::new();
+        for (key, year) in rows {
+            series.entry(key).or_default().insert(year);
+        }
+
+        Coverage { table, series }
+    }
+
+    fn years(&self) -> BTreeSet<i32> {
+        self.series.values().flatten().copied().collect()
+    }
+
+    /// The values of one of the series columns.
+    fn column(&self, index: usize) -> BTreeSet<String> {
+        self.series.keys().filter_map(|key| key.get(index).cloned()).collect()
+    }
+}
+
+/// The rows of a release year that the checks need, along with the regions referenced by the unversioned tables.
+struct Tables {
+    energy: [Coverage; 3],
+    ba: Coverage,
+    oil: Coverage,
+    propane_lng: Coverage,
+    reeds: Coverage,
+    natgas: Coverage,
+    scc: Coverage,
+    discount_rates: i64,
+    state_divisions: BTreeSet<String>,
+    bas: BTreeSet<String>,
+    padds: BTreeSet<String>,
+    technobasins: BTreeSet<String>,
+    reeds_bas: BTreeSet<String>,
+}
+
+/// Checks that the tables of a release year agree with each other. Every calendar year table must cover the same year
+/// range without gaps, the energy tables must have every division, sector and case combination, and the emissions
+/// tables must have every BA, PADD, technobasin and ReEDS region referenced in `zip_info`.
+pub fn validate(release: i32, db: &mut PgConnection) -> QueryResult<Validation> {
+    let tables = load(release, db)?;
+    let issues = tables.issues();
+
+    Ok(Validation { release_year: release, valid: issues.is_empty(), issues })
+}
+
+fn load(release: i32, db: &mut PgConnection) -> QueryResult<Tables> {
+    let energy = [
+        energy_coverage("energy_prices", release, db)?,
+        energy_coverage("energy_price_indices", release, db)?,
+        energy_coverage("escalation_rates", release, db)?,
+    ];
+
+    let ba = {
+        use crate::schema::region_case_ba::dsl::*;
+        let rows: Vec<(String, String, String, i32)> = region_case_ba
+            .filter(release_year.eq(release))
+            .select((ba, case, rate, year))
+            .load(db)?;
+        Coverage::new("region_case_ba", keyed(rows))
+    };
+    let oil = {
+        use crate::schema::region_case_oil::dsl::*;
+        let rows: Vec<(String, String, String, i32)> = region_case_oil
+            .filter(release_year.eq(release))
+            .select((padd, case, rate, year))
+            .load(db)?;
+        Coverage::new("region_case_oil", keyed(rows))
+    };
+    let propane_lng = {
+        use crate::schema::region_case_propane_lng::dsl::*;
+        let rows: Vec<(String, String, String, i32)> = region_case_propane_lng
+            .filter(release_year.eq(release))
+            .select((padd, case, rate, year))
+            .load(db)?;
+        Coverage::new("region_case_propane_lng", keyed(rows))
+    };
+    let reeds = {
+        use crate::schema::region_case_reeds::dsl::*;
+        let rows: Vec<(String, String, String, i32)> = region_case_reeds
+            .filter(release_year.eq(release))
+            .select((reeds, case, rate, year))
+            .load(db)?;
+        Coverage::new("region_case_reeds", keyed(rows))
+    };
+    let natgas = {
+        use crate::schema::region_natgas::dsl::*;
+        let rows: Vec<(String, String, String, i32)> = region_natgas
+            .filter(release_year.eq(release))
+            .select((technobasin, case, rate, year))
+            .load(db)?;
+        Coverage::new("region_natgas", keyed(rows))
+    };
+    let scc = {
+        use crate::schema::scc::dsl::*;
+        let rows: Vec<i32> = scc.filter(release_year.eq(release)).select(year).load(db)?;
+        Coverage::new("scc", rows.into_iter().map(|row| (Vec::new(), row)).collect())
+    };
+
+    let discount_rates: i64 = {
+        use crate::schema::discount_rates::dsl::*;
+        discount_rates.filter(release_year.eq(release)).count().get_result(db)?
+    };
+
+    let state_divisions: BTreeSet<String> = {
+        use crate::schema::state_division_region::dsl::*;
+        state_division_region.select(division).distinct().load::<String>(db)?.into_iter().collect()
+    };
+
+    let (bas, padds, technobasins, reeds_bas) = {
+        use crate::schema::zip_info::dsl::*;
+        let bas: BTreeSet<String> = zip_info.select(ba).distinct().load::<String>(db)?.into_iter().collect();
+        let padds: BTreeSet<String> = zip_info.select(padd).distinct().load::<String>(db)?.into_iter().collect();
+        let technobasins: BTreeSet<String> =
+            zip_info.select(technobasin).distinct().load::<String>(db)?.into_iter().collect();
+        let reeds_bas: BTreeSet<String> = zip_info
+            .select(reeds_ba)
+            .distinct()
+            .load::<Option<String>>(db)?
+            .into_iter()
+            .flatten()
+            .collect();
+        (bas, padds, technobasins, reeds_bas)
+    };
+
+    Ok(Tables {
+        energy,
+        ba,
+        oil,
+        propane_lng,
+        reeds,
+        natgas,
+        scc,
+        discount_rates,
+        state_divisions,
+        bas,
+        padds,
+        technobasins,
+        reeds_bas,
+    })
+}
+
+impl Tables {
+    fn issues(&self) -> Vec<Issue> {
+        let mut issues = Vec::new();
+
+        // Discount rates are by length of study period rather than calendar year, so they are only required to exist
+        if self.discount_rates == 0 {
+            issues.push(Issue { table: "discount_rates", message: "no rows for this release year".to_string() });
+        }
+
+        let calendar: Vec<&Coverage> = self
+            .energy
+            .iter()
+            .chain([&self.ba, &self.oil, &self.propane_lng, &self.reeds, &self.natgas, &self.scc])
+            .collect();
+
+        // Every table should cover the years of all the others together
+        let expected = calendar.iter().flat_map(|coverage| coverage.years()).collect::<BTreeSet<_>>();
+        for coverage in &calendar {
+            if coverage.series.is_empty() {
+                issues.push(Issue { table: coverage.table, message: "no rows for this release year".to_string() });
+                continue;
+            }
+
+            let missing: Vec<i32> = expected.difference(&coverage.years()).copied().collect();
+            if !missing.is_empty() {
+                issues.push(Issue { table: coverage.table, message: format!("missing years {}", ranges(&missing)) });
+            }
+
+            // Gaps in single series, such as one division missing a year the rest of the table has
+            let years = coverage.years();
+            for (key, series) in &coverage.series {
+                let missing: Vec<i32> = years.difference(series).copied().collect();
+                if !missing.is_empty() {
+                    let message = format!("{} is missing years {}", key.join(", "), ranges(&missing));
+                    issues.push(Issue { table: coverage.table, message });
+                }
+            }
+        }
+
+        // Every division, sector and case seen in any energy table must be in all of them
+        let divisions: BTreeSet<String> = self.energy.iter().flat_map(|coverage| coverage.column(0)).collect();
+        let sectors: BTreeSet<String> = self.energy.iter().flat_map(|coverage| coverage.column(1)).collect();
+        let cases: BTreeSet<String> = self.energy.iter().flat_map(|coverage| coverage.column(2)).collect();
+        for coverage in self.energy.iter().filter(|coverage| !coverage.series.is_empty()) {
+            for division in &divisions {
+                for sector in &sectors {
+                    for case in &cases {
+                        let key = vec![division.clone(), sector.clone(), case.clone()];
+                        if !coverage.series.contains_key(&key) {
+                            let message = format!("missing {}", key.join(", "));
+                            issues.push(Issue { table: coverage.table, message });
+                        }
+                    }
+                }
+            }
+        }
+
+        // Every division a state belongs to must have energy data
+        for division in self.state_divisions.difference(&divisions).filter(|_| !divisions.is_empty()) {
+            let message = format!("missing division {} of state_division_region", division);
+            issues.push(Issue { table: "energy_prices", message });
+        }
+
+        // Every region referenced by a zipcode must have emissions data
+        for (coverage, referenced, name) in [
+            (&self.ba, &self.bas, "BA"),
+            (&self.oil, &self.padds, "PADD"),
+            (&self.propane_lng, &self.padds, "PADD"),
+            (&self.natgas, &self.technobasins, "technobasin"),
+            (&self.reeds, &self.reeds_bas, "ReEDS region"),
+        ] {
+            if coverage.series.is_empty() {
+                continue;
+            }
+
+            let present = coverage.column(0);
+            for missing in referenced.difference(&present) {
+                let message = format!("missing {} {} referenced in zip_info", name, missing);
+                issues.push(Issue { table: coverage.table, message });
+            }
+        }
+
+        issues
+    }
+}
+
+fn energy_coverage(table: &'static str, release: i32, db: &mut PgConnection) -> QueryResult<Coverage> {
+    let rows: Vec<(String, String, String, i32)> = match table {
+        "energy_prices" => {
+            use crate::schema::energy_prices::dsl::*;
+            energy_prices.filter(release_year.eq(release)).select((division, sector, case, year)).load(db)?
+        }
+        "energy_price_indices" => {
+            use crate::schema::energy_price_indices::dsl::*;
+            energy_price_indices.filter(release_year.eq(release)).select((division, sector, case, year)).load(db)?
+        }
+        _ => {
+            use crate::schema::escalation_rates::dsl::*;
+            escalation_rates.filter(release_year.eq(release)).select((division, sector, case, year)).load(db)?
+        }
+    };
+
+    Ok(Coverage::new(table, keyed(rows)))
+}
+
+/// Splits rows into the series columns, such as PADD, case and rate, and the year.
+fn keyed(rows: Vec<(String, String, String, i32)>) -> Vec<(Vec<String>, i32)> {
+    rows.into_iter().map(|row| (vec![row.0, row.1, row.2], row.3)).collect()
+}
+
+/// Formats sorted years as ranges, such as `2024, 2030-2032`.
+fn ranges(years: &[i32]) -> String {
+    let mut ranges: Vec<(i32, i32)> = Vec::new();
+    for &year in years {
+        match ranges.last_mut() {
+            Some((_, end)) if *end + 1 == year => *end = year,
+            _ => ranges.push((year, year)),
+        }
+    }
+
+    ranges
+        .iter()
+        .map(|(start, end)| if start == end { start.to_string() } else { format!("{}-{}", start, end) })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Runs `backend validate`, returning the process exit code.
+pub fn command(args: &[String], db: &mut PgConnection) -> i32 {
+    let Some(release_year) = args.first().and_then(|year| year.parse().ok()).filter(|_| args.len() == 1) else {
+        eprintln!("Usage: backend validate <release year>");
+        return 2;
+    };
+
+    match validate(release_year, db) {
+        Ok(validation) if validation.valid => {
+            println!("Release year {} is valid", release_year);
+            0
+        }
+        Ok(validation) => {
+            for issue in &validation.issues {
+                println!("{}: {}", issue.table, issue.message);
+            }
+            eprintln!("{} issues in release year {}", validation.issues.len(), release_year);
+            1
+        }
+        Err(err) => {
+            eprintln!("Could not validate release year {}: {}", release_year, err);
+            1
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn set(values: &[&str]) -> BTreeSet<String> {
+        values.iter().map(|value| value.to_string()).collect()
+    }
+
+    fn regional(table: &'static str, regions: &[&str], years: std::ops::RangeInclusive<i32>) -> Coverage {
+        let rows = regions
+            .iter()
+            .flat_map(|region| {
+                years.clone().map(move |year| (vec![region.to_string(), "REF".into(), "Avg".into()], year))
+            })
+            .collect();
+        Coverage::new(table, rows)
+    }
+
+    fn energy(table: &'static str, divisions: &[&str], years: std::ops::RangeInclusive<i32>) -> Coverage {
+        let rows = divisions
+            .iter()
+            .flat_map(|division| {
+                years.clone().map(move |year| (vec![division.to_string(), "Commercial".into(), "REF".into()], year))
+            })
+            .collect();
+        Coverage::new(table, rows)
+    }
+
+    /// A release year where every table agrees.
+    fn tables() -> Tables {
+        let divisions = ["New England", "Pacific"];
+        Tables {
+            energy: [
+                energy("energy_prices", &divisions, 2025..=2027),
+                energy("energy_price_indices", &divisions, 2025..=2027),
+                energy("escalation_rates", &divisions, 2025..=2027),
+            ],
+            ba: regional("region_case_ba", &["CISO", "ISNE"], 2025..=2027),
+            oil: regional("region_case_oil", &["PADD 1", "PADD 5"], 2025..=2027),
+            propane_lng: regional("region_case_propane_lng", &["PADD 1", "PADD 5"], 2025..=2027),
+            reeds: regional("region_case_reeds", &["p10"], 2025..=2027),
+            natgas: regional("region_natgas", &["Pacific"], 2025..=2027),
+            scc: Coverage::new("scc", (2025..=2027).map(|year| (Vec::new(), year)).collect()),
+            discount_rates: 30,
+            state_divisions: set(&divisions),
+            bas: set(&["CISO", "ISNE"]),
+            padds: set(&["PADD 1", "PADD 5"]),
+            technobasins: set(&["Pacific"]),
+            reeds_bas: set(&["p10"]),
+        }
+    }
+
+    fn messages(tables: &Tables) -> Vec<(&'static str, String)> {
+        tables.issues().into_iter().map(|issue| (issue.table, issue.message)).collect()
+    }
+
+    #[test]
+    fn formats_year_ranges() {
+        assert_eq!(ranges(&[]), "");
+        assert_eq!(ranges(&[2024]), "2024");
+        assert_eq!(ranges(&[2024, 2025]), "2024-2025");
+        assert_eq!(ranges(&[2024, 2030, 2031, 2032, 2040]), "2024, 2030-2032, 2040");
+    }
+
+    #[test]
+    fn accepts_consistent_tables() {
+        assert!(messages(&tables()).is_empty());
+    }
+
+    #[test]
+    fn reports_missing_tables_and_years() {
+        let mut tables = tables();
+        tables.discount_rates = 0;
+        tables.scc = Coverage::new("scc", Vec::new());
+        tables.natgas = regional("region_natgas", &["Pacific"], 2025..=2026);
+
+        assert_eq!(
+            messages(&tables),
+            [
+                ("discount_rates", "no rows for this release year".to_string()),
+                ("region_natgas", "missing years 2027".to_string()),
+                ("scc", "no rows for this release year".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn reports_gaps_in_single_series() {
+        let mut tables = tables();
+        let rows = [("CISO", 2025), ("CISO", 2027), ("ISNE", 2025), ("ISNE", 2026), ("ISNE", 2027)]
+            .into_iter()
+            .map(|(region, year)| (vec![region.to_string(), "REF".into(), "Avg".into()], year))
+            .collect();
+        tables.ba = Coverage::new("region_case_ba", rows);
+
+        assert_eq!(messages(&tables), [("region_case_ba", "CISO, REF, Avg is missing years 2026".to_string())]);
+    }
+
+    #[test]
+    fn reports_missing_energy_combinations_and_divisions() {
+        let mut tables = tables();
+        tables.energy[1] = energy("energy_price_indices", &["New England"], 2025..=2027);
+        tables.state_divisions.insert("Mountain".to_string());
+
+        assert_eq!(
+            messages(&tables),
+            [
+                ("energy_price_indices", "missing Pacific, Commercial, REF".to_string()),
+                ("energy_prices", "missing division Mountain of state_division_region".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn reports_regions_referenced_in_zip_info() {
+        let mut tables = tables();
+        tables.padds.insert("PADD 3".to_string());
+        tables.reeds_bas.insert("p11".to_string());
+
+        assert_eq!(
+            messages(&tables),
+            [
+                ("region_case_oil", "missing PADD PADD 3 referenced in zip_info".to_string()),
+                ("region_case_propane_lng", "missing PADD PADD 3 referenced in zip_info".to_string()),
+                ("region_case_reeds", "missing ReEDS region p11 referenced in zip_info".to_string()),
+            ]
+        );
+    }
+}
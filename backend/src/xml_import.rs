@@ -0,0 +1,1055 @@
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use roxmltree::{Document, Node, NodeId};
+
+use crate::lcc::calculate_nominal_discount_rate;
+use crate::project::*;
+
+const VERSION: &str = "1.0.0";
+const PROJECT_ID: ID = 1;
+const REAL_DISCOUNT_RATE: f64 = 0.03;
+const INFLATION_RATE: f64 = 0.015;
+const COUNTRY: &str = "United States of America";
+
+/// A project converted from a BLCC 5.x XML file along with everything in the file that could not be carried over.
+pub struct Imported {
+    pub data: ProjectData,
+    pub warnings: Vec<String>,
+}
+
+/// Converts a BLCC 5.x XML project file into the current project format. This follows the converter in the frontend,
+/// so a file imported here produces the same project as one uploaded through the browser.
+pub fn import(xml: &str, release_year: i32) -> Result<Imported, String> {
+    let document = Document::parse(xml).map_err(|err| format!("Could not parse XML: {}", err))?;
+    let root = document.root_element();
+    if !root.has_tag_name("Project") {
+        return Err(format!("Expected a <Project> root element, found <{}>", root.tag_name().name()));
+    }
+
+    let reader = Reader::new(xml);
+    let data = reader.project(root, release_year);
+    let warnings = reader.finish(root);
+
+    Ok(Imported { data, warnings })
+}
+
+/// Duration in the legacy "15 years 0 months" format.
+#[derive(Clone, Copy)]
+enum DateDiff {
+    Remaining,
+    Year(i32),
+}
+
+impl DateDiff {
+    fn years(&self) -> Option<i32> {
+        match self {
+            DateDiff::Remaining => None,
+            DateDiff::Year(years) => Some(*years),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum CostElement {
+    CapitalComponent,
+    CapitalReplacement,
+    RecurringCost,
+    NonRecurringCost,
+    Ercip,
+    EnergyUsage,
+    WaterUsage,
+    RecurringContractCost,
+    NonRecurringContractCost,
+}
+
+impl CostElement {
+    fn tag(&self) -> &'static str {
+        match self {
+            CostElement::CapitalComponent => "CapitalComponent",
+            CostElement::CapitalReplacement => "CapitalReplacement",
+            CostElement::RecurringCost => "RecurringCost",
+            CostElement::NonRecurringCost => "NonRecurringCost",
+            CostElement::Ercip => "CapitalComponent",
+            CostElement::EnergyUsage => "EnergyUsage",
+            CostElement::WaterUsage => "WaterUsage",
+            CostElement::RecurringContractCost => "RecurringContractCost",
+            CostElement::NonRecurringContractCost => "NonRecurringContractCost",
+        }
+    }
+}
+
+/// A cost element found in an alternative, along with the name it will be imported under.
+struct Extracted<'a, 'input> {
+    component: CostElement,
+    node: Node<'a, 'input>,
+    name: String,
+}
+
+struct Settings {
+    study_period: i32,
+    construction_period: i32,
+    location: Location,
+    analysis_type: AnalysisType,
+}
+
+/// Reads values out of the document while recording which elements were used, so everything left over can be
+/// reported back.
+struct Reader<'x> {
+    xml: &'x str,
+    used: RefCell<HashSet<NodeId>>,
+    warnings: RefCell<Vec<String>>,
+}
+
+impl<'x> Reader<'x> {
+    fn new(xml: &'x str) -> Self {
+        Reader {
+            xml,
+            used: RefCell::new(HashSet::new()),
+            warnings: RefCell::new(vec![]),
+        }
+    }
+
+    fn warn(&self, warning: String) {
+        self.warnings.borrow_mut().push(warning);
+    }
+
+    fn mark(&self, node: Node) {
+        self.used.borrow_mut().insert(node.id());
+    }
+
+    fn child<'a, 'input>(&self, node: Node<'a, 'input>, name: &str) -> Option<Node<'a, 'input>> {
+        let child = node.children().find(|child| child.has_tag_name(name))?;
+        self.mark(child);
+
+        Some(child)
+    }
+
+    fn children<'a, 'input>(&self, node: Node<'a, 'input>, name: &str) -> Vec<Node<'a, 'input>> {
+        node.children()
+            .filter(|child| child.has_tag_name(name))
+            .inspect(|child| self.mark(*child))
+            .collect()
+    }
+
+    fn text(&self, node: Node, name: &str) -> Option<String> {
+        self.child(node, name)
+            .and_then(|child| child.text())
+            .map(|text| text.trim().to_string())
+            .filter(|text| !text.is_empty())
+    }
+
+    fn number(&self, node: Node, name: &str) -> Option<f64> {
+        let text = self.text(node, name)?;
+        let number = text.parse::<f64>().ok();
+        if number.is_none() {
+            self.warn(format!("{} is not a number: {}", path(node, name), text));
+        }
+
+        number
+    }
+
+    fn years(&self, node: Node, name: &str) -> DateDiff {
+        let text = self.text(node, name);
+        let (diff, months) = parse_years(text.as_deref());
+
+        if months.is_some_and(|months| months != 0) {
+            self.warn(format!(
+                "{} \"{}\" has months which are not supported, rounded down to whole years",
+                path(node, name),
+                text.unwrap_or_default()
+            ));
+        }
+
+        diff
+    }
+
+    /// Returns every warning, including one per element path that was never read.
+    fn finish(self, root: Node) -> Vec<String> {
+        self.mark(root);
+
+        let mut unmapped: BTreeMap<String, usize> = BTreeMap::new();
+        let used = self.used.borrow();
+        for node in root.descendants().filter(|node| node.is_element()) {
+            let parent_used = node.parent_element().is_some_and(|parent| used.contains(&parent.id()));
+            if !used.contains(&node.id()) && parent_used {
+                *unmapped.entry(element_path(node)).or_default() += 1;
+            }
+        }
+
+        let mut warnings = self.warnings.take();
+        warnings.extend(unmapped.into_iter().map(|(path, count)| match count {
+            1 => format!("Unmapped element {} was ignored", path),
+            count => format!("Unmapped element {} was ignored ({} occurrences)", path, count),
+        }));
+
+        warnings
+    }
+
+    fn project(&self, root: Node, release_year: i32) -> ProjectData {
+        let study_period = match self.years(root, "Duration") {
+            DateDiff::Remaining => 0,
+            DateDiff::Year(years) => years,
+        };
+        let construction_period = self.years(root, "PCPeriod").years().unwrap_or(0);
+        let location = self.location(root, "Location");
+        let dollar_method = match self.number(root, "DollarMethod").map(|value| value as i32) {
+            Some(1) => DollarMethod::Current,
+            _ => DollarMethod::Constant,
+        };
+
+        let real_discount_rate = self.number(root, "DiscountRate").filter(|rate| *rate != 0.0).unwrap_or(REAL_DISCOUNT_RATE);
+        let inflation_rate = self.number(root, "InflationRate").filter(|rate| *rate != 0.0).unwrap_or(INFLATION_RATE);
+
+        let analysis_type = match self.number(root, "AnalysisType").map(|value| value as i32) {
+            Some(1) => AnalysisType::FederalFinanced,
+            Some(2) => AnalysisType::MilconEnergy,
+            Some(3) => AnalysisType::MilconEcip,
+            Some(4) => AnalysisType::OmbNonEnergy,
+            Some(5) => AnalysisType::MilconNonEnergy,
+            _ => AnalysisType::FempEnergy,
+        };
+        let purpose = match self.number(root, "AnalysisPurpose").map(|value| value as i32) {
+            Some(0) => Some(Purpose::CostLease),
+            Some(1) => Some(Purpose::InvestRegulation),
+            _ => None,
+        };
+        let discounting_method = match self.number(root, "DiscountingMethod").map(|value| value as i32) {
+            Some(2) => DiscountingMethod::MidYear,
+            _ => DiscountingMethod::EndOfYear,
+        };
+
+        let settings = Settings {
+            study_period,
+            construction_period,
+            location: location.clone(),
+            analysis_type,
+        };
+        let (alternatives, costs) = self.alternatives(root, &settings);
+
+        let project = Project {
+            id: Some(PROJECT_ID),
+            version: VERSION.to_string(),
+            name: self.text(root, "Name"),
+            description: self.text(root, "Comment"),
+            analyst: self.text(root, "Analyst"),
+            analysis_type: Some(analysis_type),
+            purpose,
+            dollar_method,
+            case: Case::Ref,
+            study_period: Some(study_period),
+            construction_period,
+            discounting_method: Some(discounting_method),
+            real_discount_rate: Some(real_discount_rate),
+            nominal_discount_rate: Some(calculate_nominal_discount_rate(real_discount_rate, inflation_rate)),
+            inflation_rate: Some(inflation_rate),
+            location,
+            project_escalation_rates: None,
+            alternatives: alternatives.iter().filter_map(|alternative| alternative.id).collect(),
+            costs: costs.iter().map(Cost::id).collect(),
+            ghg: Ghg {
+                data_source: GhgDataSource::NistNetl,
+                emissions_rate_type: EmissionsRateType::Average,
+            },
+            release_year,
+        };
+
+        ProjectData { project, alternatives, costs }
+    }
+
+    fn location(&self, node: Node, name: &str) -> Location {
+        let state = self.text(node, name);
+        let abbreviation = state.as_deref().and_then(state_abbreviation);
+
+        if let (Some(state), None) = (&state, abbreviation) {
+            self.warn(format!("{} \"{}\" is not a known state", path(node, name), state));
+        }
+
+        Location {
+            country: Some(COUNTRY.to_string()),
+            state: abbreviation.map(str::to_string),
+            ..Default::default()
+        }
+    }
+
+    fn alternatives(&self, root: Node, settings: &Settings) -> (Vec<Alternative>, Vec<Cost>) {
+        let nodes = self
+            .child(root, "Alternatives")
+            .map(|alternatives| self.children(alternatives, "Alternative"))
+            .unwrap_or_default();
+
+        // Identical costs in several alternatives are imported once and shared
+        let mut cache: HashMap<String, ID> = HashMap::new();
+        let mut costs: Vec<Cost> = vec![];
+        let mut alternatives = vec![];
+
+        for (index, node) in nodes.into_iter().enumerate() {
+            let mut ids = vec![];
+
+            for extracted in self.extract_costs(node, settings) {
+                let key = self.cost_key(&extracted);
+                let next_id = costs.len() as ID + 1;
+                let cost = self.cost(&extracted, next_id, settings);
+
+                let id = *cache.entry(key).or_insert_with(|| {
+                    costs.push(cost);
+                    next_id
+                });
+                ids.push(id);
+            }
+
+            alternatives.push(Alternative {
+                id: Some(index as ID + 1),
+                name: self.text(node, "Name").unwrap_or_default(),
+                description: self.text(node, "Comment"),
+                baseline: None,
+                ercip_base_case: None,
+                costs: ids,
+            });
+        }
+
+        if settings.analysis_type == AnalysisType::MilconEcip {
+            let id = costs.len() as ID + 1;
+            costs.push(Cost::Ercip(ErcipCost {
+                base: base_cost(id, "Base Cost".to_string(), None, None),
+                construction_cost: 0.0,
+                sioh: 0.0,
+                design_cost: 0.0,
+                salvage_value: 0.0,
+                public_utility_rebate: 0.0,
+                cybersecurity: 0.0,
+            }));
+
+            alternatives.insert(
+                0,
+                Alternative {
+                    id: Some(alternatives.len() as ID + 1),
+                    name: "Base Cost".to_string(),
+                    description: None,
+                    baseline: Some(true),
+                    ercip_base_case: Some(true),
+                    costs: vec![id],
+                },
+            );
+        }
+
+        (alternatives, costs)
+    }
+
+    /// Lists the costs of an alternative in the order the frontend converter does: capital components, the costs
+    /// nested in each of them, then energy, water and contract costs.
+    fn extract_costs<'a, 'input>(&self, alternative: Node<'a, 'input>, settings: &Settings) -> Vec<Extracted<'a, 'input>> {
+        let extract = |node: Node<'a, 'input>, component: CostElement, prefix: Option<&str>| -> Vec<Extracted<'a, 'input>> {
+            let tag = component.tag();
+            let Some(list) = self.child(node, &format!("{}s", tag)) else {
+                return vec![];
+            };
+
+            self.children(list, tag)
+                .into_iter()
+                .map(|node| {
+                    let name = self.text(node, "Name");
+                    let name = match prefix {
+                        Some(prefix) => format!("{} {}", prefix, name.unwrap_or_default()),
+                        None => name.unwrap_or_else(|| "Unnamed Cost".to_string()),
+                    };
+
+                    Extracted { component, node, name }
+                })
+                .collect()
+        };
+
+        let capital = extract(alternative, CostElement::CapitalComponent, None);
+        let mut result = vec![];
+
+        for component in capital.iter() {
+            let prefix = Some(component.name.as_str());
+            result.extend(extract(component.node, CostElement::CapitalReplacement, prefix));
+            result.extend(extract(component.node, CostElement::RecurringCost, prefix));
+            result.extend(extract(component.node, CostElement::NonRecurringCost, prefix));
+
+            if settings.analysis_type == AnalysisType::MilconEcip {
+                result.push(Extracted {
+                    component: CostElement::Ercip,
+                    node: component.node,
+                    name: "ERCIP".to_string(),
+                });
+            }
+        }
+
+        let mut costs = capital;
+        costs.append(&mut result);
+        costs.extend(extract(alternative, CostElement::EnergyUsage, None));
+        costs.extend(extract(alternative, CostElement::WaterUsage, None));
+        costs.extend(extract(alternative, CostElement::RecurringContractCost, None));
+        costs.extend(extract(alternative, CostElement::NonRecurringContractCost, None));
+
+        costs
+    }
+
+    /// Two cost elements are the same cost if they are the same kind, have the same name, and have identical XML.
+    fn cost_key(&self, extracted: &Extracted) -> String {
+        let source = &self.xml[extracted.node.range()];
+        let normalized: String = source.split_whitespace().collect::<Vec<_>>().join(" ");
+
+        format!("{}\u{0}{}\u{0}{}", extracted.component.tag(), extracted.name, normalized)
+    }
+
+    fn cost(&self, extracted: &Extracted, id: ID, settings: &Settings) -> Cost {
+        let node = extracted.node;
+        let study_period = settings.study_period;
+        let construction_period = settings.construction_period;
+        let savings = Some(settings.analysis_type == AnalysisType::MilconEcip);
+        let name = extracted.name.clone();
+        let description = self.text(node, "Comment");
+
+        match extracted.component {
+            CostElement::CapitalComponent => {
+                let cost_adjustment = match self.escalation(node, "Escalation", study_period) {
+                    Some(NumberOrArray::Number(value)) => value,
+                    Some(NumberOrArray::Array(values)) => {
+                        self.warn(format!(
+                            "{} has a varying escalation which is not supported, using the first year",
+                            path(node, "Escalation")
+                        ));
+                        values.first().copied().unwrap_or(0.0)
+                    }
+                    None => 0.0,
+                };
+
+                Cost::Capital(CapitalCost {
+                    base: base_cost(id, name, description, None),
+                    initial_cost: Some(self.number(node, "InitialCost").unwrap_or(0.0)),
+                    amount_financed: self.number(node, "AmountFinanced"),
+                    rate_of_change_value: Some(
+                        self.escalation(node, "ResaleEscalation", study_period).unwrap_or(NumberOrArray::Number(0.0)),
+                    ),
+                    expected_life: self.years(node, "Duration").years(),
+                    cost_adjustment: Some(cost_adjustment),
+                    phase_in: self.phase_in(node, study_period),
+                    residual_value: self
+                        .number(node, "ResaleValueFactor")
+                        .filter(|value| *value != 0.0)
+                        .map(|value| ResidualValue {
+                            approach: DollarOrPercent::Percent,
+                            value,
+                        }),
+                })
+            }
+            CostElement::CapitalReplacement => Cost::ReplacementCapital(ReplacementCapitalCost {
+                base: base_cost(id, name, description, savings),
+                initial_cost: Some(self.number(node, "InitialCost").unwrap_or(0.0)),
+                rate_of_change_value: Some(self.escalation_or_zero(node, "Escalation", study_period)),
+                initial_occurrence: self.years(node, "Start").years().unwrap_or(0),
+                expected_life: self.years(node, "Duration").years(),
+                residual_value: Some(ResidualValue {
+                    approach: DollarOrPercent::Percent,
+                    value: self.number(node, "ResaleValueFactor").unwrap_or(0.0),
+                }),
+            }),
+            CostElement::NonRecurringCost => Cost::Omr(OmrCost {
+                base: base_cost(id, name, description, savings),
+                initial_cost: Some(self.number(node, "Amount").unwrap_or(0.0)),
+                initial_occurrence: self.years(node, "Start").years().unwrap_or(0),
+                rate_of_change_value: Some(self.escalation_or_zero(node, "Escalation", study_period)),
+                recurring: None,
+            }),
+            CostElement::RecurringCost => {
+                let index = self.use_index(node, "Index", study_period, construction_period);
+                self.duration(node);
+
+                Cost::Omr(OmrCost {
+                    base: base_cost(id, name, description, savings),
+                    initial_cost: Some(self.number(node, "Amount").unwrap_or(0.0)),
+                    initial_occurrence: initial_from_varying(index.as_ref()),
+                    rate_of_change_value: Some(self.escalation_or_zero(node, "Escalation", study_period)),
+                    recurring: Some(Recurring {
+                        rate_of_recurrence: Some(1),
+                        duration: Some(duration_from_varying(index.as_ref(), study_period, construction_period)),
+                    }),
+                })
+            }
+            CostElement::Ercip => Cost::Ercip(ErcipCost {
+                base: base_cost(id, name, None, None),
+                construction_cost: self.number(node, "ConstructionCost").unwrap_or(0.0),
+                sioh: self.number(node, "SIOH").unwrap_or(0.0),
+                design_cost: self.number(node, "DesignCost").unwrap_or(0.0),
+                salvage_value: self.number(node, "SalvageValue").unwrap_or(0.0),
+                public_utility_rebate: self.number(node, "UtilityRebate").unwrap_or(0.0),
+                cybersecurity: 0.0,
+            }),
+            CostElement::EnergyUsage => {
+                let escalation = self.escalation(node, "Escalation", study_period);
+                let cost_location = self.location(node, "State");
+                let location = (cost_location.state != settings.location.state).then_some(cost_location);
+                self.duration(node);
+
+                Cost::Energy(EnergyCost {
+                    base: BaseCost {
+                        location,
+                        ..base_cost(id, name, description, savings)
+                    },
+                    fuel_type: parse_fuel_type(self.text(node, "FuelType").as_deref()),
+                    customer_sector: self.customer_sector(node),
+                    cost_per_unit: self.number(node, "UnitCost").unwrap_or(0.0),
+                    annual_consumption: self.number(node, "YearlyUsage").unwrap_or(0.0),
+                    unit: self.unit(node, Unit::Kwh),
+                    demand_charge: self.number(node, "DemandCharge"),
+                    rebate: self.number(node, "UtilityRebate"),
+                    custom_escalation: Some(escalation.is_some()),
+                    escalation,
+                    use_index: self.use_index(node, "UsageIndex", study_period, construction_period),
+                    emissions: None,
+                })
+            }
+            CostElement::WaterUsage => Cost::Water(WaterCost {
+                base: base_cost(id, name, description, savings),
+                unit: self.unit(node, Unit::Gallon),
+                usage: self.seasonal_usage(node, "Usage"),
+                disposal: self.seasonal_usage(node, "Disposal"),
+                custom_escalation: None,
+                escalation: self.escalation(node, "UsageEscalation", study_period),
+                use_index: self.use_index(node, "UsageIndex", study_period, construction_period),
+            }),
+            CostElement::RecurringContractCost => {
+                let index = self.use_index(node, "Index", study_period, construction_period);
+                self.duration(node);
+
+                Cost::RecurringContract(RecurringContractCost {
+                    base: base_cost(id, name, description, savings),
+                    initial_cost: Some(self.number(node, "Amount").unwrap_or(0.0)),
+                    initial_occurrence: initial_from_varying(index.as_ref()),
+                    rate_of_change_value: Some(self.escalation_or_zero(node, "Escalation", study_period)),
+                    recurring: Some(Recurring {
+                        rate_of_recurrence: Some(self.years(node, "Interval").years().unwrap_or(1)),
+                        duration: Some(duration_from_varying(index.as_ref(), study_period, construction_period)),
+                    }),
+                })
+            }
+            CostElement::NonRecurringContractCost => Cost::ImplementationContract(ImplementationContractCost {
+                base: base_cost(id, name, description, savings),
+                cost: Some(self.number(node, "Amount").unwrap_or(0.0)),
+                initial_occurrence: self.years(node, "Start").years().unwrap_or(0),
+                rate_of_change_value: Some(self.escalation_or_zero(node, "Escalation", study_period)),
+            }),
+        }
+    }
+
+    /// Recurring costs run for as long as their usage index says, so an explicit duration is only noted when it
+    /// differs from the rest of the study period.
+    fn duration(&self, node: Node) {
+        if let Some(duration) = self.text(node, "Duration").filter(|duration| duration != "Remaining") {
+            self.warn(format!(
+                "{} \"{}\" is not supported, the usage index determines how long the cost lasts",
+                path(node, "Duration"),
+                duration
+            ));
+        }
+    }
+
+    fn customer_sector(&self, node: Node) -> Option<CustomerSector> {
+        let text = self.text(node, "RateSchedule")?;
+
+        match text.as_str() {
+            "Residential" => Some(CustomerSector::Residential),
+            "Commercial" => Some(CustomerSector::Commercial),
+            "Industrial" => Some(CustomerSector::Industrial),
+            "Transportation" => Some(CustomerSector::Transportation),
+            _ => {
+                self.warn(format!("{} \"{}\" is not a known rate schedule", path(node, "RateSchedule"), text));
+                None
+            }
+        }
+    }
+
+    fn unit(&self, node: Node, default: Unit) -> Unit {
+        let text = self.text(node, "Units");
+
+        match text.as_deref().and_then(parse_unit) {
+            Some(unit) => unit,
+            None => {
+                self.warn(format!(
+                    "{} \"{}\" is not a known unit, using {}",
+                    path(node, "Units"),
+                    text.unwrap_or_default(),
+                    default.label()
+                ));
+                default
+            }
+        }
+    }
+
+    fn seasonal_usage(&self, node: Node, category: &str) -> Vec<SeasonUsage> {
+        [(Season::Winter, "Winter"), (Season::Summer, "Summer")]
+            .into_iter()
+            .map(|(season, label)| SeasonUsage {
+                season,
+                amount: self.number(node, &format!("{}Yearly{}", label, category)).unwrap_or(0.0),
+                cost_per_unit: self.number(node, &format!("{}{}UnitCost", label, category)).unwrap_or(0.0),
+            })
+            .collect()
+    }
+
+    fn escalation(&self, node: Node, name: &str, study_period: i32) -> Option<NumberOrArray> {
+        let escalation = self.child(node, name)?;
+        let kind = escalation.children().find(|child| child.is_element())?;
+        self.mark(kind);
+
+        match kind.tag_name().name() {
+            "SimpleEscalation" => self.number(kind, "Rate").map(NumberOrArray::Number),
+            "VaryingEscalation" => {
+                let intervals = self.text(kind, "Intervals")?;
+                let values = self.text(kind, "Values")?;
+
+                match parse_varying(&intervals, &values, study_period)? {
+                    NumberOrArray::Array(mut values) => {
+                        values.truncate(study_period.max(0) as usize);
+                        Some(NumberOrArray::Array(values))
+                    }
+                    value => Some(value),
+                }
+            }
+            _ => None,
+        }
+    }
+
+    fn escalation_or_zero(&self, node: Node, name: &str, study_period: i32) -> NumberOrArray {
+        self.escalation(node, name, study_period).unwrap_or(NumberOrArray::Number(0.0))
+    }
+
+    /// Reads a usage index. Usage only starts once construction is done, so the construction years are moved to the
+    /// front as zeros.
+    fn use_index(&self, node: Node, name: &str, study_period: i32, construction_period: i32) -> Option<NumberOrArray> {
+        let index = self.child(node, name)?;
+        let inner = self.child(index, "UsageIndex")?;
+        let intervals = self.text(inner, "Intervals")?;
+        let values = self.text(inner, "Values")?;
+
+        match parse_varying(&intervals, &values, study_period)? {
+            NumberOrArray::Array(values) if construction_period > 0 => {
+                let construction = construction_period as usize;
+                let kept = values.len().saturating_sub(construction);
+
+                let mut shifted = vec![0.0; construction];
+                shifted.extend_from_slice(&values[..kept]);
+                Some(NumberOrArray::Array(shifted))
+            }
+            value => Some(value),
+        }
+    }
+
+    fn phase_in(&self, node: Node, study_period: i32) -> Option<Vec<f64>> {
+        let inner = self.child(node, "PhaseIn").and_then(|phase_in| self.child(phase_in, "PhaseIn"))?;
+        let portions = parse_portions(&self.text(inner, "Portions")?);
+        let intervals: Vec<DateDiff> = self
+            .text(inner, "Intervals")?
+            .split(',')
+            .map(|interval| parse_years(Some(interval)).0)
+            .collect();
+
+        let mut result = vec![0.0; study_period.max(0) as usize];
+        let mut stride = 0;
+        for (portion, interval) in portions.iter().zip(intervals.iter()) {
+            let years = interval.years().unwrap_or(0).max(0) as usize;
+
+            for j in stride..stride + years {
+                set_growing(&mut result, j + 1, portion / years as f64);
+            }
+
+            stride += years;
+        }
+
+        result.iter().any(|value| *value != 0.0).then_some(result)
+    }
+}
+
+fn base_cost(id: ID, name: String, description: Option<String>, cost_savings: Option<bool>) -> BaseCost {
+    BaseCost {
+        id,
+        name,
+        description,
+        location: None,
+        cost_savings,
+    }
+}
+
+fn set_growing(values: &mut Vec<f64>, index: usize, value: f64) {
+    if index >= values.len() {
+        values.resize(index + 1, 0.0);
+    }
+    values[index] = value;
+}
+
+/// Parses "15 years 0 months" into the whole years and the months, or `Remaining`. Anything unrecognized counts as a
+/// single year, the same as the frontend converter.
+fn parse_years(value: Option<&str>) -> (DateDiff, Option<i32>) {
+    let Some(value) = value.map(str::trim) else {
+        return (DateDiff::Year(1), None);
+    };
+
+    if value == "Remaining" {
+        return (DateDiff::Remaining, None);
+    }
+
+    let tokens: Vec<&str> = value.split_whitespace().collect();
+    for window in tokens.windows(4) {
+        if let (Ok(years), true, Ok(months), true) = (
+            window[0].parse::<i32>(),
+            window[1].starts_with("year"),
+            window[2].parse::<i32>(),
+            window[3].starts_with("month"),
+        ) {
+            return (DateDiff::Year(years), Some(months));
+        }
+    }
+
+    (DateDiff::Year(1), None)
+}
+
+fn parse_portions(value: &str) -> Vec<f64> {
+    value.split(',').map(|portion| portion.trim().parse::<f64>().unwrap_or(f64::NAN)).collect()
+}
+
+/// Expands values given over intervals ("1 year 0 months,Remaining" with "0.0,1.0") into year by year values.
+/// Returns `None` if every value is zero.
+fn parse_varying(intervals: &str, values: &str, study_period: i32) -> Option<NumberOrArray> {
+    if intervals.trim() == "Remaining" {
+        return parse_portions(values).first().filter(|value| !value.is_nan()).map(|value| NumberOrArray::Number(*value));
+    }
+
+    let portions = parse_portions(values);
+    let diffs: Vec<DateDiff> = intervals.split(',').map(|interval| parse_years(Some(interval)).0).collect();
+
+    let length = portions.len().max(study_period.max(0) as usize);
+    let mut result = vec![0.0; length];
+
+    let mut stride = 0;
+    for (portion, diff) in portions.iter().zip(diffs.iter()) {
+        match diff {
+            DateDiff::Year(years) => {
+                let years = (*years).max(0) as usize;
+                for j in stride..stride + years {
+                    set_growing(&mut result, j, *portion);
+                }
+                stride += years;
+            }
+            DateDiff::Remaining => {
+                for value in result.iter_mut().skip(stride) {
+                    *value = *portion;
+                }
+            }
+        }
+    }
+
+    result.iter().any(|value| *value != 0.0).then_some(NumberOrArray::Array(result))
+}
+
+/// The first year with usage, counting from one.
+fn initial_from_varying(values: Option<&NumberOrArray>) -> i32 {
+    match values {
+        Some(NumberOrArray::Array(values)) => {
+            values.iter().position(|value| *value != 0.0).map(|index| index as i32 + 1).unwrap_or(0)
+        }
+        _ => 1,
+    }
+}
+
+/// The number of consecutive years, starting from the first, in which the cost is used at least half the time.
+fn duration_from_varying(values: Option<&NumberOrArray>, study_period: i32, construction_period: i32) -> i32 {
+    match values {
+        Some(NumberOrArray::Number(value)) if *value >= 0.5 => study_period - construction_period,
+        Some(NumberOrArray::Number(_)) | None => 0,
+        Some(NumberOrArray::Array(values)) => values
+            .iter()
+            .skip_while(|value| **value < 0.5)
+            .take_while(|value| **value >= 0.5)
+            .count() as i32,
+    }
+}
+
+fn parse_fuel_type(value: Option<&str>) -> FuelType {
+    match value {
+        Some("Electricity") => FuelType::Electricity,
+        Some("NatGas") => FuelType::NaturalGas,
+        Some("LPG") => FuelType::Propane,
+        Some("DistOil") => FuelType::DistillateOil,
+        Some("ResidOil") => FuelType::ResidualOil,
+        Some("Coal") => FuelType::Coal,
+        _ => FuelType::Other,
+    }
+}
+
+fn parse_unit(value: &str) -> Option<Unit> {
+    match value.to_uppercase().as_str() {
+        "KWH" => Some(Unit::Kwh),
+        "GJ" => Some(Unit::Gj),
+        "MJ" => Some(Unit::Mj),
+        "THERM" => Some(Unit::Therm),
+        "MBTU" => Some(Unit::Mbtu),
+        "LITER" => Some(Unit::Liter),
+        "1,000 LITER" => Some(Unit::KLiter),
+        "GALLON" => Some(Unit::Gallon),
+        "1,000 GALLON" => Some(Unit::KGallon),
+        "CUBIC METERS" => Some(Unit::CubicMeters),
+        "CUBIC FEET" => Some(Unit::CubicFeet),
+        "KG" => Some(Unit::Kg),
+        "POUND" | "LB" => Some(Unit::Pound),
+        _ => None,
+    }
+}
+
+const STATES: &[(&str, &str)] = &[
+    ("Alabama", "AL"),
+    ("Alaska", "AK"),
+    ("American Samoa", "AS"),
+    ("Arizona", "AZ"),
+    ("Arkansas", "AR"),
+    ("California", "CA"),
+    ("Colorado", "CO"),
+    ("Connecticut", "CT"),
+    ("Delaware", "DE"),
+    ("District of Columbia", "DC"),
+    ("Florida", "FL"),
+    ("Georgia", "GA"),
+    ("Guam", "GU"),
+    ("Hawaii", "HI"),
+    ("Idaho", "ID"),
+    ("Illinois", "IL"),
+    ("Indiana", "IN"),
+    ("Iowa", "IA"),
+    ("Kansas", "KS"),
+    ("Kentucky", "KY"),
+    ("Louisiana", "LA"),
+    ("Maine", "ME"),
+    ("Maryland", "MD"),
+    ("Massachusetts", "MA"),
+    ("Michigan", "MI"),
+    ("Minnesota", "MN"),
+    ("Mississippi", "MS"),
+    ("Missouri", "MO"),
+    ("Montana", "MT"),
+    ("Nebraska", "NE"),
+    ("Nevada", "NV"),
+    ("New Hampshire", "NH"),
+    ("New Jersey", "NJ"),
+    ("New Mexico", "NM"),
+    ("New York", "NY"),
+    ("North Carolina", "NC"),
+    ("North Dakota", "ND"),
+    ("Ohio", "OH"),
+    ("Oklahoma", "OK"),
+    ("Oregon", "OR"),
+    ("Pennsylvania", "PA"),
+    ("Puerto Rico", "PR"),
+    ("Rhode Island", "RI"),
+    ("South Carolina", "SC"),
+    ("South Dakota", "SD"),
+    ("Tennessee", "TN"),
+    ("Texas", "TX"),
+    ("U.S. Average", "U.S. Average"),
+    ("Utah", "UT"),
+    ("Vermont", "VT"),
+    ("Virgin Islands", "VI"),
+    ("Virginia", "VA"),
+    ("Washington", "WA"),
+    ("West Virginia", "WV"),
+    ("Wisconsin", "WI"),
+    ("Wyoming", "WY"),
+];
+
+fn state_abbreviation(name: &str) -> Option<&'static str> {
+    STATES
+        .iter()
+        .find(|(state, _)| *state == name)
+        .map(|(_, abbreviation)| *abbreviation)
+}
+
+/// The full state name used by BLCC 5 for a state abbreviation.
+pub fn state_name(abbreviation: &str) -> Option<&'static str> {
+    STATES
+        .iter()
+        .find(|(_, state)| *state == abbreviation)
+        .map(|(name, _)| *name)
+}
+
+fn element_path(node: Node) -> String {
+    let mut names: Vec<&str> = node
+        .ancestors()
+        .filter(|node| node.is_element())
+        .map(|node| node.tag_name().name())
+        .collect();
+    names.reverse();
+
+    names.join("/")
+}
+
+fn path(node: Node, name: &str) -> String {
+    format!("{}/{}", element_path(node), name)
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use std::fs;
+    use std::path::PathBuf;
+
+    use super::*;
+
+    /// Every BLCC 5.3 project file shipped in the docs folder.
+    pub fn fixtures() -> Vec<PathBuf> {
+        let mut paths: Vec<PathBuf> = ["../docs/test-files", "../docs/old-blcc-files"]
+            .iter()
+            .flat_map(|dir| fs::read_dir(dir).unwrap_or_else(|err| panic!("Could not read {}: {}", dir, err)))
+            .map(|entry| entry.unwrap().path())
+            .filter(|path| path.extension().is_some_and(|extension| extension == "xml"))
+            .collect();
+        paths.sort();
+
+        paths
+    }
+
+    fn import_fixture(name: &str) -> Imported {
+        let path = fixtures().into_iter().find(|path| path.ends_with(name)).expect("fixture should exist");
+        import(&fs::read_to_string(path).unwrap(), 2024).expect("fixture should import")
+    }
+
+    fn cost_types(data: &ProjectData) -> Vec<String> {
+        data.costs
+            .iter()
+            .map(|cost| serde_json::to_value(cost).unwrap()["type"].as_str().unwrap().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn imports_every_shipped_file() {
+        let fixtures = fixtures();
+        assert_eq!(fixtures.len(), 18);
+
+        for path in fixtures {
+            let imported = import(&fs::read_to_string(&path).unwrap(), 2024)
+                .unwrap_or_else(|err| panic!("{} failed to import: {}", path.display(), err));
+            let data = &imported.data;
+            let ids: Vec<ID> = data.costs.iter().map(Cost::id).collect();
+
+            assert!(data.project.study_period.is_some_and(|period| period > 0), "{}", path.display());
+            assert!(!data.alternatives.is_empty(), "{}", path.display());
+            assert_eq!(data.project.alternatives.len(), data.alternatives.len(), "{}", path.display());
+            assert_eq!(data.project.costs, ids, "{}", path.display());
+            for alternative in data.alternatives.iter() {
+                assert!(alternative.costs.iter().all(|id| ids.contains(id)), "{}", path.display());
+            }
+
+            // The base date has no equivalent in the current format
+            assert!(imported.warnings.contains(&"Unmapped element Project/BaseDate was ignored".to_string()));
+        }
+    }
+
+    #[test]
+    fn imports_federal_financed() {
+        let imported = import_fixture("FederalFinanced.xml");
+        let data = &imported.data;
+
+        assert_eq!(data.project.name.as_deref(), Some("Lighting/Daylighting"));
+        assert_eq!(data.project.study_period, Some(15));
+        assert!(data.project.analysis_type == Some(AnalysisType::FederalFinanced));
+        assert_eq!(data.project.real_discount_rate, Some(0.03));
+        assert_eq!(
+            data.alternatives.iter().map(|alternative| alternative.name.as_str()).collect::<Vec<_>>(),
+            ["Existing", "Lighting Retrofit"]
+        );
+        assert_eq!(
+            cost_types(data),
+            ["Capital Investment Cost", "OMR", "Energy", "Capital Investment Cost", "OMR", "Energy", "Recurring Contract"]
+        );
+
+        let Some(Cost::Energy(electricity)) = data.costs.get(2) else {
+            panic!("expected an energy cost");
+        };
+        assert_eq!(electricity.fuel_type, FuelType::Electricity);
+        assert_eq!(electricity.cost_per_unit, 0.046);
+        assert_eq!(electricity.annual_consumption, 1082633.0);
+
+        let Some(Cost::RecurringContract(contract)) = data.costs.get(6) else {
+            panic!("expected a recurring contract cost");
+        };
+        assert_eq!(contract.initial_cost, Some(67000.0));
+        assert_eq!(contract.initial_occurrence, 1);
+
+        assert_eq!(
+            imported.warnings,
+            [
+                "Unmapped element Project/Alternatives/Alternative/EnergyUsages/EnergyUsage/Emissions was ignored (2 occurrences)",
+                "Unmapped element Project/BaseDate was ignored",
+            ]
+        );
+    }
+
+    #[test]
+    fn imports_every_cost_type() {
+        let imported = import_fixture("AllCostsCurrent.xml");
+        let data = &imported.data;
+
+        assert_eq!(data.project.study_period, Some(5));
+        assert!(data.project.dollar_method == DollarMethod::Current);
+        assert_eq!(data.alternatives.len(), 1);
+        assert_eq!(data.alternatives[0].costs.len(), 15);
+        assert_eq!(
+            cost_types(data),
+            [
+                "Capital Investment Cost",
+                "Capital Replacement Cost",
+                "OMR",
+                "OMR",
+                "Energy",
+                "Energy",
+                "Energy",
+                "Energy",
+                "Energy",
+                "Energy",
+                "Water",
+                "Recurring Contract",
+                "Recurring Contract",
+                "Recurring Contract",
+                "Non-Recurring Contract",
+            ]
+        );
+
+        // Water disposal escalation has no equivalent in the current format
+        assert!(imported.warnings.contains(
+            &"Unmapped element Project/Alternatives/Alternative/WaterUsages/WaterUsage/DisposalEscalation was ignored"
+                .to_string()
+        ));
+    }
+
+    #[test]
+    fn imports_ercip_costs() {
+        let data = import_fixture("MilconECIP.xml").data;
+
+        assert_eq!(data.project.study_period, Some(21));
+        assert_eq!(data.alternatives.len(), 2);
+        assert_eq!(cost_types(&data).iter().filter(|kind| *kind == "ERCIP").count(), 2);
+    }
+
+    #[test]
+    fn imports_one_year_study_period() {
+        let data = import_fixture("StudyPeriod1Year.xml").data;
+
+        assert_eq!(data.project.study_period, Some(1));
+        assert_eq!(cost_types(&data), ["Capital Investment Cost", "OMR"]);
+    }
+
+    #[test]
+    fn warns_about_varying_escalation_names() {
+        let imported = import_fixture("fempenergyvaryingesc.xml");
+
+        assert!(imported.warnings.contains(
+            &"Unmapped element Project/Alternatives/Alternative/EnergyUsages/EnergyUsage/Escalation/VaryingEscalation/Name was ignored"
+                .to_string()
+        ));
+    }
+
+    #[test]
+    fn rejects_other_documents() {
+        assert!(import("<Report/>", 2024).is_err());
+        assert!(import("not xml", 2024).is_err());
+    }
+}
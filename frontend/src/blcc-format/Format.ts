@@ -170,7 +170,7 @@ export type EnergyCost = Type<CostTypes.ENERGY> &
         unit: Unit;
         demandCharge?: number;
         rebate?: number;
-        emissions?: number[];
+        emissions?: (number | null)[];
     };
 
 export enum CustomerSector {
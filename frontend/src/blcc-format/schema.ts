@@ -15,6 +15,7 @@ export class ReleaseYear extends Schema.Class<ReleaseYear>("ReleaseYear")({
     year: Schema.Number,
     max: Schema.Number,
     min: Schema.Number,
+    default: Schema.Boolean,
 }) {}
 
 export const decodeReleaseYear = Schema.decodeUnknown(Schema.Array(ReleaseYear));
@@ -49,7 +50,23 @@ export const EscalationRates = Schema.Array(EscalationRateResponse);
 
 export const decodeEscalationRateResponse = Schema.decodeUnknown(EscalationRates);
 
-export const decodeNumberArray = Schema.decodeUnknown(Schema.Array(Schema.Number));
+export class YearValue extends Schema.Class<YearValue>("YearValue")({
+    year: Schema.Number,
+    value: Schema.NullOr(Schema.Number),
+}) {}
+
+export class YearSeries extends Schema.Class<YearSeries>("YearSeries")({
+    values: Schema.Array(YearValue),
+    extrapolated: Schema.optional(Schema.Array(Schema.Number)),
+}) {}
+
+export const decodeYearSeries = Schema.decodeUnknown(YearSeries);
+
+/**
+ * The value of every year in the series, in order. Years without data are kept as `null` so later values stay at the
+ * index of their year.
+ */
+export const yearSeriesValues = (series: YearSeries): (number | null)[] => series.values.map(({ value }) => value);
 
 export class DiscountRatesResponse extends Schema.Class<DiscountRatesResponse>("DiscountRatesResponse")({
     releaseYear: Schema.Number,
@@ -547,7 +547,7 @@ export namespace Model {
                             rate: RATE_MAP[emissionsRateType],
                         };
 
-                        const cache: { [key: string]: number[] } = {};
+                        const cache: { [key: string]: (number | null)[] } = {};
 
                         for (const cost of energyCosts as EnergyCost[]) {
                             if (
@@ -13,15 +13,17 @@ import {
     type Unit,
 } from "blcc-format/Format";
 import type { Collection } from "dexie";
-import { Match } from "effect";
+import { Effect, Match } from "effect";
 import { CostModel } from "model/CostModel";
 import { isEnergyCost, isNonUSLocation, isUSLocation } from "model/Guards";
 import { type LocationModel, Model } from "model/Model";
 import * as O from "optics-ts";
-import { type Observable, Subject, combineLatest, distinctUntilChanged, map, merge, of, switchMap } from "rxjs";
+import { type Observable, Subject, combineLatest, distinctUntilChanged, map, merge, switchMap } from "rxjs";
 import { filter, shareReplay, startWith, tap, withLatestFrom } from "rxjs/operators";
+import { BlccApiService } from "services/BlccApiService";
 import { COAL_KG_CO2E_PER_MEGAJOULE } from "util/UnitConversion";
 import { fuelTypeToRate, index, makeApiRequest } from "util/Util";
+import { BlccRuntime } from "util/runtime";
 import { Var } from "util/var";
 import z from "zod";
 import cost = CostModel.cost;
@@ -38,11 +40,6 @@ type ZipInfoResponse = {
     reedsBa: string;
 };
 
-const EIA_CASE_MAP = {
-    [Case.REF]: "REF",
-    [Case.LOWZTC]: "lowZTC",
-};
-
 const RATE_MAP = {
     [EmissionsRateType.AVERAGE]: "avg",
     [EmissionsRateType.LONG_RUN_MARGINAL]: "lrm",
@@ -202,7 +199,7 @@ export namespace EnergyCostModel {
         .subscribe(([unit, costCollection]) => costCollection.modify({ unit }));
 
     export namespace Emissions {
-        export const emissions$: Observable<number[] | undefined> = combineLatest([
+        export const emissions$: Observable<(number | null)[] | undefined> = combineLatest([
             Location.zipInfo$,
             Model.releaseYear.$,
             Model.studyPeriod.$,
@@ -237,48 +234,56 @@ export namespace EnergyCostModel {
             ghgDataSource: GhgDataSource,
             fuelType: FuelType,
             country: Country | undefined,
-        ): Observable<number[]> {
+        ): Promise<(number | null)[]> {
             const common = {
                 from: releaseYear,
                 to: releaseYear + (studyPeriod ?? 0),
                 releaseYear,
-                case: EIA_CASE_MAP[eiaCase],
+                case: eiaCase,
                 rate: RATE_MAP[rate],
             };
 
             const isUS = country === Country.USA;
             const padd = !isUS ? "PADD 3" : zipInfo.padd;
 
-            return Match.value(fuelType).pipe(
-                Match.when(FuelType.ELECTRICITY, () => {
-                    if (ghgDataSource === GhgDataSource.NIST_NETL)
-                        return makeApiRequest<number[]>("region_case_ba", { ...common, ba: !isUS ? "US" : zipInfo.ba });
-
-                    return makeApiRequest<number[]>("region_case_reeds", {
-                        ...common,
-                        reeds: !isUS ? "US" : zipInfo.reedsBa,
-                    });
+            return BlccRuntime.runPromise(
+                Effect.gen(function* () {
+                    const api = yield* BlccApiService;
+
+                    return yield* Match.value(fuelType).pipe(
+                        Match.when(FuelType.ELECTRICITY, () => {
+                            if (ghgDataSource === GhgDataSource.NIST_NETL)
+                                return api.fetchRegionCaseBa({ ...common, ba: !isUS ? "US" : zipInfo.ba });
+
+                            return api.fetchRegionCaseReeds({
+                                ...common,
+                                reeds: !isUS ? "US" : zipInfo.reedsBa,
+                            });
+                        }),
+                        Match.when(FuelType.NATURAL_GAS, () =>
+                            api.fetchRegionCaseNatGas({
+                                ...common,
+                                technobasin: !isUS ? "US" : zipInfo.technobasin,
+                            }),
+                        ),
+                        Match.whenOr(FuelType.DISTILLATE_OIL, FuelType.RESIDUAL_OIL, () =>
+                            api.fetchRegionCaseOil({
+                                ...common,
+                                padd,
+                            }),
+                        ),
+                        Match.when(FuelType.PROPANE, () =>
+                            api.fetchRegionCasePropaneLng({
+                                ...common,
+                                padd,
+                            }),
+                        ),
+                        Match.when(FuelType.COAL, () =>
+                            Effect.succeed(Array(studyPeriod).fill(COAL_KG_CO2E_PER_MEGAJOULE)),
+                        ),
+                        Match.orElse(() => Effect.succeed(Array(studyPeriod).fill(0))),
+                    );
                 }),
-                Match.when(FuelType.NATURAL_GAS, () =>
-                    makeApiRequest<number[]>("region_natgas", {
-                        ...common,
-                        technobasin: !isUS ? "US" : zipInfo.technobasin,
-                    }),
-                ),
-                Match.whenOr(FuelType.DISTILLATE_OIL, FuelType.RESIDUAL_OIL, () =>
-                    makeApiRequest<number[]>("region_case_oil", {
-                        ...common,
-                        padd,
-                    }),
-                ),
-                Match.when(FuelType.PROPANE, () =>
-                    makeApiRequest<number[]>("region_case_propane_lng", {
-                        ...common,
-                        padd,
-                    }),
-                ),
-                Match.when(FuelType.COAL, () => of(Array(studyPeriod).fill(COAL_KG_CO2E_PER_MEGAJOULE))),
-                Match.orElse(() => of(Array(studyPeriod).fill(0))),
             );
         }
     }
@@ -3,9 +3,10 @@ import type { Case } from "blcc-format/Format";
 import {
     decodeDiscountRatesResponse,
     decodeEscalationRateResponse,
-    decodeNumberArray,
     decodeReleaseYear,
+    decodeYearSeries,
     decodeZipInfoResponse,
+    yearSeriesValues,
 } from "blcc-format/schema";
 import { Console, Data, Effect } from "effect";
 
@@ -48,7 +49,8 @@ export class BlccApiService extends Effect.Service<BlccApiService>()("BlccApiSer
                         () => new FetchError(),
                     ),
                     Effect.andThen(jsonResponse),
-                    Effect.andThen(decodeNumberArray),
+                    Effect.andThen(decodeYearSeries),
+                    Effect.map(yearSeriesValues),
                 );
 
         return {
@@ -151,7 +153,8 @@ export class BlccApiService extends Effect.Service<BlccApiService>()("BlccApiSer
                             () => new FetchError(),
                         ),
                         Effect.andThen(jsonResponse),
-                        Effect.andThen(decodeNumberArray),
+                        Effect.andThen(decodeYearSeries),
+                        Effect.map(yearSeriesValues),
                     );
                 }),
             fetchZipInfo: (zip: number) =>
@@ -129,7 +129,7 @@ function costToBuilders(
     project: Project,
     cost: Cost,
     studyPeriod: number,
-    emissions: readonly number[] | undefined,
+    emissions: readonly (number | null)[] | undefined,
 ): BcnBuilder[] {
     return Match.type<Cost>().pipe(
         Match.when({ type: CostTypes.CAPITAL }, (cost) => capitalCostToBuilder(cost, project, studyPeriod)),
@@ -306,7 +306,7 @@ function energyCostRecurrence(project: Project, cost: EnergyCost) {
 function energyCostToBuilder(
     project: Project,
     cost: EnergyCost,
-    emissions: readonly number[] | undefined,
+    emissions: readonly (number | null)[] | undefined,
 ): BcnBuilder[] {
     const result = [];
 
@@ -368,10 +368,8 @@ function energyCostToBuilder(
     // If unit conversion failed or we have no emissions data, return
     if (convertedUnit === undefined || emissions === undefined) return result;
 
-    const emissionValues =
-        cost.emissions === undefined
-            ? emissions.map((value) => value * convertedUnit)
-            : cost.emissions.map((value) => value * convertedUnit);
+    // Years without emissions data count as no emissions so the following years keep their place
+    const emissionValues = (cost.emissions ?? emissions).map((value) => (value ?? 0) * convertedUnit);
 
     if (emissionValues.length > 0) {
         result.push(